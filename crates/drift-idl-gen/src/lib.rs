@@ -538,12 +538,32 @@ fn generate_idl_types(idl: &Idl) -> String {
         }
     });
 
+    // custom program error codes are assigned by the IDL, avoids hand-pinning the variant range
+    let error_codes = idl.errors.iter().map(|error| {
+        let variant_name = Ident::new(&error.name, proc_macro2::Span::call_site());
+        let code = proc_macro2::Literal::u32_unsuffixed(error.code);
+        quote! {
+            #code => Ok(Self::#variant_name),
+        }
+    });
+
     let error_enum = quote! {
         #[derive(PartialEq)]
         #[error_code]
         pub enum ErrorCode {
             #(#error_variants)*
         }
+
+        impl TryFrom<u32> for ErrorCode {
+            type Error = u32;
+            /// Convert a custom program error `code` to `ErrorCode`, returns `code` if it is unknown
+            fn try_from(code: u32) -> Result<Self, Self::Error> {
+                match code {
+                    #(#error_codes)*
+                    _ => Err(code),
+                }
+            }
+        }
     };
 
     errors_tokens = quote! {
@@ -5,9 +5,15 @@ use dashmap::DashMap;
 use log::debug;
 use solana_sdk::{clock::Slot, commitment_config::CommitmentConfig, pubkey::Pubkey};
 
+#[cfg(feature = "grpc")]
+use crate::grpc::GrpcSubscriber;
 use crate::{
-    types::DataAndSlot, utils::get_ws_url,
-    websocket_account_subscriber::WebsocketAccountSubscriber, SdkResult, UnsubHandle,
+    account_provider::AccountProvider,
+    failover::RpcEndpoints,
+    types::DataAndSlot,
+    utils::get_ws_url,
+    websocket_account_subscriber::{AccountUpdate, WebsocketAccountSubscriber},
+    SdkResult, UnsubHandle,
 };
 
 const LOG_TARGET: &str = "accountmap";
@@ -23,6 +29,13 @@ pub struct AccountMap {
     endpoint: String,
     commitment: CommitmentConfig,
     inner: DashMap<Pubkey, AccountSub<Subscribed>, ahash::RandomState>,
+    /// Ws subscriptions follow the active endpoint, if set
+    failover: Option<Arc<RpcEndpoints>>,
+    /// serves subscriptions in place of Ws, if set
+    provider: Option<Arc<dyn AccountProvider>>,
+    /// serves subscriptions from a gRPC stream, if set
+    #[cfg(feature = "grpc")]
+    grpc: Option<Arc<GrpcSubscriber>>,
 }
 
 impl AccountMap {
@@ -31,8 +44,28 @@ impl AccountMap {
             endpoint,
             commitment,
             inner: Default::default(),
+            failover: None,
+            provider: None,
+            #[cfg(feature = "grpc")]
+            grpc: None,
         }
     }
+    /// Serve subscriptions from `provider` in place of Ws
+    pub fn with_provider(mut self, provider: Arc<dyn AccountProvider>) -> Self {
+        self.provider = Some(provider);
+        self
+    }
+    /// Serve subscriptions from the `grpc` stream in place of Ws
+    #[cfg(feature = "grpc")]
+    pub fn with_grpc(mut self, grpc: Arc<GrpcSubscriber>) -> Self {
+        self.grpc = Some(grpc);
+        self
+    }
+    /// Ws subscriptions follow the active endpoint of `endpoints`
+    pub fn with_failover(mut self, endpoints: Arc<RpcEndpoints>) -> Self {
+        self.failover = Some(endpoints);
+        self
+    }
     /// Subscribe user account
     pub async fn subscribe_account(&self, account: &Pubkey) -> SdkResult<()> {
         if self.inner.contains_key(account) {
@@ -40,7 +73,21 @@ impl AccountMap {
         }
         debug!(target: LOG_TARGET, "subscribing: {account:?}");
 
-        let user = AccountSub::new(&self.endpoint, self.commitment, *account);
+        let mut user = AccountSub::new(&self.endpoint, self.commitment, *account);
+        if let Some(ref endpoints) = self.failover {
+            user.subscription = user.subscription.with_failover(Arc::clone(endpoints));
+        }
+        if let Some(ref provider) = self.provider {
+            self.inner
+                .insert(*account, user.subscribe_provider(provider.as_ref()).await?);
+            return Ok(());
+        }
+        #[cfg(feature = "grpc")]
+        if let Some(ref grpc) = self.grpc {
+            self.inner
+                .insert(*account, user.subscribe_grpc(grpc).await?);
+            return Ok(());
+        }
         let user = user.subscribe().await?;
 
         self.inner.insert(*account, user);
@@ -107,24 +154,71 @@ impl AccountSub<Unsubscribed> {
         let data_and_slot = Arc::new(RwLock::new(AccountSlot::default()));
         let unsub = self
             .subscription
-            .subscribe(Self::SUBSCRIPTION_ID, true, {
-                let data_and_slot = Arc::clone(&data_and_slot);
-                move |update| {
-                    let mut guard = data_and_slot.write().expect("acquired");
-                    guard.raw.clone_from(&update.data);
-                    guard.slot = update.slot;
-                }
-            })
+            .subscribe(
+                Self::SUBSCRIPTION_ID,
+                true,
+                Self::on_update(Arc::clone(&data_and_slot)),
+            )
+            .await?;
+
+        Ok(self.subscribed(data_and_slot, unsub))
+    }
+
+    /// Start the subscription from `provider`
+    async fn subscribe_provider(
+        self,
+        provider: &dyn AccountProvider,
+    ) -> SdkResult<AccountSub<Subscribed>> {
+        let data_and_slot = Arc::new(RwLock::new(AccountSlot::default()));
+        let unsub = provider
+            .watch_account(
+                self.pubkey,
+                Box::new(Self::on_update(Arc::clone(&data_and_slot))),
+            )
             .await?;
 
-        Ok(AccountSub {
+        Ok(self.subscribed(data_and_slot, unsub))
+    }
+
+    /// Start the subscription from the `grpc` stream
+    #[cfg(feature = "grpc")]
+    async fn subscribe_grpc(self, grpc: &GrpcSubscriber) -> SdkResult<AccountSub<Subscribed>> {
+        let data_and_slot = Arc::new(RwLock::new(AccountSlot::default()));
+        let unsub = grpc
+            .watch_account(
+                self.pubkey,
+                true,
+                Self::on_update(Arc::clone(&data_and_slot)),
+            )
+            .await?;
+
+        Ok(self.subscribed(data_and_slot, unsub))
+    }
+
+    /// Return handler writing account updates to `data_and_slot`
+    fn on_update(
+        data_and_slot: Arc<RwLock<AccountSlot>>,
+    ) -> impl Fn(&AccountUpdate) + Send + 'static {
+        move |update| {
+            let mut guard = data_and_slot.write().expect("acquired");
+            guard.raw.clone_from(&update.data);
+            guard.slot = update.slot;
+        }
+    }
+
+    fn subscribed(
+        self,
+        data_and_slot: Arc<RwLock<AccountSlot>>,
+        unsub: UnsubHandle,
+    ) -> AccountSub<Subscribed> {
+        AccountSub {
             pubkey: self.pubkey,
             subscription: self.subscription,
             state: Subscribed {
                 data_and_slot,
                 unsub: Mutex::new(Some(unsub)),
             },
-        })
+        }
     }
 }
 
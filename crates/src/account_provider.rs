@@ -0,0 +1,25 @@
+//! Pluggable source of live account updates
+//!
+//! `AccountMap`, `MarketMap` and `OracleMap` subscribe to accounts via Ws by default.
+//! An `AccountProvider` set with `with_provider` serves their subscriptions instead
+//! e.g. `mock::MockAccounts` for offline tests.
+use async_trait::async_trait;
+use solana_sdk::pubkey::Pubkey;
+
+use crate::{websocket_account_subscriber::AccountUpdate, SdkResult, UnsubHandle};
+
+/// Handler of account updates
+pub type OnAccountUpdate = Box<dyn Fn(&AccountUpdate) + Send + 'static>;
+
+/// A source of live account updates
+#[async_trait]
+pub trait AccountProvider: Send + Sync {
+    /// Call `on_update` with the current value of `pubkey` and any subsequent updates
+    ///
+    /// Returns a handle to end the subscription
+    async fn watch_account(
+        &self,
+        pubkey: Pubkey,
+        on_update: OnAccountUpdate,
+    ) -> SdkResult<UnsubHandle>;
+}
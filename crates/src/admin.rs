@@ -0,0 +1,688 @@
+//! Drift admin ixs
+//!
+//! Useful for creating markets and other fixtures on a local validator.
+//! Requires the `admin` feature.
+//!
+//! ```ignore
+//! let tx = AdminTransactionBuilder::new(admin.pubkey())
+//!     .initialize(usdc_mint)
+//!     .initialize_spot_market(0, usdc_mint, oracle, TOKEN_PROGRAM_ID, spot_params)
+//!     .update_perp_market_status(0, MarketStatus::Active)
+//!     .build();
+//! ```
+use anchor_lang::InstructionData;
+use solana_sdk::{
+    instruction::Instruction,
+    message::{v0, Message, VersionedMessage},
+    pubkey::Pubkey,
+};
+
+use crate::{
+    constants::{
+        self, derive_drift_signer, derive_insurance_fund_vault, derive_perp_market_account,
+        derive_spot_market_account, derive_spot_market_vault, high_leverage_mode_config_account,
+        ids, state_account, PROGRAM_ID,
+    },
+    drift_idl::{self, traits::ToAccountMetas},
+    pyth_pull,
+    types::{accounts, ContractTier, FeeStructure, MarketStatus, OracleGuardRails, OracleSource},
+    utils::encode_name,
+};
+
+/// Composable tx builder for Drift admin ixs
+///
+/// All ixs must be signed by the program's admin (except `initialize`, which sets the admin)
+pub struct AdminTransactionBuilder {
+    /// the admin authority
+    admin: Pubkey,
+    /// ordered list of instructions
+    ixs: Vec<Instruction>,
+    /// use legacy transaction mode
+    legacy: bool,
+}
+
+impl AdminTransactionBuilder {
+    /// Initialize a new `AdminTransactionBuilder` for `admin`
+    pub fn new(admin: Pubkey) -> Self {
+        Self {
+            admin,
+            ixs: Default::default(),
+            legacy: false,
+        }
+    }
+    /// Use legacy tx mode
+    pub fn legacy(mut self) -> Self {
+        self.legacy = true;
+        self
+    }
+    /// Return the ixs built so far
+    pub fn ixs(&self) -> &[Instruction] {
+        self.ixs.as_slice()
+    }
+
+    /// Initialize the drift program state, setting the admin
+    ///
+    /// * `quote_asset_mint` - mint of the quote asset (e.g. USDC)
+    pub fn initialize(self, quote_asset_mint: Pubkey) -> Self {
+        let accounts = accounts::Initialize {
+            admin: self.admin,
+            state: *state_account(),
+            quote_asset_mint,
+            drift_signer: derive_drift_signer(),
+            rent: solana_sdk::sysvar::rent::ID,
+            system_program: solana_sdk::system_program::ID,
+            token_program: constants::TOKEN_PROGRAM_ID,
+        };
+        self.push_ix(accounts, &drift_idl::instructions::Initialize {})
+    }
+
+    /// Initialize a new spot market
+    ///
+    /// * `market_index` - index of the new market, must equal the state's `number_of_spot_markets`
+    /// * `mint` - mint of the market token
+    /// * `oracle` - price oracle of the market token
+    /// * `token_program` - token program of `mint`
+    /// * `params` - initial market config
+    pub fn initialize_spot_market(
+        self,
+        market_index: u16,
+        mint: Pubkey,
+        oracle: Pubkey,
+        token_program: Pubkey,
+        params: drift_idl::instructions::InitializeSpotMarket,
+    ) -> Self {
+        let accounts = accounts::InitializeSpotMarket {
+            spot_market: derive_spot_market_account(market_index),
+            spot_market_mint: mint,
+            spot_market_vault: derive_spot_market_vault(market_index),
+            insurance_fund_vault: derive_insurance_fund_vault(market_index),
+            drift_signer: derive_drift_signer(),
+            state: *state_account(),
+            oracle,
+            admin: self.admin,
+            rent: solana_sdk::sysvar::rent::ID,
+            system_program: solana_sdk::system_program::ID,
+            token_program,
+        };
+        self.push_ix(accounts, &params)
+    }
+
+    /// Initialize a new perp market
+    ///
+    /// * `oracle` - price oracle of the market
+    /// * `params` - initial market config, `params.market_index` must equal the state's `number_of_markets`
+    pub fn initialize_perp_market(
+        self,
+        oracle: Pubkey,
+        params: drift_idl::instructions::InitializePerpMarket,
+    ) -> Self {
+        let accounts = accounts::InitializePerpMarket {
+            admin: self.admin,
+            state: *state_account(),
+            perp_market: derive_perp_market_account(params.market_index),
+            oracle,
+            rent: solana_sdk::sysvar::rent::ID,
+            system_program: solana_sdk::system_program::ID,
+        };
+        self.push_ix(accounts, &params)
+    }
+
+    /// Initialize the drift price feed account of a Pyth pull oracle
+    pub fn initialize_pyth_pull_oracle(self, feed_id: pyth_pull::FeedId) -> Self {
+        let accounts = accounts::InitializePythPullOracle {
+            admin: self.admin,
+            pyth_solana_receiver: ids::pyth_solana_receiver_program::ID,
+            price_feed: pyth_pull::derive_pyth_pull_oracle(&feed_id),
+            system_program: solana_sdk::system_program::ID,
+            state: *state_account(),
+        };
+        self.push_ix(
+            accounts,
+            &drift_idl::instructions::InitializePythPullOracle { feed_id },
+        )
+    }
+
+    /// Initialize the high leverage mode config allowing up to `max_users`
+    pub fn initialize_high_leverage_mode_config(self, max_users: u32) -> Self {
+        let accounts = accounts::InitializeHighLeverageModeConfig {
+            admin: self.admin,
+            high_leverage_mode_config: *high_leverage_mode_config_account(),
+            state: *state_account(),
+            rent: solana_sdk::sysvar::rent::ID,
+            system_program: solana_sdk::system_program::ID,
+        };
+        self.push_ix(
+            accounts,
+            &drift_idl::instructions::InitializeHighLeverageModeConfig { max_users },
+        )
+    }
+
+    /// Update the high leverage mode config
+    pub fn update_high_leverage_mode_config(self, max_users: u32, reduce_only: bool) -> Self {
+        let accounts = accounts::UpdateHighLeverageModeConfig {
+            admin: self.admin,
+            high_leverage_mode_config: *high_leverage_mode_config_account(),
+            state: *state_account(),
+        };
+        self.push_ix(
+            accounts,
+            &drift_idl::instructions::UpdateHighLeverageModeConfig {
+                max_users,
+                reduce_only,
+            },
+        )
+    }
+
+    /// Transfer the admin role to `new_admin`
+    pub fn update_admin(self, new_admin: Pubkey) -> Self {
+        let accounts = accounts::UpdateAdmin {
+            admin: self.admin,
+            state: *state_account(),
+        };
+        self.push_ix(
+            accounts,
+            &drift_idl::instructions::UpdateAdmin { admin: new_admin },
+        )
+    }
+
+    /// Update the exchange status (bitflags of paused operations)
+    pub fn update_exchange_status(self, exchange_status: u8) -> Self {
+        let accounts = accounts::UpdateExchangeStatus {
+            admin: self.admin,
+            state: *state_account(),
+        };
+        self.push_ix(
+            accounts,
+            &drift_idl::instructions::UpdateExchangeStatus { exchange_status },
+        )
+    }
+
+    /// Update the exchange oracle guard rails
+    pub fn update_oracle_guard_rails(self, oracle_guard_rails: OracleGuardRails) -> Self {
+        let accounts = accounts::UpdateOracleGuardRails {
+            admin: self.admin,
+            state: *state_account(),
+        };
+        self.push_ix(
+            accounts,
+            &drift_idl::instructions::UpdateOracleGuardRails { oracle_guard_rails },
+        )
+    }
+
+    /// Update the perp fee structure
+    pub fn update_perp_fee_structure(self, fee_structure: FeeStructure) -> Self {
+        let accounts = accounts::UpdatePerpFeeStructure {
+            admin: self.admin,
+            state: *state_account(),
+        };
+        self.push_ix(
+            accounts,
+            &drift_idl::instructions::UpdatePerpFeeStructure { fee_structure },
+        )
+    }
+
+    /// Update the spot fee structure
+    pub fn update_spot_fee_structure(self, fee_structure: FeeStructure) -> Self {
+        let accounts = accounts::UpdateSpotFeeStructure {
+            admin: self.admin,
+            state: *state_account(),
+        };
+        self.push_ix(
+            accounts,
+            &drift_idl::instructions::UpdateSpotFeeStructure { fee_structure },
+        )
+    }
+
+    /// Update the max. number of sub-accounts (in 100s, 0 = unlimited)
+    pub fn update_state_max_number_of_sub_accounts(self, max_number_of_sub_accounts: u16) -> Self {
+        let accounts = accounts::UpdateStateMaxNumberOfSubAccounts {
+            admin: self.admin,
+            state: *state_account(),
+        };
+        self.push_ix(
+            accounts,
+            &drift_idl::instructions::UpdateStateMaxNumberOfSubAccounts {
+                max_number_of_sub_accounts,
+            },
+        )
+    }
+
+    /// Update the min. perp auction duration (slots)
+    pub fn update_perp_auction_duration(self, min_perp_auction_duration: u8) -> Self {
+        let accounts = accounts::UpdatePerpAuctionDuration {
+            admin: self.admin,
+            state: *state_account(),
+        };
+        self.push_ix(
+            accounts,
+            &drift_idl::instructions::UpdatePerpAuctionDuration {
+                min_perp_auction_duration,
+            },
+        )
+    }
+
+    /// Update the default spot auction duration (slots)
+    pub fn update_spot_auction_duration(self, default_spot_auction_duration: u8) -> Self {
+        let accounts = accounts::UpdateSpotAuctionDuration {
+            admin: self.admin,
+            state: *state_account(),
+        };
+        self.push_ix(
+            accounts,
+            &drift_idl::instructions::UpdateSpotAuctionDuration {
+                default_spot_auction_duration,
+            },
+        )
+    }
+
+    /// Update the status of a perp market
+    pub fn update_perp_market_status(self, market_index: u16, status: MarketStatus) -> Self {
+        let accounts = accounts::UpdatePerpMarketStatus {
+            admin: self.admin,
+            state: *state_account(),
+            perp_market: derive_perp_market_account(market_index),
+        };
+        self.push_ix(
+            accounts,
+            &drift_idl::instructions::UpdatePerpMarketStatus { status },
+        )
+    }
+
+    /// Update the oracle of a perp market
+    pub fn update_perp_market_oracle(
+        self,
+        market_index: u16,
+        oracle: Pubkey,
+        oracle_source: OracleSource,
+    ) -> Self {
+        let accounts = accounts::UpdatePerpMarketOracle {
+            state: *state_account(),
+            perp_market: derive_perp_market_account(market_index),
+            oracle,
+            admin: self.admin,
+        };
+        self.push_ix(
+            accounts,
+            &drift_idl::instructions::UpdatePerpMarketOracle {
+                oracle,
+                oracle_source,
+            },
+        )
+    }
+
+    /// Update the margin ratios of a perp market (MARGIN_PRECISION)
+    pub fn update_perp_market_margin_ratio(
+        self,
+        market_index: u16,
+        margin_ratio_initial: u32,
+        margin_ratio_maintenance: u32,
+    ) -> Self {
+        let accounts = accounts::UpdatePerpMarketMarginRatio {
+            admin: self.admin,
+            state: *state_account(),
+            perp_market: derive_perp_market_account(market_index),
+        };
+        self.push_ix(
+            accounts,
+            &drift_idl::instructions::UpdatePerpMarketMarginRatio {
+                margin_ratio_initial,
+                margin_ratio_maintenance,
+            },
+        )
+    }
+
+    /// Update the high leverage mode margin ratios of a perp market (MARGIN_PRECISION)
+    pub fn update_perp_market_high_leverage_margin_ratio(
+        self,
+        market_index: u16,
+        margin_ratio_initial: u16,
+        margin_ratio_maintenance: u16,
+    ) -> Self {
+        let accounts = accounts::UpdatePerpMarketHighLeverageMarginRatio {
+            admin: self.admin,
+            state: *state_account(),
+            perp_market: derive_perp_market_account(market_index),
+        };
+        self.push_ix(
+            accounts,
+            &drift_idl::instructions::UpdatePerpMarketHighLeverageMarginRatio {
+                margin_ratio_initial,
+                margin_ratio_maintenance,
+            },
+        )
+    }
+
+    /// Update the order step and tick sizes of a perp market
+    pub fn update_perp_market_step_size_and_tick_size(
+        self,
+        market_index: u16,
+        step_size: u64,
+        tick_size: u64,
+    ) -> Self {
+        let accounts = accounts::UpdatePerpMarketStepSizeAndTickSize {
+            admin: self.admin,
+            state: *state_account(),
+            perp_market: derive_perp_market_account(market_index),
+        };
+        self.push_ix(
+            accounts,
+            &drift_idl::instructions::UpdatePerpMarketStepSizeAndTickSize {
+                step_size,
+                tick_size,
+            },
+        )
+    }
+
+    /// Update the min. order size of a perp market (BASE_PRECISION)
+    pub fn update_perp_market_min_order_size(self, market_index: u16, order_size: u64) -> Self {
+        let accounts = accounts::UpdatePerpMarketMinOrderSize {
+            admin: self.admin,
+            state: *state_account(),
+            perp_market: derive_perp_market_account(market_index),
+        };
+        self.push_ix(
+            accounts,
+            &drift_idl::instructions::UpdatePerpMarketMinOrderSize { order_size },
+        )
+    }
+
+    /// Update the AMM base spread of a perp market
+    pub fn update_perp_market_base_spread(self, market_index: u16, base_spread: u32) -> Self {
+        let accounts = accounts::UpdatePerpMarketBaseSpread {
+            admin: self.admin,
+            state: *state_account(),
+            perp_market: derive_perp_market_account(market_index),
+        };
+        self.push_ix(
+            accounts,
+            &drift_idl::instructions::UpdatePerpMarketBaseSpread { base_spread },
+        )
+    }
+
+    /// Update the AMM max. spread of a perp market
+    pub fn update_perp_market_max_spread(self, market_index: u16, max_spread: u32) -> Self {
+        let accounts = accounts::UpdatePerpMarketMaxSpread {
+            admin: self.admin,
+            state: *state_account(),
+            perp_market: derive_perp_market_account(market_index),
+        };
+        self.push_ix(
+            accounts,
+            &drift_idl::instructions::UpdatePerpMarketMaxSpread { max_spread },
+        )
+    }
+
+    /// Update the max. open interest of a perp market (BASE_PRECISION)
+    pub fn update_perp_market_max_open_interest(
+        self,
+        market_index: u16,
+        max_open_interest: u128,
+    ) -> Self {
+        let accounts = accounts::UpdatePerpMarketMaxOpenInterest {
+            admin: self.admin,
+            state: *state_account(),
+            perp_market: derive_perp_market_account(market_index),
+        };
+        self.push_ix(
+            accounts,
+            &drift_idl::instructions::UpdatePerpMarketMaxOpenInterest { max_open_interest },
+        )
+    }
+
+    /// Update the contract tier of a perp market
+    pub fn update_perp_market_contract_tier(
+        self,
+        market_index: u16,
+        contract_tier: ContractTier,
+    ) -> Self {
+        let accounts = accounts::UpdatePerpMarketContractTier {
+            admin: self.admin,
+            state: *state_account(),
+            perp_market: derive_perp_market_account(market_index),
+        };
+        self.push_ix(
+            accounts,
+            &drift_idl::instructions::UpdatePerpMarketContractTier { contract_tier },
+        )
+    }
+
+    /// Update the IMF factors of a perp market
+    pub fn update_perp_market_imf_factor(
+        self,
+        market_index: u16,
+        imf_factor: u32,
+        unrealized_pnl_imf_factor: u32,
+    ) -> Self {
+        let accounts = accounts::UpdatePerpMarketImfFactor {
+            admin: self.admin,
+            state: *state_account(),
+            perp_market: derive_perp_market_account(market_index),
+        };
+        self.push_ix(
+            accounts,
+            &drift_idl::instructions::UpdatePerpMarketImfFactor {
+                imf_factor,
+                unrealized_pnl_imf_factor,
+            },
+        )
+    }
+
+    /// Update the liquidation fees of a perp market
+    pub fn update_perp_market_liquidation_fee(
+        self,
+        market_index: u16,
+        liquidator_fee: u32,
+        if_liquidation_fee: u32,
+    ) -> Self {
+        let accounts = accounts::UpdatePerpMarketLiquidationFee {
+            admin: self.admin,
+            state: *state_account(),
+            perp_market: derive_perp_market_account(market_index),
+        };
+        self.push_ix(
+            accounts,
+            &drift_idl::instructions::UpdatePerpMarketLiquidationFee {
+                liquidator_fee,
+                if_liquidation_fee,
+            },
+        )
+    }
+
+    /// Update the funding period of a perp market (seconds)
+    pub fn update_perp_market_funding_period(self, market_index: u16, funding_period: i64) -> Self {
+        let accounts = accounts::UpdatePerpMarketFundingPeriod {
+            admin: self.admin,
+            state: *state_account(),
+            perp_market: derive_perp_market_account(market_index),
+        };
+        self.push_ix(
+            accounts,
+            &drift_idl::instructions::UpdatePerpMarketFundingPeriod { funding_period },
+        )
+    }
+
+    /// Set the expiry of a perp market (unix timestamp)
+    pub fn update_perp_market_expiry(self, market_index: u16, expiry_ts: i64) -> Self {
+        let accounts = accounts::UpdatePerpMarketExpiry {
+            admin: self.admin,
+            state: *state_account(),
+            perp_market: derive_perp_market_account(market_index),
+        };
+        self.push_ix(
+            accounts,
+            &drift_idl::instructions::UpdatePerpMarketExpiry { expiry_ts },
+        )
+    }
+
+    /// Update the name of a perp market
+    pub fn update_perp_market_name(self, market_index: u16, name: &str) -> Self {
+        let accounts = accounts::UpdatePerpMarketName {
+            admin: self.admin,
+            state: *state_account(),
+            perp_market: derive_perp_market_account(market_index),
+        };
+        self.push_ix(
+            accounts,
+            &drift_idl::instructions::UpdatePerpMarketName {
+                name: encode_name(name),
+            },
+        )
+    }
+
+    /// Update the status of a spot market
+    pub fn update_spot_market_status(self, market_index: u16, status: MarketStatus) -> Self {
+        let accounts = accounts::UpdateSpotMarketStatus {
+            admin: self.admin,
+            state: *state_account(),
+            spot_market: derive_spot_market_account(market_index),
+        };
+        self.push_ix(
+            accounts,
+            &drift_idl::instructions::UpdateSpotMarketStatus { status },
+        )
+    }
+
+    /// Update the oracle of a spot market
+    pub fn update_spot_market_oracle(
+        self,
+        market_index: u16,
+        oracle: Pubkey,
+        oracle_source: OracleSource,
+    ) -> Self {
+        let accounts = accounts::UpdateSpotMarketOracle {
+            admin: self.admin,
+            state: *state_account(),
+            spot_market: derive_spot_market_account(market_index),
+            oracle,
+        };
+        self.push_ix(
+            accounts,
+            &drift_idl::instructions::UpdateSpotMarketOracle {
+                oracle,
+                oracle_source,
+            },
+        )
+    }
+
+    /// Update the margin weights of a spot market (SPOT_WEIGHT_PRECISION)
+    pub fn update_spot_market_margin_weights(
+        self,
+        market_index: u16,
+        params: drift_idl::instructions::UpdateSpotMarketMarginWeights,
+    ) -> Self {
+        let accounts = accounts::UpdateSpotMarketMarginWeights {
+            admin: self.admin,
+            state: *state_account(),
+            spot_market: derive_spot_market_account(market_index),
+        };
+        self.push_ix(accounts, &params)
+    }
+
+    /// Update the borrow rate curve of a spot market
+    pub fn update_spot_market_borrow_rate(
+        self,
+        market_index: u16,
+        params: drift_idl::instructions::UpdateSpotMarketBorrowRate,
+    ) -> Self {
+        let accounts = accounts::UpdateSpotMarketBorrowRate {
+            admin: self.admin,
+            state: *state_account(),
+            spot_market: derive_spot_market_account(market_index),
+        };
+        self.push_ix(accounts, &params)
+    }
+
+    /// Update the max. token deposits of a spot market (token precision, 0 = unlimited)
+    pub fn update_spot_market_max_token_deposits(
+        self,
+        market_index: u16,
+        max_token_deposits: u64,
+    ) -> Self {
+        let accounts = accounts::UpdateSpotMarketMaxTokenDeposits {
+            admin: self.admin,
+            state: *state_account(),
+            spot_market: derive_spot_market_account(market_index),
+        };
+        self.push_ix(
+            accounts,
+            &drift_idl::instructions::UpdateSpotMarketMaxTokenDeposits { max_token_deposits },
+        )
+    }
+
+    /// Update the order step and tick sizes of a spot market
+    pub fn update_spot_market_step_size_and_tick_size(
+        self,
+        market_index: u16,
+        step_size: u64,
+        tick_size: u64,
+    ) -> Self {
+        let accounts = accounts::UpdateSpotMarketStepSizeAndTickSize {
+            admin: self.admin,
+            state: *state_account(),
+            spot_market: derive_spot_market_account(market_index),
+        };
+        self.push_ix(
+            accounts,
+            &drift_idl::instructions::UpdateSpotMarketStepSizeAndTickSize {
+                step_size,
+                tick_size,
+            },
+        )
+    }
+
+    /// Build the transaction message ready for signing and sending
+    pub fn build(self) -> VersionedMessage {
+        if self.legacy {
+            let message = Message::new(self.ixs.as_ref(), Some(&self.admin));
+            VersionedMessage::Legacy(message)
+        } else {
+            let message =
+                v0::Message::try_compile(&self.admin, self.ixs.as_slice(), &[], Default::default())
+                    .expect("ok");
+            VersionedMessage::V0(message)
+        }
+    }
+
+    fn push_ix(mut self, accounts: impl ToAccountMetas, data: &impl InstructionData) -> Self {
+        self.ixs.push(Instruction {
+            program_id: PROGRAM_ID,
+            accounts: accounts.to_account_metas(),
+            data: InstructionData::data(data),
+        });
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn admin_ixs() {
+        let admin = Pubkey::new_unique();
+        let oracle = Pubkey::new_unique();
+        let builder = AdminTransactionBuilder::new(admin)
+            .initialize_perp_market(
+                oracle,
+                drift_idl::instructions::InitializePerpMarket {
+                    market_index: 1,
+                    ..Default::default()
+                },
+            )
+            .update_perp_market_status(1, MarketStatus::Active);
+
+        let ixs = builder.ixs();
+        assert_eq!(ixs.len(), 2);
+        assert_eq!(ixs[0].accounts[0].pubkey, admin);
+        assert!(ixs[0].accounts[0].is_signer);
+        assert_eq!(ixs[0].accounts[2].pubkey, derive_perp_market_account(1));
+        assert_eq!(ixs[0].accounts[3].pubkey, oracle);
+        assert_eq!(
+            ixs[1].data,
+            InstructionData::data(&drift_idl::instructions::UpdatePerpMarketStatus {
+                status: MarketStatus::Active
+            })
+        );
+        assert!(ixs[1].accounts[2].is_writable);
+    }
+}
@@ -9,7 +9,7 @@ use solana_client::nonblocking::rpc_client::RpcClient;
 use solana_sdk::hash::Hash;
 use tokio::sync::oneshot;
 
-use crate::UnsubHandle;
+use crate::{failover::RpcEndpoints, UnsubHandle};
 
 /// Subscribes to latest network blockhashes
 pub struct BlockhashSubscriber {
@@ -17,6 +17,8 @@ pub struct BlockhashSubscriber {
     last_twenty_hashes: Arc<RwLock<VecDeque<Hash>>>,
     rpc_client: Arc<RpcClient>,
     unsub: Mutex<Option<UnsubHandle>>,
+    /// hashes are discarded when the active endpoint changes, if set
+    failover: Option<Arc<RpcEndpoints>>,
 }
 
 impl BlockhashSubscriber {
@@ -28,9 +30,19 @@ impl BlockhashSubscriber {
             rpc_client: Arc::clone(&rpc_client),
             refresh_frequency,
             unsub: Mutex::default(),
+            failover: None,
         }
     }
 
+    /// Poll blockhashes from the active endpoint of `endpoints`
+    ///
+    /// Hashes from the previous endpoint are discarded on switching, they may be unknown to the new endpoint
+    pub fn with_failover(mut self, endpoints: Arc<RpcEndpoints>) -> Self {
+        self.rpc_client = Arc::new(endpoints.rpc_client(self.rpc_client.commitment()));
+        self.failover = Some(endpoints);
+        self
+    }
+
     /// Start the blockhash subscriber task
     pub fn subscribe(&self) {
         let (unsub_tx, mut unsub_rx) = oneshot::channel();
@@ -46,6 +58,7 @@ impl BlockhashSubscriber {
             let rpc_client = Arc::clone(&self.rpc_client);
             let last_twenty_hashes = Arc::clone(&self.last_twenty_hashes);
             let mut refresh = tokio::time::interval(self.refresh_frequency);
+            let mut active_endpoint = self.failover.as_ref().map(|f| f.watch_active());
 
             async move {
                 loop {
@@ -53,6 +66,13 @@ impl BlockhashSubscriber {
                     match rpc_client.get_latest_blockhash().await {
                         Ok(blockhash) => {
                             let mut hashes = last_twenty_hashes.write().expect("acquired");
+                            if let Some(ref mut active) = active_endpoint {
+                                if active.has_changed().unwrap_or_default() {
+                                    warn!("endpoint changed, discarding blockhashes");
+                                    active.mark_unchanged();
+                                    hashes.clear();
+                                }
+                            }
                             hashes.push_back(blockhash);
                             if hashes.len() > 20 {
                                 let _ = hashes.pop_front();
@@ -136,6 +156,7 @@ mod tests {
             unsub: Mutex::default(),
             rpc_client: Arc::new(mock_rpc),
             refresh_frequency: Duration::from_secs(4),
+            failover: None,
         };
 
         // valid hash is oldest (most finalized)
@@ -156,4 +177,72 @@ mod tests {
         // oldest hash updated as buffer updates
         assert!(blockhash_subscriber.get_valid_blockhash().unwrap() != oldest_block_hash);
     }
+    #[tokio::test]
+    async fn blockhashes_discarded_on_endpoint_change() {
+        let mut response_mocks = Mocks::default();
+        let latest_block_hash = Hash::new_unique();
+        response_mocks.insert(
+            RpcRequest::GetLatestBlockhash,
+            json!({
+                "context": {
+                    "slot": 12345,
+                },
+                "value": {
+                    "blockhash": latest_block_hash.to_string(),
+                    "lastValidBlockHeight": 1,
+                }
+            }),
+        );
+        let mock_rpc = RpcClient::new_mock_with_mocks(
+            "https://api.mainnet-beta.solana.com".into(),
+            response_mocks,
+        );
+        let endpoints = Arc::new(
+            RpcEndpoints::new(vec![
+                (
+                    "https://primary.example.com".into(),
+                    "wss://primary.example.com".into(),
+                ),
+                (
+                    "https://backup.example.com".into(),
+                    "wss://backup.example.com".into(),
+                ),
+            ])
+            .unwrap(),
+        );
+
+        let blockhash_subscriber = BlockhashSubscriber {
+            last_twenty_hashes: Arc::new(RwLock::new(VecDeque::from_iter(
+                std::iter::repeat_with(Hash::new_unique).take(5),
+            ))),
+            unsub: Mutex::default(),
+            rpc_client: Arc::new(mock_rpc),
+            refresh_frequency: Duration::from_millis(200),
+            failover: Some(Arc::clone(&endpoints)),
+        };
+        blockhash_subscriber.subscribe();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(
+            blockhash_subscriber
+                .last_twenty_hashes
+                .read()
+                .unwrap()
+                .len(),
+            6
+        );
+
+        endpoints.set_active(1);
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        assert!(blockhash_subscriber
+            .last_twenty_hashes
+            .read()
+            .unwrap()
+            .iter()
+            .all(|h| *h == latest_block_hash));
+        assert_eq!(
+            blockhash_subscriber.get_valid_blockhash().unwrap(),
+            latest_block_hash
+        );
+        blockhash_subscriber.unsubscribe();
+    }
 }
@@ -0,0 +1,296 @@
+//! Bundle submission to a block engine
+//!
+//! Bundles are groups of signed txs executed atomically and in order, e.g. cancel + place orders.
+//! The block engine is paid via a tip transfer, see `TransactionBuilder::with_tip`.
+//!
+//! ```ignore
+//! let bundle_sender = BundleSender::new(RpcClient::new(block_engine_url.into()));
+//! let tip_account = bundle_sender.get_tip_accounts().await?[0];
+//! let cancel_tx = client.init_tx(&sub_account, false).await?.cancel_all_orders().build();
+//! let place_tx = client
+//!     .init_tx(&sub_account, false)
+//!     .await?
+//!     .place_orders(orders)
+//!     .with_tip(&tip_account, 10_000)
+//!     .build();
+//! let bundle_id = client
+//!     .sign_and_send_bundle(&bundle_sender, vec![cancel_tx, place_tx])
+//!     .await?;
+//! let status = bundle_sender.confirm_bundle(&bundle_id).await?;
+//! ```
+use std::time::{Duration, Instant};
+
+use base64::Engine;
+use log::debug;
+use serde::Deserialize;
+use serde_json::json;
+use solana_client::{
+    nonblocking::rpc_client::RpcClient, rpc_request::RpcRequest, rpc_response::Response,
+};
+use solana_sdk::{clock::Slot, pubkey::Pubkey, transaction::VersionedTransaction};
+
+use crate::types::{SdkError, SdkResult};
+
+const LOG_TARGET: &str = "bundle";
+
+/// Max. number of txs in a bundle
+pub const MAX_BUNDLE_SIZE: usize = 5;
+
+/// Status of a submitted bundle
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum BundleStatus {
+    /// bundle is not known to the block engine or was dropped
+    Invalid,
+    /// bundle has not landed yet
+    Pending,
+    /// bundle failed and will not land
+    Failed,
+    /// bundle landed at `slot`
+    Landed { slot: Slot },
+}
+
+impl BundleStatus {
+    /// True if the bundle status will not change any further
+    pub fn is_final(&self) -> bool {
+        !matches!(self, Self::Pending)
+    }
+}
+
+#[derive(Deserialize)]
+enum InflightStatus {
+    Invalid,
+    Pending,
+    Failed,
+    Landed,
+}
+
+#[derive(Deserialize)]
+struct InflightBundleStatus {
+    status: InflightStatus,
+    landed_slot: Option<Slot>,
+}
+
+/// Submits bundles to a block engine JSON-RPC endpoint
+pub struct BundleSender {
+    /// block engine RPC client
+    rpc_client: RpcClient,
+    /// max. number of txs per bundle
+    max_txs: usize,
+    /// interval to poll bundle status at
+    poll_interval: Duration,
+    /// max. time to wait for a bundle to land
+    timeout: Duration,
+}
+
+impl BundleSender {
+    /// Create a new `BundleSender`
+    ///
+    /// * `rpc_client` - RPC client for the block engine endpoint e.g. `https://mainnet.block-engine.jito.wtf/api/v1/bundles`
+    pub fn new(rpc_client: RpcClient) -> Self {
+        Self {
+            rpc_client,
+            max_txs: MAX_BUNDLE_SIZE,
+            poll_interval: Duration::from_millis(500),
+            timeout: Duration::from_secs(30),
+        }
+    }
+
+    /// Set the max. number of txs per bundle
+    pub fn with_max_txs(mut self, max_txs: usize) -> Self {
+        self.max_txs = max_txs;
+        self
+    }
+
+    /// Set the bundle status polling interval and timeout
+    pub fn with_polling(mut self, poll_interval: Duration, timeout: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self.timeout = timeout;
+        self
+    }
+
+    /// Return the accounts accepted by the block engine for tips
+    pub async fn get_tip_accounts(&self) -> SdkResult<Vec<Pubkey>> {
+        let accounts: Vec<String> = self
+            .rpc_client
+            .send(
+                RpcRequest::Custom {
+                    method: "getTipAccounts",
+                },
+                json!([]),
+            )
+            .await?;
+
+        accounts
+            .iter()
+            .map(|a| a.parse().map_err(|_| SdkError::InvalidBase58))
+            .collect()
+    }
+
+    /// Submit signed `txs` as a bundle
+    ///
+    /// Returns the bundle id
+    pub async fn send_bundle(&self, txs: &[VersionedTransaction]) -> SdkResult<String> {
+        if txs.is_empty() || txs.len() > self.max_txs {
+            return Err(SdkError::InvalidBundleSize(txs.len()));
+        }
+
+        let encoded_txs = txs
+            .iter()
+            .map(|tx| {
+                bincode::serialize(tx)
+                    .map(|tx| base64::engine::general_purpose::STANDARD.encode(tx))
+                    .map_err(|err| SdkError::Bundle(err.to_string()))
+            })
+            .collect::<SdkResult<Vec<String>>>()?;
+
+        let bundle_id: String = self
+            .rpc_client
+            .send(
+                RpcRequest::Custom {
+                    method: "sendBundle",
+                },
+                json!([encoded_txs, { "encoding": "base64" }]),
+            )
+            .await?;
+        debug!(target: LOG_TARGET, "sent bundle: {bundle_id}");
+
+        Ok(bundle_id)
+    }
+
+    /// Query the status of bundle `bundle_id`
+    pub async fn get_bundle_status(&self, bundle_id: &str) -> SdkResult<BundleStatus> {
+        let response: Response<Vec<InflightBundleStatus>> = self
+            .rpc_client
+            .send(
+                RpcRequest::Custom {
+                    method: "getInflightBundleStatuses",
+                },
+                json!([[bundle_id]]),
+            )
+            .await?;
+
+        let status = match response.value.into_iter().next() {
+            Some(InflightBundleStatus {
+                status: InflightStatus::Landed,
+                landed_slot,
+            }) => BundleStatus::Landed {
+                slot: landed_slot.unwrap_or(response.context.slot),
+            },
+            Some(InflightBundleStatus {
+                status: InflightStatus::Pending,
+                ..
+            }) => BundleStatus::Pending,
+            Some(InflightBundleStatus {
+                status: InflightStatus::Failed,
+                ..
+            }) => BundleStatus::Failed,
+            Some(InflightBundleStatus {
+                status: InflightStatus::Invalid,
+                ..
+            })
+            | None => BundleStatus::Invalid,
+        };
+
+        Ok(status)
+    }
+
+    /// Poll the status of bundle `bundle_id` until final or timed out
+    ///
+    /// Returns the last known bundle status
+    pub async fn confirm_bundle(&self, bundle_id: &str) -> SdkResult<BundleStatus> {
+        let start = Instant::now();
+        loop {
+            let status = self.get_bundle_status(bundle_id).await?;
+            if status.is_final() || start.elapsed() >= self.timeout {
+                debug!(target: LOG_TARGET, "bundle: {bundle_id}, status: {status:?}");
+                return Ok(status);
+            }
+            tokio::time::sleep(self.poll_interval).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use solana_client::rpc_client::Mocks;
+    use solana_sdk::{message::Message, signature::Keypair, signer::Signer};
+
+    use super::*;
+
+    fn signed_tx(keypair: &Keypair) -> VersionedTransaction {
+        let message = Message::new(&[], Some(&keypair.pubkey()));
+        VersionedTransaction::try_new(
+            solana_sdk::message::VersionedMessage::Legacy(message),
+            &[keypair],
+        )
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn send_and_confirm_bundle() {
+        let tip_account = Pubkey::new_unique();
+        let mut mocks = Mocks::default();
+        mocks.insert(
+            RpcRequest::Custom {
+                method: "getTipAccounts",
+            },
+            json!([tip_account.to_string()]),
+        );
+        mocks.insert(
+            RpcRequest::Custom {
+                method: "sendBundle",
+            },
+            json!("bundle-id"),
+        );
+        mocks.insert(
+            RpcRequest::Custom {
+                method: "getInflightBundleStatuses",
+            },
+            json!({
+                "context": { "slot": 101 },
+                "value": [{
+                    "bundle_id": "bundle-id",
+                    "status": "Landed",
+                    "landed_slot": 100,
+                }],
+            }),
+        );
+        let bundle_sender =
+            BundleSender::new(RpcClient::new_mock_with_mocks("succeeds".into(), mocks))
+                .with_polling(Duration::from_millis(10), Duration::from_millis(100));
+
+        assert_eq!(
+            bundle_sender.get_tip_accounts().await.unwrap(),
+            vec![tip_account]
+        );
+
+        let keypair = Keypair::new();
+        let bundle_id = bundle_sender
+            .send_bundle(&[signed_tx(&keypair), signed_tx(&keypair)])
+            .await
+            .unwrap();
+        assert_eq!(bundle_id, "bundle-id");
+        assert_eq!(
+            bundle_sender.confirm_bundle(&bundle_id).await.unwrap(),
+            BundleStatus::Landed { slot: 100 }
+        );
+    }
+
+    #[tokio::test]
+    async fn send_bundle_size() {
+        let bundle_sender =
+            BundleSender::new(RpcClient::new_mock("succeeds".into())).with_max_txs(1);
+        let keypair = Keypair::new();
+
+        assert!(matches!(
+            bundle_sender.send_bundle(&[]).await,
+            Err(SdkError::InvalidBundleSize(0))
+        ));
+        assert!(matches!(
+            bundle_sender
+                .send_bundle(&[signed_tx(&keypair), signed_tx(&keypair)])
+                .await,
+            Err(SdkError::InvalidBundleSize(2))
+        ));
+    }
+}
@@ -0,0 +1,210 @@
+//! Compute unit estimation
+//!
+//! Txs can be simulated to find the compute units consumed, the tx compute unit limit is then
+//! set to the simulated units plus some safety margin, see `DriftClient::with_estimated_compute_units`.
+//!
+//! ```ignore
+//! let tx = client
+//!     .init_tx(&sub_account, false)
+//!     .await?
+//!     .with_priority_fee(1_000, None)
+//!     .place_orders(orders)
+//!     .build();
+//! let tx = client
+//!     .with_estimated_compute_units(tx, ComputeUnitMargin::default())
+//!     .await?;
+//! client.sign_and_send(tx).await?;
+//! ```
+use solana_sdk::{
+    compute_budget::{self, ComputeBudgetInstruction},
+    instruction::CompiledInstruction,
+    message::VersionedMessage,
+    pubkey::Pubkey,
+    system_instruction::SystemInstruction,
+    system_program,
+};
+
+/// Max. compute units a tx may request
+pub const MAX_COMPUTE_UNIT_LIMIT: u32 = 1_400_000;
+
+/// Safety margin applied to simulated compute units
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ComputeUnitMargin {
+    /// margin as a percentage of the simulated units e.g. 10 => +10%
+    pub percent: u32,
+    /// min. margin (compute units)
+    pub min_units: u32,
+}
+
+impl Default for ComputeUnitMargin {
+    fn default() -> Self {
+        Self {
+            percent: 10,
+            min_units: 5_000,
+        }
+    }
+}
+
+impl ComputeUnitMargin {
+    /// Create a new margin of `percent` with at least `min_units`
+    pub fn new(percent: u32, min_units: u32) -> Self {
+        Self { percent, min_units }
+    }
+
+    /// Return the compute unit limit for `units_consumed` with the margin applied
+    ///
+    /// The result is capped at `MAX_COMPUTE_UNIT_LIMIT`
+    pub fn apply(&self, units_consumed: u64) -> u32 {
+        let margin = (units_consumed * self.percent as u64 / 100).max(self.min_units as u64);
+        (units_consumed + margin).min(MAX_COMPUTE_UNIT_LIMIT as u64) as u32
+    }
+}
+
+/// Return the compute unit limit set by `message`, if any
+pub fn compute_unit_limit(message: &VersionedMessage) -> Option<u32> {
+    let limit_tag = set_compute_unit_limit_tag();
+    let keys = message.static_account_keys();
+    message.instructions().iter().find_map(|ix| {
+        if keys.get(ix.program_id_index as usize) == Some(&compute_budget::ID)
+            && ix.data.first() == Some(&limit_tag)
+        {
+            Some(u32::from_le_bytes(ix.data.get(1..5)?.try_into().ok()?))
+        } else {
+            None
+        }
+    })
+}
+
+/// Set the compute unit limit of `message` to `cu_limit`
+///
+/// Rewrites an existing compute unit limit ix, otherwise a new one is prepended (following the advance nonce ix of durable nonce txs).
+/// Must be called before signing, the message's signatures are invalidated.
+pub fn set_compute_unit_limit(message: &mut VersionedMessage, cu_limit: u32) {
+    let limit_tag = set_compute_unit_limit_tag();
+    let data = ComputeBudgetInstruction::set_compute_unit_limit(cu_limit).data;
+    let (header, keys, ixs) = match message {
+        VersionedMessage::Legacy(m) => (&mut m.header, &mut m.account_keys, &mut m.instructions),
+        VersionedMessage::V0(m) => (&mut m.header, &mut m.account_keys, &mut m.instructions),
+    };
+
+    if let Some(ix) = ixs.iter_mut().find(|ix| {
+        keys.get(ix.program_id_index as usize) == Some(&compute_budget::ID)
+            && ix.data.first() == Some(&limit_tag)
+    }) {
+        ix.data = data;
+        return;
+    }
+
+    let program_id_index = match keys.iter().position(|k| *k == compute_budget::ID) {
+        Some(idx) => idx as u8,
+        None => {
+            // append as a readonly, non-signer static key
+            // indexes of lookup table accounts follow the static keys and must be shifted
+            let idx = keys.len() as u8;
+            for ix in ixs.iter_mut() {
+                for account in ix.accounts.iter_mut().filter(|a| **a >= idx) {
+                    *account += 1;
+                }
+            }
+            keys.push(compute_budget::ID);
+            header.num_readonly_unsigned_accounts += 1;
+            idx
+        }
+    };
+
+    // a durable nonce tx must keep its advance nonce ix first
+    let insert_idx = match ixs.first() {
+        Some(ix) if is_advance_nonce_ix(ix, keys) => 1,
+        _ => 0,
+    };
+    ixs.insert(
+        insert_idx,
+        CompiledInstruction {
+            program_id_index,
+            accounts: vec![],
+            data,
+        },
+    );
+}
+
+fn is_advance_nonce_ix(ix: &CompiledInstruction, keys: &[Pubkey]) -> bool {
+    keys.get(ix.program_id_index as usize) == Some(&system_program::ID)
+        && matches!(
+            bincode::deserialize(&ix.data),
+            Ok(SystemInstruction::AdvanceNonceAccount)
+        )
+}
+
+fn set_compute_unit_limit_tag() -> u8 {
+    ComputeBudgetInstruction::set_compute_unit_limit(0).data[0]
+}
+
+#[cfg(test)]
+mod tests {
+    use solana_sdk::{
+        address_lookup_table::AddressLookupTableAccount,
+        hash::Hash,
+        instruction::{AccountMeta, Instruction},
+        message::{v0, Message},
+        pubkey::Pubkey,
+    };
+
+    use super::*;
+
+    #[test]
+    fn margin_apply() {
+        let margin = ComputeUnitMargin::new(10, 5_000);
+        assert_eq!(margin.apply(100_000), 110_000);
+        assert_eq!(margin.apply(10_000), 15_000);
+        assert_eq!(margin.apply(1_390_000), MAX_COMPUTE_UNIT_LIMIT);
+    }
+
+    #[test]
+    fn set_limit_rewrites_existing_ix() {
+        let payer = Pubkey::new_unique();
+        let ixs = [
+            ComputeBudgetInstruction::set_compute_unit_price(1_000),
+            ComputeBudgetInstruction::set_compute_unit_limit(200_000),
+            Instruction::new_with_bytes(Pubkey::new_unique(), &[1], vec![]),
+        ];
+        let mut message = VersionedMessage::Legacy(Message::new(&ixs, Some(&payer)));
+        assert_eq!(compute_unit_limit(&message), Some(200_000));
+
+        set_compute_unit_limit(&mut message, 123_456);
+        assert_eq!(compute_unit_limit(&message), Some(123_456));
+        assert_eq!(message.instructions().len(), 3);
+    }
+
+    #[test]
+    fn set_limit_inserts_ix() {
+        let payer = Pubkey::new_unique();
+        let program = Pubkey::new_unique();
+        let lut_account = Pubkey::new_unique();
+        let lut = AddressLookupTableAccount {
+            key: Pubkey::new_unique(),
+            addresses: vec![lut_account],
+        };
+        let ix = Instruction::new_with_bytes(
+            program,
+            &[1],
+            vec![AccountMeta::new_readonly(lut_account, false)],
+        );
+        let mut message = VersionedMessage::V0(
+            v0::Message::try_compile(&payer, &[ix], &[lut], Hash::default()).unwrap(),
+        );
+        assert_eq!(compute_unit_limit(&message), None);
+        let lut_account_index = message.instructions()[0].accounts[0];
+
+        set_compute_unit_limit(&mut message, 50_000);
+        assert_eq!(compute_unit_limit(&message), Some(50_000));
+        let instructions = message.instructions();
+        assert_eq!(instructions.len(), 2);
+        assert_eq!(
+            message.static_account_keys()[instructions[0].program_id_index as usize],
+            compute_budget::ID
+        );
+        // lookup table account index is shifted by the new static key
+        assert_eq!(instructions[1].accounts[0], lut_account_index + 1);
+        assert_eq!(message.header().num_readonly_unsigned_accounts, 2);
+    }
+}
@@ -17,9 +17,16 @@ pub const JIT_PROXY_ID: Pubkey =
 pub const DEFAULT_PUBKEY: Pubkey = solana_sdk::pubkey!("11111111111111111111111111111111");
 
 static STATE_ACCOUNT: OnceLock<Pubkey> = OnceLock::new();
+static HIGH_LEVERAGE_MODE_CONFIG_ACCOUNT: OnceLock<Pubkey> = OnceLock::new();
 
 pub const TOKEN_PROGRAM_ID: Pubkey =
     solana_sdk::pubkey!("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
+/// SPL token 2022 program address
+pub const TOKEN_2022_PROGRAM_ID: Pubkey =
+    solana_sdk::pubkey!("TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb");
+/// SPL associated token account program address
+pub const ASSOCIATED_TOKEN_PROGRAM_ID: Pubkey =
+    solana_sdk::pubkey!("ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL");
 
 /// Drift market lookup table (DevNet)
 pub const LUT_DEVNET: Pubkey = solana_sdk::pubkey!("FaMS3U4uBojvGn5FSDEPimddcXsCfwkKsFgMVVnDdxGb");
@@ -35,6 +42,15 @@ pub fn state_account() -> &'static Pubkey {
     })
 }
 
+/// Drift high leverage mode config account
+pub fn high_leverage_mode_config_account() -> &'static Pubkey {
+    HIGH_LEVERAGE_MODE_CONFIG_ACCOUNT.get_or_init(|| {
+        let (account, _seed) =
+            Pubkey::find_program_address(&[&b"high_leverage_mode_config"[..]], &PROGRAM_ID);
+        account
+    })
+}
+
 /// calculate the PDA of a drift spot market given index
 pub fn derive_spot_market_account(market_index: u16) -> Pubkey {
     let (account, _seed) = Pubkey::find_program_address(
@@ -62,12 +78,43 @@ pub fn derive_spot_market_vault(market_index: u16) -> Pubkey {
     account
 }
 
+/// calculate the PDA for a drift spot market's insurance fund vault given index
+pub fn derive_insurance_fund_vault(market_index: u16) -> Pubkey {
+    let (account, _seed) = Pubkey::find_program_address(
+        &[&b"insurance_fund_vault"[..], &market_index.to_le_bytes()],
+        &PROGRAM_ID,
+    );
+    account
+}
+
 /// calculate the PDA for the drift signer
 pub fn derive_drift_signer() -> Pubkey {
     let (account, _seed) = Pubkey::find_program_address(&[&b"drift_signer"[..]], &PROGRAM_ID);
     account
 }
 
+/// calculate the associated token account address of `owner` for `mint`
+pub fn derive_associated_token_account(
+    owner: &Pubkey,
+    mint: &Pubkey,
+    token_program: &Pubkey,
+) -> Pubkey {
+    let (account, _seed) = Pubkey::find_program_address(
+        &[owner.as_ref(), token_program.as_ref(), mint.as_ref()],
+        &ASSOCIATED_TOKEN_PROGRAM_ID,
+    );
+    account
+}
+
+/// Return the token program of a drift spot market's mint
+pub fn spot_market_token_program(market: &SpotMarket) -> Pubkey {
+    if market.token_program == 1 {
+        TOKEN_2022_PROGRAM_ID
+    } else {
+        TOKEN_PROGRAM_ID
+    }
+}
+
 /// Helper methods for market data structs
 pub trait MarketExt {
     fn market_type(&self) -> &'static str;
@@ -216,6 +263,12 @@ pub mod ids {
         pub const ID: Pubkey = solana_sdk::pubkey!("HDwcJBJXjL9FpJ7UBsYBtaDjsBUhuLCUYoz3zr8SWWaQ");
     }
 
+    pub mod pyth_solana_receiver_program {
+        use solana_sdk::pubkey::Pubkey;
+
+        pub const ID: Pubkey = solana_sdk::pubkey!("rec5EKMGg6MxZYaMdyBfgwp4d5rB9T1VQH5pJv5LtFJ");
+    }
+
     pub mod drift_oracle_receiver_program {
         use solana_sdk::pubkey::Pubkey;
 
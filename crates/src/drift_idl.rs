@@ -20366,6 +20366,323 @@ pub mod errors {
         #[msg("Pyth lazer message does not correspond to correct fed id")]
         PythLazerMessagePriceFeedMismatch,
     }
+    impl TryFrom<u32> for ErrorCode {
+        type Error = u32;
+        #[doc = r" Convert a custom program error `code` to `ErrorCode`, returns `code` if it is unknown"]
+        fn try_from(code: u32) -> Result<Self, Self::Error> {
+            match code {
+                6000 => Ok(Self::InvalidSpotMarketAuthority),
+                6001 => Ok(Self::InvalidInsuranceFundAuthority),
+                6002 => Ok(Self::InsufficientDeposit),
+                6003 => Ok(Self::InsufficientCollateral),
+                6004 => Ok(Self::SufficientCollateral),
+                6005 => Ok(Self::MaxNumberOfPositions),
+                6006 => Ok(Self::AdminControlsPricesDisabled),
+                6007 => Ok(Self::MarketDelisted),
+                6008 => Ok(Self::MarketIndexAlreadyInitialized),
+                6009 => Ok(Self::UserAccountAndUserPositionsAccountMismatch),
+                6010 => Ok(Self::UserHasNoPositionInMarket),
+                6011 => Ok(Self::InvalidInitialPeg),
+                6012 => Ok(Self::InvalidRepegRedundant),
+                6013 => Ok(Self::InvalidRepegDirection),
+                6014 => Ok(Self::InvalidRepegProfitability),
+                6015 => Ok(Self::SlippageOutsideLimit),
+                6016 => Ok(Self::OrderSizeTooSmall),
+                6017 => Ok(Self::InvalidUpdateK),
+                6018 => Ok(Self::AdminWithdrawTooLarge),
+                6019 => Ok(Self::MathError),
+                6020 => Ok(Self::BnConversionError),
+                6021 => Ok(Self::ClockUnavailable),
+                6022 => Ok(Self::UnableToLoadOracle),
+                6023 => Ok(Self::PriceBandsBreached),
+                6024 => Ok(Self::ExchangePaused),
+                6025 => Ok(Self::InvalidWhitelistToken),
+                6026 => Ok(Self::WhitelistTokenNotFound),
+                6027 => Ok(Self::InvalidDiscountToken),
+                6028 => Ok(Self::DiscountTokenNotFound),
+                6029 => Ok(Self::ReferrerNotFound),
+                6030 => Ok(Self::ReferrerStatsNotFound),
+                6031 => Ok(Self::ReferrerMustBeWritable),
+                6032 => Ok(Self::ReferrerStatsMustBeWritable),
+                6033 => Ok(Self::ReferrerAndReferrerStatsAuthorityUnequal),
+                6034 => Ok(Self::InvalidReferrer),
+                6035 => Ok(Self::InvalidOracle),
+                6036 => Ok(Self::OracleNotFound),
+                6037 => Ok(Self::LiquidationsBlockedByOracle),
+                6038 => Ok(Self::MaxDeposit),
+                6039 => Ok(Self::CantDeleteUserWithCollateral),
+                6040 => Ok(Self::InvalidFundingProfitability),
+                6041 => Ok(Self::CastingFailure),
+                6042 => Ok(Self::InvalidOrder),
+                6043 => Ok(Self::InvalidOrderMaxTs),
+                6044 => Ok(Self::InvalidOrderMarketType),
+                6045 => Ok(Self::InvalidOrderForInitialMarginReq),
+                6046 => Ok(Self::InvalidOrderNotRiskReducing),
+                6047 => Ok(Self::InvalidOrderSizeTooSmall),
+                6048 => Ok(Self::InvalidOrderNotStepSizeMultiple),
+                6049 => Ok(Self::InvalidOrderBaseQuoteAsset),
+                6050 => Ok(Self::InvalidOrderIOC),
+                6051 => Ok(Self::InvalidOrderPostOnly),
+                6052 => Ok(Self::InvalidOrderIOCPostOnly),
+                6053 => Ok(Self::InvalidOrderTrigger),
+                6054 => Ok(Self::InvalidOrderAuction),
+                6055 => Ok(Self::InvalidOrderOracleOffset),
+                6056 => Ok(Self::InvalidOrderMinOrderSize),
+                6057 => Ok(Self::PlacePostOnlyLimitFailure),
+                6058 => Ok(Self::UserHasNoOrder),
+                6059 => Ok(Self::OrderAmountTooSmall),
+                6060 => Ok(Self::MaxNumberOfOrders),
+                6061 => Ok(Self::OrderDoesNotExist),
+                6062 => Ok(Self::OrderNotOpen),
+                6063 => Ok(Self::FillOrderDidNotUpdateState),
+                6064 => Ok(Self::ReduceOnlyOrderIncreasedRisk),
+                6065 => Ok(Self::UnableToLoadAccountLoader),
+                6066 => Ok(Self::TradeSizeTooLarge),
+                6067 => Ok(Self::UserCantReferThemselves),
+                6068 => Ok(Self::DidNotReceiveExpectedReferrer),
+                6069 => Ok(Self::CouldNotDeserializeReferrer),
+                6070 => Ok(Self::CouldNotDeserializeReferrerStats),
+                6071 => Ok(Self::UserOrderIdAlreadyInUse),
+                6072 => Ok(Self::NoPositionsLiquidatable),
+                6073 => Ok(Self::InvalidMarginRatio),
+                6074 => Ok(Self::CantCancelPostOnlyOrder),
+                6075 => Ok(Self::InvalidOracleOffset),
+                6076 => Ok(Self::CantExpireOrders),
+                6077 => Ok(Self::CouldNotLoadMarketData),
+                6078 => Ok(Self::PerpMarketNotFound),
+                6079 => Ok(Self::InvalidMarketAccount),
+                6080 => Ok(Self::UnableToLoadPerpMarketAccount),
+                6081 => Ok(Self::MarketWrongMutability),
+                6082 => Ok(Self::UnableToCastUnixTime),
+                6083 => Ok(Self::CouldNotFindSpotPosition),
+                6084 => Ok(Self::NoSpotPositionAvailable),
+                6085 => Ok(Self::InvalidSpotMarketInitialization),
+                6086 => Ok(Self::CouldNotLoadSpotMarketData),
+                6087 => Ok(Self::SpotMarketNotFound),
+                6088 => Ok(Self::InvalidSpotMarketAccount),
+                6089 => Ok(Self::UnableToLoadSpotMarketAccount),
+                6090 => Ok(Self::SpotMarketWrongMutability),
+                6091 => Ok(Self::SpotMarketInterestNotUpToDate),
+                6092 => Ok(Self::SpotMarketInsufficientDeposits),
+                6093 => Ok(Self::UserMustSettleTheirOwnPositiveUnsettledPNL),
+                6094 => Ok(Self::CantUpdatePoolBalanceType),
+                6095 => Ok(Self::InsufficientCollateralForSettlingPNL),
+                6096 => Ok(Self::AMMNotUpdatedInSameSlot),
+                6097 => Ok(Self::AuctionNotComplete),
+                6098 => Ok(Self::MakerNotFound),
+                6099 => Ok(Self::MakerStatsNotFound),
+                6100 => Ok(Self::MakerMustBeWritable),
+                6101 => Ok(Self::MakerStatsMustBeWritable),
+                6102 => Ok(Self::MakerOrderNotFound),
+                6103 => Ok(Self::CouldNotDeserializeMaker),
+                6104 => Ok(Self::CouldNotDeserializeMakerStats),
+                6105 => Ok(Self::AuctionPriceDoesNotSatisfyMaker),
+                6106 => Ok(Self::MakerCantFulfillOwnOrder),
+                6107 => Ok(Self::MakerOrderMustBePostOnly),
+                6108 => Ok(Self::CantMatchTwoPostOnlys),
+                6109 => Ok(Self::OrderBreachesOraclePriceLimits),
+                6110 => Ok(Self::OrderMustBeTriggeredFirst),
+                6111 => Ok(Self::OrderNotTriggerable),
+                6112 => Ok(Self::OrderDidNotSatisfyTriggerCondition),
+                6113 => Ok(Self::PositionAlreadyBeingLiquidated),
+                6114 => Ok(Self::PositionDoesntHaveOpenPositionOrOrders),
+                6115 => Ok(Self::AllOrdersAreAlreadyLiquidations),
+                6116 => Ok(Self::CantCancelLiquidationOrder),
+                6117 => Ok(Self::UserIsBeingLiquidated),
+                6118 => Ok(Self::LiquidationsOngoing),
+                6119 => Ok(Self::WrongSpotBalanceType),
+                6120 => Ok(Self::UserCantLiquidateThemself),
+                6121 => Ok(Self::InvalidPerpPositionToLiquidate),
+                6122 => Ok(Self::InvalidBaseAssetAmountForLiquidatePerp),
+                6123 => Ok(Self::InvalidPositionLastFundingRate),
+                6124 => Ok(Self::InvalidPositionDelta),
+                6125 => Ok(Self::UserBankrupt),
+                6126 => Ok(Self::UserNotBankrupt),
+                6127 => Ok(Self::UserHasInvalidBorrow),
+                6128 => Ok(Self::DailyWithdrawLimit),
+                6129 => Ok(Self::DefaultError),
+                6130 => Ok(Self::InsufficientLPTokens),
+                6131 => Ok(Self::CantLPWithPerpPosition),
+                6132 => Ok(Self::UnableToBurnLPTokens),
+                6133 => Ok(Self::TryingToRemoveLiquidityTooFast),
+                6134 => Ok(Self::InvalidSpotMarketVault),
+                6135 => Ok(Self::InvalidSpotMarketState),
+                6136 => Ok(Self::InvalidSerumProgram),
+                6137 => Ok(Self::InvalidSerumMarket),
+                6138 => Ok(Self::InvalidSerumBids),
+                6139 => Ok(Self::InvalidSerumAsks),
+                6140 => Ok(Self::InvalidSerumOpenOrders),
+                6141 => Ok(Self::FailedSerumCPI),
+                6142 => Ok(Self::FailedToFillOnExternalMarket),
+                6143 => Ok(Self::InvalidFulfillmentConfig),
+                6144 => Ok(Self::InvalidFeeStructure),
+                6145 => Ok(Self::InsufficientIFShares),
+                6146 => Ok(Self::MarketActionPaused),
+                6147 => Ok(Self::MarketPlaceOrderPaused),
+                6148 => Ok(Self::MarketFillOrderPaused),
+                6149 => Ok(Self::MarketWithdrawPaused),
+                6150 => Ok(Self::ProtectedAssetTierViolation),
+                6151 => Ok(Self::IsolatedAssetTierViolation),
+                6152 => Ok(Self::UserCantBeDeleted),
+                6153 => Ok(Self::ReduceOnlyWithdrawIncreasedRisk),
+                6154 => Ok(Self::MaxOpenInterest),
+                6155 => Ok(Self::CantResolvePerpBankruptcy),
+                6156 => Ok(Self::LiquidationDoesntSatisfyLimitPrice),
+                6157 => Ok(Self::MarginTradingDisabled),
+                6158 => Ok(Self::InvalidMarketStatusToSettlePnl),
+                6159 => Ok(Self::PerpMarketNotInSettlement),
+                6160 => Ok(Self::PerpMarketNotInReduceOnly),
+                6161 => Ok(Self::PerpMarketSettlementBufferNotReached),
+                6162 => Ok(Self::PerpMarketSettlementUserHasOpenOrders),
+                6163 => Ok(Self::PerpMarketSettlementUserHasActiveLP),
+                6164 => Ok(Self::UnableToSettleExpiredUserPosition),
+                6165 => Ok(Self::UnequalMarketIndexForSpotTransfer),
+                6166 => Ok(Self::InvalidPerpPositionDetected),
+                6167 => Ok(Self::InvalidSpotPositionDetected),
+                6168 => Ok(Self::InvalidAmmDetected),
+                6169 => Ok(Self::InvalidAmmForFillDetected),
+                6170 => Ok(Self::InvalidAmmLimitPriceOverride),
+                6171 => Ok(Self::InvalidOrderFillPrice),
+                6172 => Ok(Self::SpotMarketBalanceInvariantViolated),
+                6173 => Ok(Self::SpotMarketVaultInvariantViolated),
+                6174 => Ok(Self::InvalidPDA),
+                6175 => Ok(Self::InvalidPDASigner),
+                6176 => Ok(Self::RevenueSettingsCannotSettleToIF),
+                6177 => Ok(Self::NoRevenueToSettleToIF),
+                6178 => Ok(Self::NoAmmPerpPnlDeficit),
+                6179 => Ok(Self::SufficientPerpPnlPool),
+                6180 => Ok(Self::InsufficientPerpPnlPool),
+                6181 => Ok(Self::PerpPnlDeficitBelowThreshold),
+                6182 => Ok(Self::MaxRevenueWithdrawPerPeriodReached),
+                6183 => Ok(Self::MaxIFWithdrawReached),
+                6184 => Ok(Self::NoIFWithdrawAvailable),
+                6185 => Ok(Self::InvalidIFUnstake),
+                6186 => Ok(Self::InvalidIFUnstakeSize),
+                6187 => Ok(Self::InvalidIFUnstakeCancel),
+                6188 => Ok(Self::InvalidIFForNewStakes),
+                6189 => Ok(Self::InvalidIFRebase),
+                6190 => Ok(Self::InvalidInsuranceUnstakeSize),
+                6191 => Ok(Self::InvalidOrderLimitPrice),
+                6192 => Ok(Self::InvalidIFDetected),
+                6193 => Ok(Self::InvalidAmmMaxSpreadDetected),
+                6194 => Ok(Self::InvalidConcentrationCoef),
+                6195 => Ok(Self::InvalidSrmVault),
+                6196 => Ok(Self::InvalidVaultOwner),
+                6197 => Ok(Self::InvalidMarketStatusForFills),
+                6198 => Ok(Self::IFWithdrawRequestInProgress),
+                6199 => Ok(Self::NoIFWithdrawRequestInProgress),
+                6200 => Ok(Self::IFWithdrawRequestTooSmall),
+                6201 => Ok(Self::IncorrectSpotMarketAccountPassed),
+                6202 => Ok(Self::BlockchainClockInconsistency),
+                6203 => Ok(Self::InvalidIFSharesDetected),
+                6204 => Ok(Self::NewLPSizeTooSmall),
+                6205 => Ok(Self::MarketStatusInvalidForNewLP),
+                6206 => Ok(Self::InvalidMarkTwapUpdateDetected),
+                6207 => Ok(Self::MarketSettlementAttemptOnActiveMarket),
+                6208 => Ok(Self::MarketSettlementRequiresSettledLP),
+                6209 => Ok(Self::MarketSettlementAttemptTooEarly),
+                6210 => Ok(Self::MarketSettlementTargetPriceInvalid),
+                6211 => Ok(Self::UnsupportedSpotMarket),
+                6212 => Ok(Self::SpotOrdersDisabled),
+                6213 => Ok(Self::MarketBeingInitialized),
+                6214 => Ok(Self::InvalidUserSubAccountId),
+                6215 => Ok(Self::InvalidTriggerOrderCondition),
+                6216 => Ok(Self::InvalidSpotPosition),
+                6217 => Ok(Self::CantTransferBetweenSameUserAccount),
+                6218 => Ok(Self::InvalidPerpPosition),
+                6219 => Ok(Self::UnableToGetLimitPrice),
+                6220 => Ok(Self::InvalidLiquidation),
+                6221 => Ok(Self::SpotFulfillmentConfigDisabled),
+                6222 => Ok(Self::InvalidMaker),
+                6223 => Ok(Self::FailedUnwrap),
+                6224 => Ok(Self::MaxNumberOfUsers),
+                6225 => Ok(Self::InvalidOracleForSettlePnl),
+                6226 => Ok(Self::MarginOrdersOpen),
+                6227 => Ok(Self::TierViolationLiquidatingPerpPnl),
+                6228 => Ok(Self::CouldNotLoadUserData),
+                6229 => Ok(Self::UserWrongMutability),
+                6230 => Ok(Self::InvalidUserAccount),
+                6231 => Ok(Self::CouldNotLoadUserStatsData),
+                6232 => Ok(Self::UserStatsWrongMutability),
+                6233 => Ok(Self::InvalidUserStatsAccount),
+                6234 => Ok(Self::UserNotFound),
+                6235 => Ok(Self::UnableToLoadUserAccount),
+                6236 => Ok(Self::UserStatsNotFound),
+                6237 => Ok(Self::UnableToLoadUserStatsAccount),
+                6238 => Ok(Self::UserNotInactive),
+                6239 => Ok(Self::RevertFill),
+                6240 => Ok(Self::InvalidMarketAccountforDeletion),
+                6241 => Ok(Self::InvalidSpotFulfillmentParams),
+                6242 => Ok(Self::FailedToGetMint),
+                6243 => Ok(Self::FailedPhoenixCPI),
+                6244 => Ok(Self::FailedToDeserializePhoenixMarket),
+                6245 => Ok(Self::InvalidPricePrecision),
+                6246 => Ok(Self::InvalidPhoenixProgram),
+                6247 => Ok(Self::InvalidPhoenixMarket),
+                6248 => Ok(Self::InvalidSwap),
+                6249 => Ok(Self::SwapLimitPriceBreached),
+                6250 => Ok(Self::SpotMarketReduceOnly),
+                6251 => Ok(Self::FundingWasNotUpdated),
+                6252 => Ok(Self::ImpossibleFill),
+                6253 => Ok(Self::CantUpdatePerpBidAskTwap),
+                6254 => Ok(Self::UserReduceOnly),
+                6255 => Ok(Self::InvalidMarginCalculation),
+                6256 => Ok(Self::CantPayUserInitFee),
+                6257 => Ok(Self::CantReclaimRent),
+                6258 => Ok(Self::InsuranceFundOperationPaused),
+                6259 => Ok(Self::NoUnsettledPnl),
+                6260 => Ok(Self::PnlPoolCantSettleUser),
+                6261 => Ok(Self::OracleNonPositive),
+                6262 => Ok(Self::OracleTooVolatile),
+                6263 => Ok(Self::OracleTooUncertain),
+                6264 => Ok(Self::OracleStaleForMargin),
+                6265 => Ok(Self::OracleInsufficientDataPoints),
+                6266 => Ok(Self::OracleStaleForAMM),
+                6267 => Ok(Self::UnableToParsePullOracleMessage),
+                6268 => Ok(Self::MaxBorrows),
+                6269 => Ok(Self::OracleUpdatesNotMonotonic),
+                6270 => Ok(Self::OraclePriceFeedMessageMismatch),
+                6271 => Ok(Self::OracleUnsupportedMessageType),
+                6272 => Ok(Self::OracleDeserializeMessageFailed),
+                6273 => Ok(Self::OracleWrongGuardianSetOwner),
+                6274 => Ok(Self::OracleWrongWriteAuthority),
+                6275 => Ok(Self::OracleWrongVaaOwner),
+                6276 => Ok(Self::OracleTooManyPriceAccountUpdates),
+                6277 => Ok(Self::OracleMismatchedVaaAndPriceUpdates),
+                6278 => Ok(Self::OracleBadRemainingAccountPublicKey),
+                6279 => Ok(Self::FailedOpenbookV2CPI),
+                6280 => Ok(Self::InvalidOpenbookV2Program),
+                6281 => Ok(Self::InvalidOpenbookV2Market),
+                6282 => Ok(Self::NonZeroTransferFee),
+                6283 => Ok(Self::LiquidationOrderFailedToFill),
+                6284 => Ok(Self::InvalidPredictionMarketOrder),
+                6285 => Ok(Self::InvalidVerificationIxIndex),
+                6286 => Ok(Self::SigVerificationFailed),
+                6287 => Ok(Self::MismatchedSwiftOrderParamsMarketIndex),
+                6288 => Ok(Self::InvalidSwiftOrderParam),
+                6289 => Ok(Self::PlaceAndTakeOrderSuccessConditionFailed),
+                6290 => Ok(Self::InvalidHighLeverageModeConfig),
+                6291 => Ok(Self::InvalidRFQUserAccount),
+                6292 => Ok(Self::RFQUserAccountWrongMutability),
+                6293 => Ok(Self::RFQUserAccountFull),
+                6294 => Ok(Self::RFQOrderNotFilled),
+                6295 => Ok(Self::InvalidRFQOrder),
+                6296 => Ok(Self::InvalidRFQMatch),
+                6297 => Ok(Self::InvalidSwiftUserAccount),
+                6298 => Ok(Self::SwiftUserAccountWrongMutability),
+                6299 => Ok(Self::SwiftUserOrdersAccountFull),
+                6300 => Ok(Self::SwiftOrderDoesNotExist),
+                6301 => Ok(Self::InvalidSwiftOrderId),
+                6302 => Ok(Self::InvalidPoolId),
+                6303 => Ok(Self::InvalidProtectedMakerModeConfig),
+                6304 => Ok(Self::InvalidPythLazerStorageOwner),
+                6305 => Ok(Self::UnverifiedPythLazerMessage),
+                6306 => Ok(Self::InvalidPythLazerMessage),
+                6307 => Ok(Self::PythLazerMessagePriceFeedMismatch),
+                _ => Err(code),
+            }
+        }
+    }
 }
 pub mod events {
     #![doc = r" IDL event types"]
@@ -25,7 +25,7 @@ use solana_transaction_status::{
 use tokio::{
     sync::{
         mpsc::{channel, Receiver, Sender},
-        RwLock,
+        watch, RwLock,
     },
     task::JoinHandle,
 };
@@ -37,6 +37,7 @@ use crate::{
         events::{FundingPaymentRecord, OrderActionRecord, OrderRecord},
         types::{MarketType, Order, OrderAction, OrderActionExplanation, PositionDirection},
     },
+    failover::{active_changed, RpcEndpoints},
     types::SdkResult,
 };
 
@@ -118,7 +119,18 @@ impl EventSubscriber {
         sub_account: Pubkey,
         retry_policy: impl TaskRetryPolicy,
     ) -> SdkResult<DriftEventStream> {
-        log_stream(endpoint, sub_account, retry_policy).await
+        log_stream(endpoint, None, sub_account, retry_policy).await
+    }
+    /// Subscribe to drift events of `sub_account`, backed by Ws APIs of the active endpoint of `endpoints`
+    ///
+    /// The underlying stream reconnects when the active endpoint changes, otherwise according to the given `retry_policy`
+    pub async fn subscribe_with_failover(
+        endpoints: Arc<RpcEndpoints>,
+        sub_account: Pubkey,
+        retry_policy: impl TaskRetryPolicy,
+    ) -> SdkResult<DriftEventStream> {
+        let endpoint = endpoints.active_ws_url();
+        log_stream(&endpoint, Some(endpoints), sub_account, retry_policy).await
     }
     /// Subscribe to drift events of `sub_account`, backed by RPC polling APIs
     pub fn subscribe_polled(provider: impl EventRpcProvider, account: Pubkey) -> DriftEventStream {
@@ -132,19 +144,41 @@ struct LogEventStream {
     sub_account: Pubkey,
     event_tx: Sender<DriftEvent>,
     commitment: CommitmentConfig,
+    /// follows the active endpoint, if set
+    failover: Option<Arc<RpcEndpoints>>,
 }
 
 impl LogEventStream {
     /// Returns a future for running the configured log event stream
     async fn stream_fn(self) {
+        let mut active_endpoint = self.failover.as_ref().map(|f| f.watch_active());
+        while self.connect(&mut active_endpoint).await {
+            info!(target: LOG_TARGET, "endpoint changed, reconnecting: {:?}", self.sub_account);
+        }
+    }
+
+    /// Run the log stream until it ends
+    ///
+    /// Returns true if ended due to the active endpoint changing
+    async fn connect(&self, active_endpoint: &mut Option<watch::Receiver<usize>>) -> bool {
         let sub_account = self.sub_account;
+        let active = self.failover.as_ref().map(|f| f.active());
+        let endpoint = self
+            .failover
+            .as_ref()
+            .zip(active)
+            .map_or_else(|| self.endpoint.to_string(), |(f, idx)| f.ws_url(idx));
         info!(target: LOG_TARGET, "log stream connecting: {sub_account:?}");
-        let provider_init = PubsubClient::new(self.endpoint.as_str()).await;
+        let provider_init = PubsubClient::new(endpoint.as_str()).await;
 
         // the provider's internal websocket connection can close, if so need to reconnect
         if let Err(ref err) = provider_init {
             warn!(target: LOG_TARGET, "log subscription failed {err:?}, retrying: {sub_account:?}");
-            return;
+            // the retry connects to the new active endpoint
+            if let Some((endpoints, idx)) = self.failover.as_ref().zip(active) {
+                endpoints.mark_ws_unhealthy(idx);
+            }
+            return false;
         }
 
         let provider = provider_init.unwrap();
@@ -162,18 +196,27 @@ impl LogEventStream {
         // the provider's internal websocket connection can close, if so need to reconnect
         if let Err(ref err) = subscribe_result {
             warn!(target: LOG_TARGET, "log subscription failed {err:?}, retrying: {sub_account:?}");
-            return;
+            return false;
         }
 
         let (mut log_stream, unsub_fn) = subscribe_result.unwrap();
         debug!(target: LOG_TARGET, "start log subscription: {sub_account:?}");
 
-        while let Some(response) = log_stream.next().await {
-            self.process_log(response.value).await;
-        }
+        let endpoint_changed = loop {
+            tokio::select! {
+                response = log_stream.next() => match response {
+                    Some(response) => self.process_log(response.value).await,
+                    None => break false,
+                },
+                _ = active_changed(active_endpoint) => break true,
+            }
+        };
 
-        warn!(target: LOG_TARGET, "log stream ended: {sub_account:?}");
+        if !endpoint_changed {
+            warn!(target: LOG_TARGET, "log stream ended: {sub_account:?}");
+        }
         unsub_fn().await;
+        endpoint_changed
     }
 
     /// Process a log response from RPC, emitting any relevant events
@@ -233,8 +276,11 @@ fn polled_stream(provider: impl EventRpcProvider, sub_account: Pubkey) -> DriftE
 }
 
 /// Creates a Ws-backed event stream using `logsSubscribe` interface
+///
+/// * `failover` - connect to the active endpoint of `failover` in place of `endpoint`, if set
 async fn log_stream(
     endpoint: &str,
+    failover: Option<Arc<RpcEndpoints>>,
     sub_account: Pubkey,
     retry_policy: impl TaskRetryPolicy,
 ) -> SdkResult<DriftEventStream> {
@@ -253,6 +299,7 @@ async fn log_stream(
                 sub_account,
                 event_tx: event_tx.clone(),
                 commitment: CommitmentConfig::confirmed(),
+                failover: failover.clone(),
             };
             log_stream.stream_fn()
         },
@@ -429,6 +476,10 @@ pub fn try_parse_log(raw: &str, signature: &str, tx_idx: usize) -> Option<DriftE
         .or_else(|| raw.strip_prefix(PROGRAM_DATA))
     {
         if let Ok(borsh_bytes) = base64::engine::general_purpose::STANDARD.decode(log) {
+            // too short for an event discriminator e.g. a plain text log
+            if borsh_bytes.len() < 8 {
+                return None;
+            }
             let (disc, mut data) = borsh_bytes.split_at(8);
             let disc: [u8; 8] = disc.try_into().unwrap();
 
@@ -736,6 +787,7 @@ mod test {
                 .unwrap(),
             event_tx,
             commitment: CommitmentConfig::confirmed(),
+            failover: None,
         };
 
         let logs: Vec<String> = [
@@ -0,0 +1,499 @@
+//! Read-side RPC failover
+//!
+//! Reads and Ws subscriptions use the highest priority healthy endpoint. Endpoints are unhealthy
+//! for a cooldown period after a transport error, or while lagging the highest known slot.
+//! The primary endpoint is used again once it recovers.
+//!
+//! ```ignore
+//! let client = DriftClient::new_with_endpoints(
+//!     Context::MainNet,
+//!     vec![(primary_url, primary_ws_url), (backup_url, backup_ws_url)],
+//!     CommitmentConfig::confirmed(),
+//!     wallet,
+//! )
+//! .await?;
+//!
+//! // other subscribers follow the same endpoints
+//! let endpoints = client.rpc_endpoints().unwrap();
+//! let usermap = GlobalUserMap::new(commitment, url, true, None).with_failover(Arc::clone(&endpoints));
+//! let events = EventSubscriber::subscribe_with_failover(endpoints, sub_account, retry_policy::forever(1)).await?;
+//! ```
+use std::{
+    future::pending,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Mutex, Weak,
+    },
+    time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+use futures_util::future::join_all;
+use log::{debug, warn};
+use serde_json::{json, Value};
+use solana_client::{
+    client_error::{ClientError, ClientErrorKind},
+    http_sender::HttpSender,
+    nonblocking::rpc_client::RpcClient,
+    rpc_client::RpcClientConfig,
+    rpc_request::{RpcError, RpcRequest},
+    rpc_sender::{RpcSender, RpcTransportStats},
+};
+use solana_sdk::{clock::Slot, commitment_config::CommitmentConfig};
+use tokio::sync::watch;
+
+use crate::{
+    types::{SdkError, SdkResult},
+    utils::{get_http_url, get_ws_url},
+};
+
+const LOG_TARGET: &str = "failover";
+/// JSON-RPC error code of an unhealthy node
+const NODE_UNHEALTHY: i64 = -32005;
+
+/// Health of an RPC endpoint
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct EndpointStatus {
+    /// endpoint URL
+    pub url: String,
+    /// true if the endpoint may be used
+    pub healthy: bool,
+    /// true if the endpoint lags the highest known slot
+    pub lagging: bool,
+    /// slot of the last health check
+    pub slot: Slot,
+    /// number of failed requests
+    pub errors: u64,
+}
+
+struct Endpoint {
+    url: String,
+    ws_url: String,
+    sender: HttpSender,
+    /// unhealthy until this time after an error
+    cooldown_until: Mutex<Option<Instant>>,
+    lagging: AtomicBool,
+    slot: AtomicU64,
+    errors: AtomicU64,
+}
+
+impl Endpoint {
+    fn is_healthy(&self) -> bool {
+        let cooldown = self.cooldown_until.lock().expect("acquired");
+        !self.lagging.load(Ordering::Relaxed) && cooldown.map_or(true, |t| Instant::now() >= t)
+    }
+}
+
+/// Prioritized list of RPC endpoints with health tracking
+pub struct RpcEndpoints {
+    /// endpoints by priority
+    endpoints: Vec<Endpoint>,
+    /// index of the active endpoint
+    active: watch::Sender<usize>,
+    /// endpoints more than this many slots behind are lagging
+    max_slot_lag: u64,
+    /// duration an endpoint is unhealthy after an error
+    cooldown: Duration,
+}
+
+impl RpcEndpoints {
+    pub const DEFAULT_MAX_SLOT_LAG: u64 = 50;
+    pub const DEFAULT_COOLDOWN: Duration = Duration::from_secs(10);
+    pub const HEALTH_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+    /// Create a new `RpcEndpoints`
+    ///
+    /// * `urls` - (RPC, Ws) endpoint URL pairs, highest priority first
+    ///
+    /// Returns error if `urls` is empty or contains invalid URLs
+    pub fn new(urls: Vec<(String, String)>) -> SdkResult<Self> {
+        if urls.is_empty() {
+            return Err(SdkError::InvalidUrl);
+        }
+        let endpoints = urls
+            .into_iter()
+            .map(|(url, ws_url)| {
+                let url = get_http_url(&url)?;
+                Ok(Endpoint {
+                    sender: HttpSender::new(url.clone()),
+                    url,
+                    ws_url: get_ws_url(&ws_url)?,
+                    cooldown_until: Mutex::default(),
+                    lagging: AtomicBool::new(false),
+                    slot: AtomicU64::new(0),
+                    errors: AtomicU64::new(0),
+                })
+            })
+            .collect::<SdkResult<Vec<_>>>()?;
+
+        Ok(Self {
+            endpoints,
+            active: watch::Sender::new(0),
+            max_slot_lag: Self::DEFAULT_MAX_SLOT_LAG,
+            cooldown: Self::DEFAULT_COOLDOWN,
+        })
+    }
+
+    /// Set the max. slots an endpoint may lag behind before failing over
+    pub fn with_max_slot_lag(mut self, max_slot_lag: u64) -> Self {
+        self.max_slot_lag = max_slot_lag;
+        self
+    }
+
+    /// Set the duration an endpoint is unhealthy after an error
+    pub fn with_cooldown(mut self, cooldown: Duration) -> Self {
+        self.cooldown = cooldown;
+        self
+    }
+
+    /// Return an `RpcClient` failing over between endpoints
+    pub fn rpc_client(self: &Arc<Self>, commitment: CommitmentConfig) -> RpcClient {
+        RpcClient::new_sender(
+            FailoverSender(Arc::clone(self)),
+            RpcClientConfig::with_commitment(commitment),
+        )
+    }
+
+    /// Return the index of the active endpoint
+    pub fn active(&self) -> usize {
+        *self.active.borrow()
+    }
+
+    /// Return the URL of the active endpoint
+    pub fn active_url(&self) -> String {
+        self.endpoints[self.active()].url.clone()
+    }
+
+    /// Return the Ws URL of the active endpoint
+    pub fn active_ws_url(&self) -> String {
+        self.ws_url(self.active())
+    }
+
+    /// Return the Ws URL of the endpoint at `idx`
+    pub fn ws_url(&self, idx: usize) -> String {
+        self.endpoints[idx].ws_url.clone()
+    }
+
+    /// Return a receiver notified when the active endpoint changes
+    pub fn watch_active(&self) -> watch::Receiver<usize> {
+        self.active.subscribe()
+    }
+
+    /// Return the health of all endpoints, by priority
+    pub fn status(&self) -> Vec<EndpointStatus> {
+        self.endpoints
+            .iter()
+            .map(|e| EndpointStatus {
+                url: e.url.clone(),
+                healthy: e.is_healthy(),
+                lagging: e.lagging.load(Ordering::Relaxed),
+                slot: e.slot.load(Ordering::Relaxed),
+                errors: e.errors.load(Ordering::Relaxed),
+            })
+            .collect()
+    }
+
+    /// Check the slot of every endpoint, marking unreachable or lagging endpoints unhealthy
+    pub async fn check_health(&self) {
+        let slots = join_all(self.endpoints.iter().map(|e| async move {
+            e.sender
+                .send(RpcRequest::GetSlot, json!([CommitmentConfig::processed()]))
+                .await
+                .ok()
+                .and_then(|slot| slot.as_u64())
+        }))
+        .await;
+
+        let max_slot = slots.iter().flatten().max().copied().unwrap_or_default();
+        for (idx, slot) in slots.into_iter().enumerate() {
+            let endpoint = &self.endpoints[idx];
+            match slot {
+                Some(slot) => {
+                    endpoint.slot.store(slot, Ordering::Relaxed);
+                    let lagging = max_slot.saturating_sub(slot) > self.max_slot_lag;
+                    if lagging {
+                        warn!(target: LOG_TARGET, "{} lagging by {} slots", endpoint.url, max_slot - slot);
+                    }
+                    endpoint.lagging.store(lagging, Ordering::Relaxed);
+                }
+                None => self.mark_unhealthy(idx),
+            }
+        }
+
+        self.activate_healthy();
+    }
+
+    /// Run `check_health` every `interval` until `self` is dropped
+    pub fn spawn_health_check(self: &Arc<Self>, interval: Duration) {
+        let endpoints: Weak<Self> = Arc::downgrade(self);
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(interval);
+            loop {
+                interval.tick().await;
+                match endpoints.upgrade() {
+                    Some(endpoints) => endpoints.check_health().await,
+                    None => break,
+                }
+            }
+        });
+    }
+
+    /// Return endpoint indexes in the order they should be tried
+    ///
+    /// healthy endpoints by priority, then unhealthy endpoints as a last resort
+    fn candidates(&self) -> Vec<usize> {
+        let (mut healthy, unhealthy): (Vec<usize>, Vec<usize>) =
+            (0..self.endpoints.len()).partition(|idx| self.endpoints[*idx].is_healthy());
+        healthy.extend(unhealthy);
+        healthy
+    }
+
+    fn mark_unhealthy(&self, idx: usize) {
+        let endpoint = &self.endpoints[idx];
+        endpoint.errors.fetch_add(1, Ordering::Relaxed);
+        *endpoint.cooldown_until.lock().expect("acquired") = Some(Instant::now() + self.cooldown);
+    }
+
+    /// Mark the endpoint at `idx` unhealthy after a Ws connection error
+    ///
+    /// Switches to the highest priority healthy endpoint, if any
+    pub(crate) fn mark_ws_unhealthy(&self, idx: usize) {
+        warn!(target: LOG_TARGET, "{} Ws connection failed", self.endpoints[idx].ws_url);
+        self.mark_unhealthy(idx);
+        self.activate_healthy();
+    }
+
+    /// Set the highest priority healthy endpoint as the active endpoint, if any
+    fn activate_healthy(&self) {
+        if let Some(idx) = (0..self.endpoints.len()).find(|idx| self.endpoints[*idx].is_healthy()) {
+            self.set_active(idx);
+        }
+    }
+
+    /// Set the endpoint at `active` as the active endpoint
+    pub(crate) fn set_active(&self, active: usize) {
+        self.active.send_if_modified(|current| {
+            if *current == active {
+                return false;
+            }
+            warn!(
+                target: LOG_TARGET,
+                "switching endpoint: {} => {}", self.endpoints[*current].url, self.endpoints[active].url
+            );
+            *current = active;
+            true
+        });
+    }
+}
+
+/// Resolves when the active endpoint changes, never if `active` is `None`
+pub(crate) async fn active_changed(active: &mut Option<watch::Receiver<usize>>) {
+    match active {
+        Some(active) => {
+            if active.changed().await.is_err() {
+                pending::<()>().await
+            }
+        }
+        None => pending::<()>().await,
+    }
+}
+
+/// True if `err` indicates the endpoint is unavailable, rather than a failed request
+fn is_endpoint_error(err: &ClientError) -> bool {
+    match err.kind() {
+        ClientErrorKind::Io(_) | ClientErrorKind::Reqwest(_) | ClientErrorKind::Middleware(_) => {
+            true
+        }
+        ClientErrorKind::RpcError(RpcError::RpcResponseError { code, .. }) => {
+            *code == NODE_UNHEALTHY
+        }
+        _ => false,
+    }
+}
+
+/// RPC transport failing over between endpoints
+struct FailoverSender(Arc<RpcEndpoints>);
+
+#[async_trait]
+impl RpcSender for FailoverSender {
+    async fn send(&self, request: RpcRequest, params: Value) -> Result<Value, ClientError> {
+        let endpoints = &self.0;
+        let mut last_err = None;
+        for idx in endpoints.candidates() {
+            match endpoints.endpoints[idx]
+                .sender
+                .send(request, params.clone())
+                .await
+            {
+                Ok(result) => {
+                    endpoints.set_active(idx);
+                    return Ok(result);
+                }
+                Err(err) if is_endpoint_error(&err) => {
+                    debug!(target: LOG_TARGET, "{request} failed on {}: {err:?}", endpoints.endpoints[idx].url);
+                    endpoints.mark_unhealthy(idx);
+                    last_err = Some(err);
+                }
+                Err(err) => return Err(err),
+            }
+        }
+
+        Err(last_err.expect("at least 1 endpoint"))
+    }
+
+    fn get_transport_stats(&self) -> RpcTransportStats {
+        self.0.endpoints[self.0.active()]
+            .sender
+            .get_transport_stats()
+    }
+
+    fn url(&self) -> String {
+        self.0.active_url()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+    };
+
+    use super::*;
+
+    /// Serve `getSlot` responses of `slot`, or HTTP 503 if `healthy` is false
+    async fn serve(slot: Arc<AtomicU64>, healthy: Arc<AtomicBool>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let mut request = Vec::new();
+                let mut buf = [0_u8; 1024];
+                // read headers and body
+                loop {
+                    let n = stream.read(&mut buf).await.unwrap_or_default();
+                    request.extend_from_slice(&buf[..n]);
+                    let raw = String::from_utf8_lossy(&request).to_lowercase();
+                    if let Some(headers_end) = raw.find("\r\n\r\n") {
+                        let content_length = raw
+                            .lines()
+                            .find_map(|l| l.strip_prefix("content-length:"))
+                            .and_then(|l| l.trim().parse::<usize>().ok())
+                            .unwrap_or_default();
+                        if request.len() >= headers_end + 4 + content_length {
+                            break;
+                        }
+                    }
+                    if n == 0 {
+                        break;
+                    }
+                }
+                let response = if healthy.load(Ordering::Relaxed) {
+                    let body = json!({
+                        "jsonrpc": "2.0",
+                        "id": 1,
+                        "result": slot.load(Ordering::Relaxed),
+                    })
+                    .to_string();
+                    format!(
+                        "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{body}",
+                        body.len()
+                    )
+                } else {
+                    "HTTP/1.1 503 Service Unavailable\r\ncontent-length: 0\r\nconnection: close\r\n\r\n"
+                        .to_string()
+                };
+                let _ = stream.write_all(response.as_bytes()).await;
+            }
+        });
+
+        url
+    }
+
+    #[tokio::test]
+    async fn fails_over_and_back() {
+        let primary_slot = Arc::new(AtomicU64::new(1_000));
+        let primary_healthy = Arc::new(AtomicBool::new(false));
+        let primary = serve(Arc::clone(&primary_slot), Arc::clone(&primary_healthy)).await;
+        let backup = serve(
+            Arc::new(AtomicU64::new(1_010)),
+            Arc::new(AtomicBool::new(true)),
+        )
+        .await;
+
+        let endpoints = Arc::new(
+            RpcEndpoints::new(vec![
+                (primary.clone(), "ws://primary.example.com".into()),
+                (backup.clone(), "ws://backup.example.com".into()),
+            ])
+            .unwrap()
+            .with_cooldown(Duration::ZERO),
+        );
+        let mut active = Some(endpoints.watch_active());
+        let rpc_client = endpoints.rpc_client(CommitmentConfig::confirmed());
+
+        // primary is down
+        assert_eq!(rpc_client.get_slot().await.unwrap(), 1_010);
+        assert_eq!(endpoints.active(), 1);
+        assert_eq!(rpc_client.url(), backup);
+        assert_eq!(endpoints.active_ws_url(), "ws://backup.example.com");
+        active_changed(&mut active).await;
+        assert_eq!(endpoints.status()[0].errors, 1);
+
+        // primary recovers
+        primary_healthy.store(true, Ordering::Relaxed);
+        assert_eq!(rpc_client.get_slot().await.unwrap(), 1_000);
+        assert_eq!(endpoints.active(), 0);
+        active_changed(&mut active).await;
+
+        // primary lags
+        primary_slot.store(900, Ordering::Relaxed);
+        endpoints.check_health().await;
+        let status = endpoints.status();
+        assert!(status[0].lagging && !status[0].healthy);
+        assert_eq!(status[1].slot, 1_010);
+        assert_eq!(endpoints.active(), 1);
+        assert_eq!(rpc_client.get_slot().await.unwrap(), 1_010);
+
+        // primary catches up
+        primary_slot.store(1_010, Ordering::Relaxed);
+        endpoints.check_health().await;
+        assert_eq!(endpoints.active(), 0);
+    }
+
+    #[test]
+    fn ws_connection_error_fails_over() {
+        let endpoints = RpcEndpoints::new(vec![
+            (
+                "https://primary.example.com".into(),
+                "wss://primary-ws.example.com".into(),
+            ),
+            (
+                "https://backup.example.com".into(),
+                "wss://backup-ws.example.com".into(),
+            ),
+        ])
+        .unwrap();
+        assert_eq!(endpoints.active_ws_url(), "wss://primary-ws.example.com");
+
+        endpoints.mark_ws_unhealthy(0);
+        assert_eq!(endpoints.active(), 1);
+        assert_eq!(endpoints.active_ws_url(), "wss://backup-ws.example.com");
+        assert_eq!(endpoints.status()[0].errors, 1);
+    }
+
+    #[test]
+    fn invalid_endpoints() {
+        assert!(RpcEndpoints::new(vec![]).is_err());
+        assert!(RpcEndpoints::new(vec![(
+            "localhost:8899".into(),
+            "ws://localhost:8900".into()
+        )])
+        .is_err());
+        assert!(RpcEndpoints::new(vec![(
+            "http://localhost:8899".into(),
+            "localhost:8900".into()
+        )])
+        .is_err());
+    }
+}
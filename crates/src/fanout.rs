@@ -0,0 +1,253 @@
+//! Multi-endpoint tx sending
+//!
+//! Signed txs are sent to several RPC endpoints concurrently (e.g. staked or specialized send providers).
+//! The primary endpoint's preflight result is authoritative, other endpoints improve tx propagation.
+//! Per-endpoint latency and error stats are tracked.
+//!
+//! ```ignore
+//! let client = DriftClient::new(context, RpcClient::new(primary_url), wallet)
+//!     .await?
+//!     .with_send_endpoints(vec![RpcClient::new(provider_url)]);
+//! client.sign_and_send(tx).await?; // sends to primary and provider
+//! client.send_endpoint_stats(); // inspect stats
+//! ```
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use futures_util::future::join_all;
+use log::debug;
+use solana_client::{nonblocking::rpc_client::RpcClient, rpc_config::RpcSendTransactionConfig};
+use solana_sdk::{signature::Signature, transaction::VersionedTransaction};
+
+use crate::types::{SdkError, SdkResult};
+
+const LOG_TARGET: &str = "fanout";
+
+/// Tx send stats of an endpoint
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct EndpointStats {
+    /// endpoint URL
+    pub url: String,
+    /// number of successful sends
+    pub sent: u64,
+    /// number of failed sends
+    pub errors: u64,
+    /// latency of the last send (successful or not)
+    pub last_latency: Option<Duration>,
+    /// cumulative latency of successful sends
+    pub total_latency: Duration,
+    /// the last send error, if any
+    pub last_error: Option<String>,
+}
+
+impl EndpointStats {
+    /// Return the avg. latency of successful sends
+    pub fn avg_latency(&self) -> Option<Duration> {
+        if self.sent == 0 {
+            None
+        } else {
+            Some(self.total_latency / self.sent as u32)
+        }
+    }
+}
+
+/// A tx send endpoint with stats
+struct Endpoint {
+    rpc_client: Arc<RpcClient>,
+    stats: Mutex<EndpointStats>,
+}
+
+/// Sends txs to multiple RPC endpoints concurrently
+///
+/// The first endpoint is the primary endpoint
+pub struct FanoutSender {
+    endpoints: Vec<Endpoint>,
+}
+
+impl FanoutSender {
+    /// Create a new `FanoutSender` over `endpoints`
+    pub fn new(endpoints: Vec<Arc<RpcClient>>) -> Self {
+        Self {
+            endpoints: endpoints
+                .into_iter()
+                .map(|rpc_client| Endpoint {
+                    stats: Mutex::new(EndpointStats {
+                        url: rpc_client.url(),
+                        ..Default::default()
+                    }),
+                    rpc_client,
+                })
+                .collect(),
+        }
+    }
+
+    /// Send `tx` to all endpoints concurrently
+    ///
+    /// Returns the tx signature or the primary endpoint's tx error e.g. a preflight failure.
+    /// If the primary endpoint is unreachable, returns the signature if any other endpoint accepted the tx
+    pub async fn send(
+        &self,
+        tx: &VersionedTransaction,
+        config: RpcSendTransactionConfig,
+    ) -> SdkResult<Signature> {
+        let results = join_all(self.endpoints.iter().map(|endpoint| async move {
+            let start = Instant::now();
+            let result = endpoint
+                .rpc_client
+                .send_transaction_with_config(tx, config)
+                .await;
+            let latency = start.elapsed();
+
+            let mut stats = endpoint.stats.lock().expect("acquired");
+            stats.last_latency = Some(latency);
+            match result {
+                Ok(signature) => {
+                    stats.sent += 1;
+                    stats.total_latency += latency;
+                    Ok(signature)
+                }
+                Err(err) => {
+                    debug!(target: LOG_TARGET, "send failed: {}, {err:?}", stats.url);
+                    stats.errors += 1;
+                    stats.last_error = Some(err.to_string());
+                    Err(err)
+                }
+            }
+        }))
+        .await;
+
+        let mut results = results.into_iter();
+        let primary_err = match results.next() {
+            Some(Ok(signature)) => return Ok(signature),
+            // the tx itself is invalid, no other endpoint will land it
+            Some(Err(err)) if err.get_transaction_error().is_some() => return Err(err.into()),
+            Some(Err(err)) => err,
+            None => return Err(SdkError::Generic("no send endpoints".into())),
+        };
+
+        results
+            .find_map(Result::ok)
+            .ok_or_else(|| primary_err.into())
+    }
+
+    /// Return a snapshot of the send stats of all endpoints
+    pub fn stats(&self) -> Vec<EndpointStats> {
+        self.endpoints
+            .iter()
+            .map(|e| e.stats.lock().expect("acquired").clone())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::Value;
+    use solana_client::{
+        client_error::ClientError,
+        rpc_request::{RpcError, RpcRequest, RpcResponseErrorData},
+        rpc_response::RpcSimulateTransactionResult,
+        rpc_sender::{RpcSender, RpcTransportStats},
+    };
+    use solana_sdk::{
+        instruction::InstructionError, message::Message, signature::Keypair, signer::Signer,
+        transaction::TransactionError,
+    };
+
+    use super::*;
+
+    #[tokio::test]
+    async fn send_to_all_endpoints() {
+        let keypair = Keypair::new();
+        let message = Message::new(&[], Some(&keypair.pubkey()));
+        let tx = VersionedTransaction::try_new(
+            solana_sdk::message::VersionedMessage::Legacy(message),
+            &[&keypair],
+        )
+        .unwrap();
+
+        let endpoint = |url: &str| Arc::new(RpcClient::new_mock(url.to_string()));
+        let sender = FanoutSender::new(vec![endpoint("fails"), endpoint("succeeds")]);
+
+        let signature = sender.send(&tx, Default::default()).await.unwrap();
+        assert_eq!(signature, tx.signatures[0]);
+
+        let stats = sender.stats();
+        assert_eq!(stats[0].url, "fails");
+        assert_eq!((stats[0].sent, stats[0].errors), (0, 1));
+        assert!(stats[0].last_error.is_some());
+        assert_eq!((stats[1].sent, stats[1].errors), (1, 0));
+        assert!(stats[1].avg_latency().is_some());
+
+        // all endpoints fail
+        let sender = FanoutSender::new(vec![endpoint("fails")]);
+        assert!(sender.send(&tx, Default::default()).await.is_err());
+    }
+
+    /// RPC transport failing tx preflight
+    struct PreflightFailureSender;
+
+    #[async_trait::async_trait]
+    impl RpcSender for PreflightFailureSender {
+        async fn send(&self, _request: RpcRequest, _params: Value) -> Result<Value, ClientError> {
+            Err(RpcError::RpcResponseError {
+                code: -32002,
+                message: "Transaction simulation failed".into(),
+                data: RpcResponseErrorData::SendTransactionPreflightFailure(
+                    RpcSimulateTransactionResult {
+                        err: Some(TransactionError::InstructionError(
+                            0,
+                            InstructionError::Custom(6_003),
+                        )),
+                        logs: None,
+                        accounts: None,
+                        units_consumed: None,
+                        return_data: None,
+                        inner_instructions: None,
+                        replacement_blockhash: None,
+                    },
+                ),
+            }
+            .into())
+        }
+
+        fn get_transport_stats(&self) -> RpcTransportStats {
+            RpcTransportStats::default()
+        }
+
+        fn url(&self) -> String {
+            "preflight_failure".into()
+        }
+    }
+
+    #[tokio::test]
+    async fn send_returns_primary_preflight_error() {
+        let keypair = Keypair::new();
+        let message = Message::new(&[], Some(&keypair.pubkey()));
+        let tx = VersionedTransaction::try_new(
+            solana_sdk::message::VersionedMessage::Legacy(message),
+            &[&keypair],
+        )
+        .unwrap();
+
+        let primary = Arc::new(RpcClient::new_sender(
+            PreflightFailureSender,
+            Default::default(),
+        ));
+        let sender = FanoutSender::new(vec![
+            primary,
+            Arc::new(RpcClient::new_mock("succeeds".into())),
+        ]);
+
+        let err = sender.send(&tx, Default::default()).await.unwrap_err();
+        assert!(matches!(
+            err,
+            SdkError::Rpc(ref inner) if inner.get_transaction_error().is_some()
+        ));
+        // the tx is still propagated
+        let stats = sender.stats();
+        assert_eq!((stats[0].sent, stats[0].errors), (0, 1));
+        assert_eq!((stats[1].sent, stats[1].errors), (1, 0));
+    }
+}
@@ -0,0 +1,820 @@
+//! Yellowstone (geyser) gRPC subscriptions
+//!
+//! Feeds `MarketMap`, `OracleMap`, `AccountMap` and `GlobalUserMap` updates from a single gRPC stream
+//! in place of individual Ws subscriptions. Requires the `grpc` feature.
+//!
+//! ```ignore
+//! let grpc = Arc::new(GrpcSubscriber::new(
+//!     "https://grpc.example.com:10000",
+//!     Some(x_token),
+//!     Arc::new(RpcClient::new(rpc_endpoint)),
+//! ));
+//! grpc.subscribe().await?;
+//!
+//! // all live data from the gRPC stream
+//! let client = DriftClient::new_with_grpc(Context::MainNet, rpc_client, wallet, Arc::clone(&grpc)).await?;
+//! let usermap = GlobalUserMap::new(commitment, rpc_endpoint, true, None).with_grpc(grpc);
+//! ```
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use dashmap::DashMap;
+use futures_util::{SinkExt, StreamExt};
+use log::{debug, error, warn};
+use solana_client::{nonblocking::rpc_client::RpcClient, rpc_filter::RpcFilterType};
+use solana_sdk::{
+    clock::Slot,
+    commitment_config::{CommitmentConfig, CommitmentLevel},
+    pubkey::Pubkey,
+};
+use tokio::sync::{
+    mpsc,
+    oneshot::{self, error::TryRecvError},
+};
+use tonic::transport::ClientTlsConfig;
+use yellowstone_grpc_client::GeyserGrpcClient;
+use yellowstone_grpc_proto::prelude::{
+    subscribe_request_filter_accounts_filter::Filter as AccountsFilter,
+    subscribe_request_filter_accounts_filter_memcmp::Data as MemcmpData,
+    subscribe_update::UpdateOneof, CommitmentLevel as GrpcCommitmentLevel, SubscribeRequest,
+    SubscribeRequestFilterAccounts, SubscribeRequestFilterAccountsFilter,
+    SubscribeRequestFilterAccountsFilterMemcmp, SubscribeRequestPing, SubscribeUpdate,
+    SubscribeUpdateAccount,
+};
+
+use crate::{
+    types::{SdkError, SdkResult},
+    websocket_account_subscriber::AccountUpdate,
+    UnsubHandle,
+};
+
+const LOG_TARGET: &str = "grpc";
+/// stream filter name of account subscriptions
+const ACCOUNTS_FILTER: &str = "accounts";
+/// stream filter name prefix of program subscriptions
+const PROGRAM_FILTER: &str = "program";
+
+/// An account update listener
+struct Watcher {
+    /// closed once the listener unsubscribes
+    unsub: oneshot::Receiver<()>,
+    on_update: Box<dyn Fn(&AccountUpdate) + Send>,
+}
+
+impl Watcher {
+    /// Call the listener with `update`
+    ///
+    /// Returns false if the listener unsubscribed
+    fn notify(&mut self, update: &AccountUpdate) -> bool {
+        let subscribed = matches!(self.unsub.try_recv(), Err(TryRecvError::Empty));
+        if subscribed {
+            (self.on_update)(update);
+        }
+        subscribed
+    }
+}
+
+/// A program accounts listener
+struct ProgramWatcher {
+    program_id: Pubkey,
+    filters: Vec<SubscribeRequestFilterAccountsFilter>,
+    watcher: Watcher,
+}
+
+/// Account and program listeners of a stream
+#[derive(Default)]
+struct Watchers {
+    accounts: Mutex<HashMap<Pubkey, Vec<Watcher>>>,
+    /// program listeners by stream filter name
+    programs: Mutex<HashMap<String, ProgramWatcher>>,
+    next_program_id: AtomicU64,
+    /// slot of the last update by account
+    account_slots: DashMap<Pubkey, Slot, ahash::RandomState>,
+    latest_slot: AtomicU64,
+}
+
+impl Watchers {
+    /// Build a stream request for all listeners
+    fn request(&self, commitment: GrpcCommitmentLevel) -> SubscribeRequest {
+        let mut filters = HashMap::<String, SubscribeRequestFilterAccounts>::new();
+
+        let accounts: Vec<String> = {
+            let mut accounts = self.accounts.lock().expect("acquired");
+            accounts.retain(|_, w| {
+                w.retain_mut(|w| matches!(w.unsub.try_recv(), Err(TryRecvError::Empty)));
+                !w.is_empty()
+            });
+            accounts.keys().map(|p| p.to_string()).collect()
+        };
+        // an empty account filter subscribes _all_ accounts
+        if !accounts.is_empty() {
+            filters.insert(
+                ACCOUNTS_FILTER.into(),
+                SubscribeRequestFilterAccounts {
+                    account: accounts,
+                    ..Default::default()
+                },
+            );
+        }
+
+        let mut programs = self.programs.lock().expect("acquired");
+        programs.retain(|_, p| matches!(p.watcher.unsub.try_recv(), Err(TryRecvError::Empty)));
+        for (name, program) in programs.iter() {
+            filters.insert(
+                name.clone(),
+                SubscribeRequestFilterAccounts {
+                    owner: vec![program.program_id.to_string()],
+                    filters: program.filters.clone(),
+                    ..Default::default()
+                },
+            );
+        }
+
+        SubscribeRequest {
+            accounts: filters,
+            commitment: Some(commitment as i32),
+            ..Default::default()
+        }
+    }
+
+    /// Dispatch a stream `update` to its listeners
+    ///
+    /// Updates older than the last seen update of the account are dropped
+    fn handle(&self, update: SubscribeUpdate) {
+        let Some(UpdateOneof::Account(SubscribeUpdateAccount {
+            account: Some(account),
+            slot,
+            ..
+        })) = update.update_oneof
+        else {
+            return;
+        };
+        let (Ok(pubkey), Ok(owner)) = (
+            Pubkey::try_from(account.pubkey.as_slice()),
+            Pubkey::try_from(account.owner.as_slice()),
+        ) else {
+            warn!(target: LOG_TARGET, "invalid account update");
+            return;
+        };
+
+        let mut last_slot = self.account_slots.entry(pubkey).or_default();
+        if slot < *last_slot {
+            debug!(target: LOG_TARGET, "stale update: {pubkey:?}@{slot}");
+            return;
+        }
+        *last_slot = slot;
+        drop(last_slot);
+        self.latest_slot.fetch_max(slot, Ordering::Relaxed);
+
+        let account_update = AccountUpdate {
+            pubkey,
+            owner,
+            lamports: account.lamports,
+            data: account.data,
+            slot,
+        };
+
+        for filter in update.filters {
+            if filter == ACCOUNTS_FILTER {
+                let mut accounts = self.accounts.lock().expect("acquired");
+                if let Some(watchers) = accounts.get_mut(&pubkey) {
+                    watchers.retain_mut(|w| w.notify(&account_update));
+                }
+            } else {
+                let mut programs = self.programs.lock().expect("acquired");
+                if let Some(program) = programs.get_mut(&filter) {
+                    if !program.watcher.notify(&account_update) {
+                        programs.remove(&filter);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Subscribes to account updates over a single Yellowstone gRPC stream
+///
+/// Listeners may be added before or after the stream is started
+pub struct GrpcSubscriber {
+    endpoint: String,
+    x_token: Option<String>,
+    commitment: CommitmentConfig,
+    /// RPC client for seeding initial account values
+    rpc: Arc<RpcClient>,
+    watchers: Arc<Watchers>,
+    /// sends updated requests to the stream task
+    requests: Mutex<Option<mpsc::UnboundedSender<SubscribeRequest>>>,
+    unsub: Mutex<Option<UnsubHandle>>,
+}
+
+impl GrpcSubscriber {
+    pub const MAX_RECONNECTION_ATTEMPTS: u32 = 20;
+
+    /// Create a new `GrpcSubscriber`
+    ///
+    /// * `endpoint` - Yellowstone gRPC endpoint
+    /// * `x_token` - endpoint auth token, if required
+    /// * `rpc_client` - used to fetch initial account values, sets the stream commitment
+    pub fn new(endpoint: &str, x_token: Option<String>, rpc_client: Arc<RpcClient>) -> Self {
+        Self {
+            endpoint: endpoint.to_string(),
+            x_token,
+            commitment: rpc_client.commitment(),
+            rpc: rpc_client,
+            watchers: Default::default(),
+            requests: Mutex::default(),
+            unsub: Mutex::default(),
+        }
+    }
+
+    /// Return the slot of the latest received update
+    pub fn get_latest_slot(&self) -> Slot {
+        self.watchers.latest_slot.load(Ordering::Relaxed)
+    }
+
+    /// Returns true if the stream is started
+    pub fn is_subscribed(&self) -> bool {
+        self.unsub.lock().expect("acquired").is_some()
+    }
+
+    /// Start the stream task
+    ///
+    /// Reconnects with the latest listeners if the stream ends
+    pub async fn subscribe(&self) -> SdkResult<()> {
+        if self.is_subscribed() {
+            return Ok(());
+        }
+
+        let (requests_tx, mut requests_rx) = mpsc::unbounded_channel::<SubscribeRequest>();
+        let (unsub_tx, mut unsub_rx) = oneshot::channel::<()>();
+        let (connected_tx, connected_rx) = oneshot::channel::<SdkResult<()>>();
+
+        tokio::spawn({
+            let endpoint = self.endpoint.clone();
+            let x_token = self.x_token.clone();
+            let commitment = to_grpc_commitment(self.commitment.commitment);
+            let watchers = Arc::clone(&self.watchers);
+            let base_delay = Duration::from_secs(2);
+            let mut connected_tx = Some(connected_tx);
+            let mut attempt = 0;
+
+            async move {
+                let exit_status = 'outer: loop {
+                    let request = watchers.request(commitment);
+                    let stream = async {
+                        let mut builder = GeyserGrpcClient::build_from_shared(endpoint.clone())
+                            .and_then(|b| b.x_token(x_token.clone()))
+                            .map_err(|err| SdkError::Grpc(err.to_string()))?;
+                        if endpoint.starts_with("https") {
+                            builder = builder
+                                .tls_config(ClientTlsConfig::new().with_native_roots())
+                                .map_err(|err| SdkError::Grpc(err.to_string()))?;
+                        }
+                        let mut client = builder
+                            .connect()
+                            .await
+                            .map_err(|err| SdkError::Grpc(err.to_string()))?;
+                        client
+                            .subscribe_with_request(Some(request))
+                            .await
+                            .map_err(|err| SdkError::Grpc(err.to_string()))
+                    }
+                    .await;
+
+                    let (mut sink, mut updates) = match stream {
+                        Ok(stream) => {
+                            attempt = 0;
+                            if let Some(tx) = connected_tx.take() {
+                                let _ = tx.send(Ok(()));
+                            }
+                            stream
+                        }
+                        Err(err) => {
+                            // fail early on the first connection
+                            if let Some(tx) = connected_tx.take() {
+                                let _ = tx.send(Err(err));
+                                break 'outer Ok(());
+                            }
+                            warn!(target: LOG_TARGET, "couldn't connect: {err:?}, retrying...");
+                            attempt += 1;
+                            if attempt >= Self::MAX_RECONNECTION_ATTEMPTS {
+                                break 'outer Err(SdkError::MaxReconnectionAttemptsReached);
+                            }
+                            tokio::time::sleep(base_delay).await;
+                            continue;
+                        }
+                    };
+                    debug!(target: LOG_TARGET, "stream connected");
+
+                    loop {
+                        tokio::select! {
+                            biased;
+                            _ = &mut unsub_rx => {
+                                debug!(target: LOG_TARGET, "unsubscribing");
+                                break 'outer Ok(());
+                            }
+                            Some(request) = requests_rx.recv() => {
+                                if let Err(err) = sink.send(request).await {
+                                    warn!(target: LOG_TARGET, "couldn't update stream: {err:?}");
+                                    break;
+                                }
+                            }
+                            update = updates.next() => {
+                                match update {
+                                    Some(Ok(update)) => {
+                                        if let Some(UpdateOneof::Ping(_)) = update.update_oneof {
+                                            // keep the connection alive
+                                            let ping = SubscribeRequest {
+                                                ping: Some(SubscribeRequestPing { id: 1 }),
+                                                ..Default::default()
+                                            };
+                                            if sink.send(ping).await.is_err() {
+                                                break;
+                                            }
+                                        } else {
+                                            watchers.handle(update);
+                                        }
+                                    }
+                                    Some(Err(status)) => {
+                                        warn!(target: LOG_TARGET, "stream error: {status:?}");
+                                        break;
+                                    }
+                                    None => {
+                                        warn!(target: LOG_TARGET, "stream ended");
+                                        break;
+                                    }
+                                }
+                            }
+                        }
+                    }
+
+                    tokio::time::sleep(base_delay).await;
+                };
+
+                if let Err(err) = exit_status {
+                    error!(target: LOG_TARGET, "stream task failed: {err:?}");
+                }
+            }
+        });
+
+        connected_rx
+            .await
+            .map_err(|_| SdkError::Grpc("stream task ended".into()))??;
+        *self.requests.lock().expect("acquired") = Some(requests_tx);
+        *self.unsub.lock().expect("acquired") = Some(unsub_tx);
+
+        Ok(())
+    }
+
+    /// Stop the stream task
+    pub fn unsubscribe(&self) {
+        if let Some(unsub) = self.unsub.lock().expect("acquired").take() {
+            if unsub.send(()).is_err() {
+                error!(target: LOG_TARGET, "couldn't unsubscribe");
+            }
+        }
+        self.requests.lock().expect("acquired").take();
+    }
+
+    /// Call `on_update` with updates of account `pubkey`
+    ///
+    /// * `sync` - fetch the current value of the account first
+    ///
+    /// Returns error if `sync` is set and the account does not exist
+    pub async fn watch_account<F>(
+        &self,
+        pubkey: Pubkey,
+        sync: bool,
+        on_update: F,
+    ) -> SdkResult<UnsubHandle>
+    where
+        F: 'static + Send + Fn(&AccountUpdate),
+    {
+        if sync {
+            let response = self
+                .rpc
+                .get_account_with_commitment(&pubkey, self.commitment)
+                .await?;
+            let account = response.value.ok_or(SdkError::InvalidAccount)?;
+            on_update(&AccountUpdate {
+                pubkey,
+                owner: account.owner,
+                lamports: account.lamports,
+                data: account.data,
+                slot: response.context.slot,
+            });
+        }
+
+        let (unsub_tx, unsub_rx) = oneshot::channel();
+        self.watchers
+            .accounts
+            .lock()
+            .expect("acquired")
+            .entry(pubkey)
+            .or_default()
+            .push(Watcher {
+                unsub: unsub_rx,
+                on_update: Box::new(on_update),
+            });
+        self.update_stream();
+
+        Ok(unsub_tx)
+    }
+
+    /// Call `on_update` with updates of `program_id` accounts matching all `filters`
+    ///
+    /// Returns error if a filter has no gRPC equivalent
+    pub fn watch_program<F>(
+        &self,
+        program_id: Pubkey,
+        filters: Vec<RpcFilterType>,
+        on_update: F,
+    ) -> SdkResult<UnsubHandle>
+    where
+        F: 'static + Send + Fn(&AccountUpdate),
+    {
+        // a dropped filter would widen the subscription to all program accounts
+        let filters = filters
+            .iter()
+            .map(to_grpc_filter)
+            .collect::<SdkResult<Vec<_>>>()?;
+        let (unsub_tx, unsub_rx) = oneshot::channel();
+        let id = self
+            .watchers
+            .next_program_id
+            .fetch_add(1, Ordering::Relaxed);
+        self.watchers.programs.lock().expect("acquired").insert(
+            format!("{PROGRAM_FILTER}-{id}"),
+            ProgramWatcher {
+                program_id,
+                filters,
+                watcher: Watcher {
+                    unsub: unsub_rx,
+                    on_update: Box::new(on_update),
+                },
+            },
+        );
+        self.update_stream();
+
+        Ok(unsub_tx)
+    }
+
+    /// Send the latest listeners to the stream, if started
+    fn update_stream(&self) {
+        if let Some(ref requests) = *self.requests.lock().expect("acquired") {
+            let request = self
+                .watchers
+                .request(to_grpc_commitment(self.commitment.commitment));
+            let _ = requests.send(request);
+        }
+    }
+}
+
+impl Drop for GrpcSubscriber {
+    fn drop(&mut self) {
+        self.unsubscribe();
+    }
+}
+
+fn to_grpc_commitment(commitment: CommitmentLevel) -> GrpcCommitmentLevel {
+    match commitment {
+        CommitmentLevel::Processed => GrpcCommitmentLevel::Processed,
+        CommitmentLevel::Confirmed => GrpcCommitmentLevel::Confirmed,
+        CommitmentLevel::Finalized => GrpcCommitmentLevel::Finalized,
+    }
+}
+
+/// Convert an RPC account filter to its gRPC equivalent
+///
+/// Returns error for filters without a gRPC equivalent e.g. undecodable memcmp bytes
+fn to_grpc_filter(filter: &RpcFilterType) -> SdkResult<SubscribeRequestFilterAccountsFilter> {
+    let filter = match filter {
+        RpcFilterType::DataSize(size) => AccountsFilter::Datasize(*size),
+        RpcFilterType::Memcmp(memcmp) => {
+            let bytes = memcmp
+                .bytes()
+                .ok_or_else(|| SdkError::Grpc(format!("invalid memcmp filter: {memcmp:?}")))?;
+            AccountsFilter::Memcmp(SubscribeRequestFilterAccountsFilterMemcmp {
+                offset: memcmp.offset() as u64,
+                data: Some(MemcmpData::Bytes(bytes.into_owned())),
+            })
+        }
+        RpcFilterType::TokenAccountState => AccountsFilter::TokenAccountState(true),
+    };
+
+    Ok(SubscribeRequestFilterAccountsFilter {
+        filter: Some(filter),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{pin::Pin, sync::atomic::AtomicUsize};
+
+    use futures_util::{stream, Stream};
+    use solana_client::rpc_filter::{Memcmp, MemcmpEncodedBytes};
+    use tokio::net::TcpListener;
+    use tonic::{
+        transport::{server::TcpIncoming, Server},
+        Request, Response, Status, Streaming,
+    };
+    use yellowstone_grpc_proto::prelude::{
+        geyser_server::{Geyser, GeyserServer},
+        GetBlockHeightRequest, GetBlockHeightResponse, GetLatestBlockhashRequest,
+        GetLatestBlockhashResponse, GetSlotRequest, GetSlotResponse, GetVersionRequest,
+        GetVersionResponse, IsBlockhashValidRequest, IsBlockhashValidResponse, PingRequest,
+        PongResponse, SubscribeUpdateAccountInfo,
+    };
+
+    use super::*;
+    use crate::{memcmp::get_user_filter, PROGRAM_ID};
+
+    type UpdateSender = mpsc::UnboundedSender<Result<SubscribeUpdate, Status>>;
+
+    /// Geyser server forwarding subscribe requests to the test
+    ///
+    /// Each connection is handed to the test as a sender of stream updates, dropping it ends the stream
+    struct MockGeyser {
+        requests: mpsc::UnboundedSender<SubscribeRequest>,
+        connections: mpsc::UnboundedSender<UpdateSender>,
+    }
+
+    #[tonic::async_trait]
+    impl Geyser for MockGeyser {
+        type SubscribeStream =
+            Pin<Box<dyn Stream<Item = Result<SubscribeUpdate, Status>> + Send + 'static>>;
+
+        async fn subscribe(
+            &self,
+            request: Request<Streaming<SubscribeRequest>>,
+        ) -> Result<Response<Self::SubscribeStream>, Status> {
+            let mut inbound = request.into_inner();
+            let requests = self.requests.clone();
+            tokio::spawn(async move {
+                while let Ok(Some(request)) = inbound.message().await {
+                    let _ = requests.send(request);
+                }
+            });
+
+            let (updates_tx, updates_rx) = mpsc::unbounded_channel();
+            let _ = self.connections.send(updates_tx);
+            let updates = stream::unfold(updates_rx, |mut rx| async move {
+                rx.recv().await.map(|update| (update, rx))
+            });
+
+            Ok(Response::new(Box::pin(updates)))
+        }
+
+        async fn ping(&self, _: Request<PingRequest>) -> Result<Response<PongResponse>, Status> {
+            Err(Status::unimplemented("ping"))
+        }
+
+        async fn get_latest_blockhash(
+            &self,
+            _: Request<GetLatestBlockhashRequest>,
+        ) -> Result<Response<GetLatestBlockhashResponse>, Status> {
+            Err(Status::unimplemented("get_latest_blockhash"))
+        }
+
+        async fn get_block_height(
+            &self,
+            _: Request<GetBlockHeightRequest>,
+        ) -> Result<Response<GetBlockHeightResponse>, Status> {
+            Err(Status::unimplemented("get_block_height"))
+        }
+
+        async fn get_slot(
+            &self,
+            _: Request<GetSlotRequest>,
+        ) -> Result<Response<GetSlotResponse>, Status> {
+            Err(Status::unimplemented("get_slot"))
+        }
+
+        async fn is_blockhash_valid(
+            &self,
+            _: Request<IsBlockhashValidRequest>,
+        ) -> Result<Response<IsBlockhashValidResponse>, Status> {
+            Err(Status::unimplemented("is_blockhash_valid"))
+        }
+
+        async fn get_version(
+            &self,
+            _: Request<GetVersionRequest>,
+        ) -> Result<Response<GetVersionResponse>, Status> {
+            Err(Status::unimplemented("get_version"))
+        }
+    }
+
+    /// Start a local mock Geyser server
+    ///
+    /// Returns its URL, and receivers of subscribe requests and stream connections
+    async fn serve() -> (
+        String,
+        mpsc::UnboundedReceiver<SubscribeRequest>,
+        mpsc::UnboundedReceiver<UpdateSender>,
+    ) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let (requests_tx, requests_rx) = mpsc::unbounded_channel();
+        let (connections_tx, connections_rx) = mpsc::unbounded_channel();
+        let geyser = MockGeyser {
+            requests: requests_tx,
+            connections: connections_tx,
+        };
+        let incoming = TcpIncoming::from_listener(listener, true, None).unwrap();
+        tokio::spawn(
+            Server::builder()
+                .add_service(GeyserServer::new(geyser))
+                .serve_with_incoming(incoming),
+        );
+
+        (url, requests_rx, connections_rx)
+    }
+
+    /// Receive the next item of `rx`, panics after a timeout
+    async fn next<T>(rx: &mut mpsc::UnboundedReceiver<T>) -> T {
+        tokio::time::timeout(Duration::from_secs(10), rx.recv())
+            .await
+            .expect("received in time")
+            .expect("channel open")
+    }
+
+    /// Wait until `counter` reaches `count`, panics after a timeout
+    async fn wait_for(counter: &AtomicUsize, count: usize) {
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while counter.load(Ordering::Relaxed) < count {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("updated in time");
+    }
+
+    fn account_update(filter: &str, pubkey: Pubkey, slot: Slot) -> SubscribeUpdate {
+        SubscribeUpdate {
+            filters: vec![filter.into()],
+            update_oneof: Some(UpdateOneof::Account(SubscribeUpdateAccount {
+                account: Some(SubscribeUpdateAccountInfo {
+                    pubkey: pubkey.to_bytes().to_vec(),
+                    owner: PROGRAM_ID.to_bytes().to_vec(),
+                    lamports: 1_000,
+                    data: vec![1, 2, 3],
+                    ..Default::default()
+                }),
+                slot,
+                ..Default::default()
+            })),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn dispatches_slot_ordered_updates() {
+        let grpc = GrpcSubscriber::new(
+            "http://localhost:10000",
+            None,
+            Arc::new(RpcClient::new_mock("succeeds".into())),
+        );
+        let account = Pubkey::new_unique();
+        let account_updates = Arc::new(AtomicUsize::new(0));
+        let account_unsub = grpc
+            .watch_account(account, false, {
+                let account_updates = Arc::clone(&account_updates);
+                move |update| {
+                    assert_eq!(update.data, vec![1, 2, 3]);
+                    account_updates.fetch_add(1, Ordering::Relaxed);
+                }
+            })
+            .await
+            .unwrap();
+        let program_updates = Arc::new(AtomicUsize::new(0));
+        let _program_unsub = grpc
+            .watch_program(PROGRAM_ID, vec![get_user_filter()], {
+                let program_updates = Arc::clone(&program_updates);
+                move |_| {
+                    program_updates.fetch_add(1, Ordering::Relaxed);
+                }
+            })
+            .unwrap();
+
+        let request = grpc.watchers.request(GrpcCommitmentLevel::Confirmed);
+        assert_eq!(
+            request.accounts[ACCOUNTS_FILTER].account,
+            vec![account.to_string()]
+        );
+        let program_filter = &request.accounts["program-0"];
+        assert_eq!(program_filter.owner, vec![PROGRAM_ID.to_string()]);
+        assert_eq!(program_filter.filters.len(), 1);
+
+        grpc.watchers
+            .handle(account_update(ACCOUNTS_FILTER, account, 100));
+        grpc.watchers
+            .handle(account_update("program-0", Pubkey::new_unique(), 101));
+        // stale
+        grpc.watchers
+            .handle(account_update(ACCOUNTS_FILTER, account, 99));
+        assert_eq!(account_updates.load(Ordering::Relaxed), 1);
+        assert_eq!(program_updates.load(Ordering::Relaxed), 1);
+        assert_eq!(grpc.get_latest_slot(), 101);
+
+        // unsubscribed listeners are dropped
+        let _ = account_unsub.send(());
+        grpc.watchers
+            .handle(account_update(ACCOUNTS_FILTER, account, 102));
+        assert_eq!(account_updates.load(Ordering::Relaxed), 1);
+        let request = grpc.watchers.request(GrpcCommitmentLevel::Confirmed);
+        assert!(!request.accounts.contains_key(ACCOUNTS_FILTER));
+    }
+
+    #[tokio::test]
+    async fn streams_from_geyser_server() {
+        let (url, mut requests, mut connections) = serve().await;
+        let grpc =
+            GrpcSubscriber::new(&url, None, Arc::new(RpcClient::new_mock("succeeds".into())));
+
+        let account = Pubkey::new_unique();
+        let account_updates = Arc::new(AtomicUsize::new(0));
+        let _account_unsub = grpc
+            .watch_account(account, false, {
+                let account_updates = Arc::clone(&account_updates);
+                move |_| {
+                    account_updates.fetch_add(1, Ordering::Relaxed);
+                }
+            })
+            .await
+            .unwrap();
+
+        // initial request has the listeners added before the stream started
+        grpc.subscribe().await.unwrap();
+        let connection = next(&mut connections).await;
+        let request = next(&mut requests).await;
+        assert_eq!(
+            request.accounts[ACCOUNTS_FILTER].account,
+            vec![account.to_string()]
+        );
+        assert_eq!(
+            request.commitment,
+            Some(GrpcCommitmentLevel::Finalized as i32)
+        );
+        connection
+            .send(Ok(account_update(ACCOUNTS_FILTER, account, 100)))
+            .unwrap();
+        wait_for(&account_updates, 1).await;
+
+        // listener added after the stream started updates the stream
+        let program_updates = Arc::new(AtomicUsize::new(0));
+        let _program_unsub = grpc
+            .watch_program(PROGRAM_ID, vec![get_user_filter()], {
+                let program_updates = Arc::clone(&program_updates);
+                move |_| {
+                    program_updates.fetch_add(1, Ordering::Relaxed);
+                }
+            })
+            .unwrap();
+        let request = next(&mut requests).await;
+        assert!(request.accounts.contains_key(ACCOUNTS_FILTER));
+        assert_eq!(
+            request.accounts["program-0"].owner,
+            vec![PROGRAM_ID.to_string()]
+        );
+        connection
+            .send(Ok(account_update("program-0", Pubkey::new_unique(), 101)))
+            .unwrap();
+        wait_for(&program_updates, 1).await;
+
+        // stream ends, reconnects with all listeners
+        drop(connection);
+        let connection = next(&mut connections).await;
+        let request = next(&mut requests).await;
+        assert!(request.accounts.contains_key(ACCOUNTS_FILTER));
+        assert!(request.accounts.contains_key("program-0"));
+        connection
+            .send(Ok(account_update(ACCOUNTS_FILTER, account, 102)))
+            .unwrap();
+        wait_for(&account_updates, 2).await;
+        assert_eq!(grpc.get_latest_slot(), 102);
+
+        grpc.unsubscribe();
+    }
+
+    #[test]
+    fn watch_program_rejects_invalid_filter() {
+        let grpc = GrpcSubscriber::new(
+            "http://localhost:10000",
+            None,
+            Arc::new(RpcClient::new_mock("succeeds".into())),
+        );
+        // not base58
+        let filter =
+            RpcFilterType::Memcmp(Memcmp::new(0, MemcmpEncodedBytes::Base58("0OIl".into())));
+
+        let result = grpc.watch_program(PROGRAM_ID, vec![get_user_filter(), filter], |_| {});
+        assert!(matches!(result, Err(SdkError::Grpc(_))));
+    }
+}
@@ -19,8 +19,11 @@ use solana_sdk::{
 use crate::{
     accounts::User,
     build_accounts,
+    compute_units::ComputeUnitMargin,
     constants::{self, state_account, JIT_PROXY_ID},
-    drift_idl, DriftClient, MarketId, MarketType, PostOnlyParam, ReferrerInfo, SdkError, SdkResult,
+    drift_idl,
+    tx_sender::{TxOutcome, TxSenderConfig},
+    DriftClient, MarketId, MarketType, PostOnlyParam, ReferrerInfo, SdkError, SdkResult,
     TransactionBuilder, Wallet,
 };
 
@@ -80,6 +83,7 @@ pub struct JitProxyClient {
     drift_client: DriftClient,
     config: RpcSendTransactionConfig,
     cu_params: Option<ComputeBudgetParams>,
+    cu_margin: Option<ComputeUnitMargin>,
 }
 
 impl JitProxyClient {
@@ -92,6 +96,7 @@ impl JitProxyClient {
             drift_client,
             config: config.unwrap_or_default(),
             cu_params,
+            cu_margin: None,
         }
     }
 
@@ -103,6 +108,13 @@ impl JitProxyClient {
         self.cu_params = Some(cu_params);
     }
 
+    /// Set the compute unit limit of jit txs by simulation plus `cu_margin`
+    ///
+    /// overrides the static `cu_limit` of `ComputeBudgetParams`, `None` disables estimation
+    pub fn update_cu_margin(&mut self, cu_margin: Option<ComputeUnitMargin>) {
+        self.cu_margin = cu_margin;
+    }
+
     /// Build a jit tx
     ///
     /// `params` JIT order params
@@ -218,15 +230,53 @@ impl JitProxyClient {
         authority: &Pubkey,
         sub_account_id: Option<u16>,
     ) -> SdkResult<Signature> {
+        let tx = self
+            .prepare_jit_tx(params, authority, sub_account_id)
+            .await?;
+        self.drift_client
+            .sign_and_send_with_config(tx, None, self.config)
+            .await
+    }
+
+    /// Send a jit tx given params, rebroadcasting until confirmed or expired
+    ///
+    /// `sender_config` rebroadcast and confirmation config
+    ///
+    /// Returns the tx outcome
+    pub async fn jit_and_confirm(
+        &self,
+        params: JitIxParams,
+        authority: &Pubkey,
+        sub_account_id: Option<u16>,
+        sender_config: TxSenderConfig,
+    ) -> SdkResult<TxOutcome> {
+        let tx = self
+            .prepare_jit_tx(params, authority, sub_account_id)
+            .await?;
+        self.drift_client.sign_and_confirm(tx, sender_config).await
+    }
+
+    /// Build a jit tx for the given sub-account, estimating compute units if configured
+    async fn prepare_jit_tx(
+        &self,
+        params: JitIxParams,
+        authority: &Pubkey,
+        sub_account_id: Option<u16>,
+    ) -> SdkResult<VersionedMessage> {
         let sub_account =
             Wallet::derive_user_account(authority, sub_account_id.unwrap_or_default());
         let sub_account_data = self.drift_client.get_user_account(&sub_account).await?;
         let tx = self
             .build_jit_tx(params, authority, (&sub_account, &sub_account_data))
             .await?;
-        self.drift_client
-            .sign_and_send_with_config(tx, None, self.config)
-            .await
+        match self.cu_margin {
+            Some(cu_margin) => {
+                self.drift_client
+                    .with_estimated_compute_units(tx, cu_margin)
+                    .await
+            }
+            None => Ok(tx),
+        }
     }
 }
 
@@ -5,16 +5,24 @@ use std::{borrow::Cow, sync::Arc, time::Duration};
 use anchor_lang::{AccountDeserialize, InstructionData};
 use futures_util::TryFutureExt;
 use log::debug;
-use solana_client::{nonblocking::rpc_client::RpcClient, rpc_response::Response};
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::{
+    nonblocking::rpc_client::RpcClient,
+    rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig, RpcSimulateTransactionConfig},
+    rpc_filter::RpcFilterType,
+    rpc_response::{Response, RpcSimulateTransactionResult},
+};
 use solana_sdk::{
     account::Account,
     clock::Slot,
+    commitment_config::CommitmentConfig,
     compute_budget::ComputeBudgetInstruction,
     hash::Hash,
     instruction::{AccountMeta, Instruction},
     message::{v0, Message, VersionedMessage},
     signature::{keypair_from_seed, Keypair, Signature},
-    signer::Signer,
+    signer::{Signer, SignerError},
+    system_instruction,
     transaction::VersionedTransaction,
 };
 pub use solana_sdk::{address_lookup_table::AddressLookupTableAccount, pubkey::Pubkey};
@@ -22,13 +30,20 @@ pub use solana_sdk::{address_lookup_table::AddressLookupTableAccount, pubkey::Pu
 use crate::{
     account_map::AccountMap,
     blockhash_subscriber::BlockhashSubscriber,
+    bundle::BundleSender,
+    compute_units::ComputeUnitMargin,
     constants::{
-        derive_perp_market_account, derive_spot_market_account, state_account, MarketExt,
-        ProgramData, PROGRAM_ID,
+        derive_perp_market_account, derive_spot_market_account, high_leverage_mode_config_account,
+        state_account, MarketExt, ProgramData, PROGRAM_ID,
     },
     drift_idl::traits::ToAccountMetas,
+    failover::RpcEndpoints,
+    fanout::{EndpointStats, FanoutSender},
     marketmap::MarketMap,
+    nonce::NonceData,
     oraclemap::{Oracle, OracleMap},
+    simulation::SimulationReport,
+    tx_sender::{TxOutcome, TxSender, TxSenderConfig},
     types::{
         accounts::{PerpMarket, SpotMarket, State, User, UserStats},
         DataAndSlot, MarketType, *,
@@ -38,6 +53,9 @@ use crate::{
 
 // utils
 pub mod async_utils;
+pub mod compute_units;
+pub mod failover;
+pub mod fanout;
 pub mod ffi;
 pub mod math;
 pub mod memcmp;
@@ -59,18 +77,36 @@ pub mod blockhash_subscriber;
 pub mod event_subscriber;
 pub mod priority_fee_subscriber;
 
+pub mod bundle;
 pub mod jit_client;
+pub mod nonce;
+pub mod rfq;
+pub mod signer;
+pub mod simulation;
+pub mod swap;
+pub mod swift;
+pub mod tx_sender;
 
 pub mod marketmap;
 pub mod oraclemap;
+pub mod pyth_pull;
 pub mod slot_subscriber;
 pub mod usermap;
 
 // wrappers
 pub mod account_map;
+pub mod account_provider;
 
+#[cfg(feature = "admin")]
+pub mod admin;
 #[cfg(feature = "dlob")]
 pub mod dlob;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+#[cfg(any(test, feature = "mock"))]
+pub mod mock;
+#[cfg(any(test, feature = "mock"))]
+pub mod replay;
 
 /// DriftClient
 ///
@@ -105,6 +141,8 @@ pub struct DriftClient {
     pub context: Context,
     backend: &'static DriftClientBackend,
     wallet: Wallet,
+    /// additional tx send endpoints, if configured
+    send_endpoints: Option<Arc<FanoutSender>>,
 }
 
 impl DriftClient {
@@ -116,15 +154,203 @@ impl DriftClient {
     pub async fn new(context: Context, rpc_client: RpcClient, wallet: Wallet) -> SdkResult<Self> {
         // check URL format here to fail early, otherwise happens at request time.
         let _ = get_http_url(&rpc_client.url())?;
+        let account_map = AccountMap::new(rpc_client.url(), rpc_client.commitment());
         Ok(Self {
             backend: Box::leak(Box::new(
-                DriftClientBackend::new(context, Arc::new(rpc_client)).await?,
+                DriftClientBackend::new(context, Arc::new(rpc_client), account_map).await?,
             )),
             context,
             wallet,
+            send_endpoints: None,
+        })
+    }
+
+    /// Create a new `DriftClient` instance with live data served by a Yellowstone gRPC stream
+    ///
+    /// Account, market and oracle subscriptions use `grpc` in place of Ws.
+    /// The caller is responsible for starting the stream, see `GrpcSubscriber::subscribe`
+    ///
+    /// * `context` - devnet or mainnet
+    /// * `rpc_client` - an RpcClient instance
+    /// * `wallet` - wallet to use for tx signing convenience
+    /// * `grpc` - gRPC stream subscriber
+    #[cfg(feature = "grpc")]
+    pub async fn new_with_grpc(
+        context: Context,
+        rpc_client: RpcClient,
+        wallet: Wallet,
+        grpc: Arc<grpc::GrpcSubscriber>,
+    ) -> SdkResult<Self> {
+        let _ = get_http_url(&rpc_client.url())?;
+        let account_map =
+            AccountMap::new(rpc_client.url(), rpc_client.commitment()).with_grpc(Arc::clone(&grpc));
+        let backend = DriftClientBackend::new(context, Arc::new(rpc_client), account_map)
+            .await?
+            .with_grpc(grpc);
+        Ok(Self {
+            backend: Box::leak(Box::new(backend)),
+            context,
+            wallet,
+            send_endpoints: None,
+        })
+    }
+
+    /// Create a new `DriftClient` instance served by in-memory `accounts`, for offline testing
+    ///
+    /// Default `State` and lookup table accounts are seeded if not already set
+    ///
+    /// * `context` - devnet or mainnet
+    /// * `accounts` - in-memory accounts, updates are pushed to subscribers immediately
+    /// * `wallet` - wallet to use for tx signing convenience
+    #[cfg(any(test, feature = "mock"))]
+    pub async fn new_mock(
+        context: Context,
+        accounts: Arc<mock::MockAccounts>,
+        wallet: Wallet,
+    ) -> SdkResult<Self> {
+        if accounts.get_account(state_account()).is_none() {
+            accounts.set_state(&State::default());
+        }
+        if accounts.get_account(&context.lut()).is_none() {
+            // lookup table meta without addresses
+            accounts.set_account(
+                context.lut(),
+                Account {
+                    lamports: 1,
+                    data: vec![0; 56],
+                    owner: solana_sdk::address_lookup_table::program::ID,
+                    executable: false,
+                    rent_epoch: 0,
+                },
+            );
+        }
+        let rpc_client = accounts.rpc_client();
+        let account_map = AccountMap::new(rpc_client.url(), rpc_client.commitment())
+            .with_provider(Arc::clone(&accounts));
+        let backend = DriftClientBackend::new(context, Arc::new(rpc_client), account_map)
+            .await?
+            .with_provider(accounts);
+        Ok(Self {
+            backend: Box::leak(Box::new(backend)),
+            context,
+            wallet,
+            send_endpoints: None,
+        })
+    }
+
+    /// Create a new `DriftClient` instance recording its RPC session with `recorder`
+    ///
+    /// Records JSON-RPC requests to `endpoint` and account, market and oracle subscription updates
+    ///
+    /// * `context` - devnet or mainnet
+    /// * `endpoint` - RPC endpoint url
+    /// * `wallet` - wallet to use for tx signing convenience
+    /// * `recorder` - records the session, see `replay::Recorder::save`
+    #[cfg(any(test, feature = "mock"))]
+    pub async fn new_recording(
+        context: Context,
+        endpoint: &str,
+        wallet: Wallet,
+        recorder: Arc<replay::Recorder>,
+    ) -> SdkResult<Self> {
+        let _ = get_http_url(endpoint)?;
+        let rpc_client = recorder.rpc_client(endpoint, CommitmentConfig::confirmed());
+        let provider = recorder.account_provider(endpoint, rpc_client.commitment())?;
+        let account_map = AccountMap::new(rpc_client.url(), rpc_client.commitment())
+            .with_provider(Arc::clone(&provider));
+        let backend = DriftClientBackend::new(context, Arc::new(rpc_client), account_map)
+            .await?
+            .with_provider(provider);
+        Ok(Self {
+            backend: Box::leak(Box::new(backend)),
+            context,
+            wallet,
+            send_endpoints: None,
+        })
+    }
+
+    /// Create a new `DriftClient` instance serving a recorded RPC session
+    ///
+    /// * `context` - devnet or mainnet, as recorded
+    /// * `replay` - the recorded session
+    /// * `wallet` - wallet to use for tx signing convenience
+    #[cfg(any(test, feature = "mock"))]
+    pub async fn new_replay(
+        context: Context,
+        replay: &replay::Replay,
+        wallet: Wallet,
+    ) -> SdkResult<Self> {
+        let rpc_client = replay.rpc_client();
+        let account_map = AccountMap::new(rpc_client.url(), rpc_client.commitment())
+            .with_provider(replay.accounts());
+        let backend = DriftClientBackend::new(context, Arc::new(rpc_client), account_map)
+            .await?
+            .with_provider(replay.accounts());
+        Ok(Self {
+            backend: Box::leak(Box::new(backend)),
+            context,
+            wallet,
+            send_endpoints: None,
         })
     }
 
+    /// Create a new `DriftClient` instance failing over between RPC `endpoints`
+    ///
+    /// Reads and Ws subscriptions use the highest priority healthy endpoint, see `failover::RpcEndpoints`
+    ///
+    /// * `context` - devnet or mainnet
+    /// * `endpoints` - (RPC, Ws) endpoint url pairs, highest priority first
+    /// * `commitment` - commitment of reads and subscriptions
+    /// * `wallet` - wallet to use for tx signing convenience
+    pub async fn new_with_endpoints(
+        context: Context,
+        endpoints: Vec<(String, String)>,
+        commitment: CommitmentConfig,
+        wallet: Wallet,
+    ) -> SdkResult<Self> {
+        let endpoints = Arc::new(RpcEndpoints::new(endpoints)?);
+        endpoints.spawn_health_check(RpcEndpoints::HEALTH_CHECK_INTERVAL);
+        let rpc_client = endpoints.rpc_client(commitment);
+        let account_map =
+            AccountMap::new(rpc_client.url(), commitment).with_failover(Arc::clone(&endpoints));
+        let backend = DriftClientBackend::new(context, Arc::new(rpc_client), account_map)
+            .await?
+            .with_failover(endpoints);
+        Ok(Self {
+            backend: Box::leak(Box::new(backend)),
+            context,
+            wallet,
+            send_endpoints: None,
+        })
+    }
+
+    /// Send txs to `endpoints` in addition to the primary RPC client
+    ///
+    /// Signed txs are sent to all endpoints concurrently, reads continue to use the primary RPC client
+    pub fn with_send_endpoints(mut self, endpoints: Vec<RpcClient>) -> Self {
+        let mut all_endpoints = vec![Arc::clone(&self.backend.rpc_client)];
+        all_endpoints.extend(endpoints.into_iter().map(Arc::new));
+        self.send_endpoints = Some(Arc::new(FanoutSender::new(all_endpoints)));
+        self
+    }
+
+    /// Return tx send stats per endpoint (primary first)
+    ///
+    /// Empty if additional send endpoints are not configured
+    pub fn send_endpoint_stats(&self) -> Vec<EndpointStats> {
+        self.send_endpoints
+            .as_ref()
+            .map(|f| f.stats())
+            .unwrap_or_default()
+    }
+
+    /// Return the RPC endpoints of a client created with `new_with_endpoints`
+    ///
+    /// e.g. to fail over `GlobalUserMap` or `EventSubscriber` between the same endpoints
+    pub fn rpc_endpoints(&self) -> Option<Arc<RpcEndpoints>> {
+        self.backend.failover.clone()
+    }
+
     /// Starts background subscriptions for live blockhashes
     ///
     /// This is a no-op if already subscribed
@@ -369,6 +595,67 @@ impl DriftClient {
         self.backend.get_account(&user_stats_pubkey).await
     }
 
+    /// Get all user (sub)accounts of `authority` via RPC
+    ///
+    /// Returns (address, `User`) pairs sorted by sub-account id
+    pub async fn get_user_accounts_by_authority(
+        &self,
+        authority: &Pubkey,
+    ) -> SdkResult<Vec<(Pubkey, User)>> {
+        self.backend
+            .get_user_accounts(memcmp::get_user_authority_filter(authority))
+            .await
+    }
+
+    /// Get all user (sub)accounts delegated to `delegate` via RPC
+    ///
+    /// Returns (address, `User`) pairs sorted by authority and sub-account id
+    pub async fn get_delegated_accounts(
+        &self,
+        delegate: &Pubkey,
+    ) -> SdkResult<Vec<(Pubkey, User)>> {
+        self.backend
+            .get_user_accounts(memcmp::get_user_delegate_filter(delegate))
+            .await
+    }
+
+    /// Get the high leverage mode config account
+    ///
+    /// Subscribes to the account on first use, subsequent calls use the cached value
+    pub async fn get_high_leverage_mode_config(
+        &self,
+    ) -> SdkResult<types::accounts::HighLeverageModeConfig> {
+        let config_account = high_leverage_mode_config_account();
+        self.backend
+            .account_map
+            .subscribe_account(config_account)
+            .await?;
+        self.backend.try_get_account(config_account)
+    }
+
+    /// Get the number of users that may still enable high leverage mode
+    ///
+    /// Returns 0 if high leverage mode is reduce only
+    pub async fn high_leverage_mode_remaining_slots(&self) -> SdkResult<u32> {
+        let config = self.get_high_leverage_mode_config().await?;
+        if config.is_reduce_only() {
+            Ok(0)
+        } else {
+            Ok(config.remaining_slots())
+        }
+    }
+
+    /// Get the RFQ user account of a drift user
+    ///
+    /// * `account` - the drift user PDA (subaccount)
+    ///
+    /// Returns the deserialized account data (`RFQUser`)
+    pub async fn get_rfq_user(&self, account: &Pubkey) -> SdkResult<types::accounts::RFQUser> {
+        self.backend
+            .get_account(&rfq::derive_rfq_user(account))
+            .await
+    }
+
     /// Get the latest recent_block_hash
     /// uses latest cached if subscribed, otherwise falls back to network query
     pub async fn get_latest_blockhash(&self) -> SdkResult<Hash> {
@@ -404,10 +691,22 @@ impl DriftClient {
     /// Returns the signature on success
     pub async fn sign_and_send(&self, tx: VersionedMessage) -> SdkResult<Signature> {
         let recent_block_hash = self.backend.get_latest_blockhash().await?;
-        self.backend
-            .sign_and_send(self.wallet(), tx, recent_block_hash)
-            .await
-            .map_err(|err| err.to_out_of_sol_error().unwrap_or(err))
+        let result = match self.send_endpoints {
+            Some(ref fanout) => {
+                let tx = self.wallet.sign_tx(tx, recent_block_hash).await?;
+                let config = RpcSendTransactionConfig {
+                    preflight_commitment: Some(self.backend.rpc_client.commitment().commitment),
+                    ..Default::default()
+                };
+                fanout.send(&tx, config).await
+            }
+            None => {
+                self.backend
+                    .sign_and_send(self.wallet(), tx, recent_block_hash)
+                    .await
+            }
+        };
+        result.map_err(|err| err.to_out_of_sol_error().unwrap_or(err))
     }
 
     /// Sign and send a tx to the network
@@ -426,12 +725,99 @@ impl DriftClient {
             Some(h) => h,
             None => self.backend.get_latest_blockhash().await?,
         };
-        self.backend
-            .sign_and_send_with_config(self.wallet(), tx, recent_block_hash, config)
+        let result = match self.send_endpoints {
+            Some(ref fanout) => {
+                let tx = self.wallet.sign_tx(tx, recent_block_hash).await?;
+                fanout.send(&tx, config).await
+            }
+            None => {
+                self.backend
+                    .sign_and_send_with_config(self.wallet(), tx, recent_block_hash, config)
+                    .await
+            }
+        };
+        result.map_err(|err| err.to_out_of_sol_error().unwrap_or(err))
+    }
+
+    /// Sign `txs` with a shared recent blockhash and submit them as a bundle via `bundle_sender`
+    ///
+    /// Txs are executed atomically in the given order, the bundle should include a tip (see `TransactionBuilder::with_tip`)
+    ///
+    /// Returns the bundle id on success
+    pub async fn sign_and_send_bundle(
+        &self,
+        bundle_sender: &BundleSender,
+        txs: Vec<VersionedMessage>,
+    ) -> SdkResult<String> {
+        let recent_block_hash = self.backend.get_latest_blockhash().await?;
+        let mut signed_txs = Vec::with_capacity(txs.len());
+        for tx in txs {
+            signed_txs.push(self.wallet.sign_tx(tx, recent_block_hash).await?);
+        }
+        bundle_sender.send_bundle(&signed_txs).await
+    }
+
+    /// Simulate `tx` without landing it
+    ///
+    /// Returns a report of the tx compute units, errors, logs and drift events
+    pub async fn simulate(&self, tx: &VersionedMessage) -> SdkResult<SimulationReport> {
+        let response = self.backend.simulate(tx.clone()).await?;
+        Ok(SimulationReport::new(
+            response.context.slot,
+            response.value,
+            tx,
+        ))
+    }
+
+    /// Simulate `tx` returning the compute units consumed
+    ///
+    /// The tx compute unit limit is raised to the max. for simulation
+    pub async fn simulate_compute_units(&self, tx: &VersionedMessage) -> SdkResult<u64> {
+        let mut tx = tx.clone();
+        compute_units::set_compute_unit_limit(&mut tx, compute_units::MAX_COMPUTE_UNIT_LIMIT);
+        self.backend.simulate_compute_units(tx).await
+    }
+
+    /// Set the compute unit limit of `tx` from its simulated compute units plus `margin`
+    ///
+    /// Rewrites the tx compute unit limit ix (or adds one), must be called before signing
+    ///
+    /// Returns the updated tx or error if simulation fails
+    pub async fn with_estimated_compute_units(
+        &self,
+        mut tx: VersionedMessage,
+        margin: ComputeUnitMargin,
+    ) -> SdkResult<VersionedMessage> {
+        let units_consumed = self.simulate_compute_units(&tx).await?;
+        compute_units::set_compute_unit_limit(&mut tx, margin.apply(units_consumed));
+        Ok(tx)
+    }
+
+    /// Sign and send a tx, rebroadcasting until confirmed or its blockhash expires
+    ///
+    /// * `config` - rebroadcast and confirmation config
+    ///
+    /// Returns the tx outcome (landed, failed or expired)
+    pub async fn sign_and_confirm(
+        &self,
+        tx: VersionedMessage,
+        config: TxSenderConfig,
+    ) -> SdkResult<TxOutcome> {
+        self.tx_sender(config)
+            .sign_and_send(self.wallet(), tx)
             .await
             .map_err(|err| err.to_out_of_sol_error().unwrap_or(err))
     }
 
+    /// Create a `TxSender` using this client's RPC connection and send endpoints
+    pub fn tx_sender(&self, config: TxSenderConfig) -> TxSender {
+        let tx_sender = TxSender::new(Arc::clone(&self.backend.rpc_client), config);
+        match self.send_endpoints {
+            Some(ref fanout) => tx_sender.with_fanout(Arc::clone(fanout)),
+            None => tx_sender,
+        }
+    }
+
     /// Get spot market account
     ///
     /// uses latest cached value if subscribed, otherwise falls back to network query
@@ -551,6 +937,188 @@ impl DriftClient {
         ))
     }
 
+    /// Create the wallet authority's next sub-account
+    ///
+    /// Initializes the authority's `UserStats` account too, if it does not yet exist
+    ///
+    /// * `name` - display name of the sub-account (max. 32 bytes)
+    ///
+    /// Returns the new sub-account address and tx signature on success.
+    /// Returns error if the exchange sub-account limit is reached or the authority can't pay
+    /// the init fee (see `State::init_user_fee`) and rent
+    pub async fn create_next_sub_account(&self, name: &str) -> SdkResult<(Pubkey, Signature)> {
+        let authority = *self.wallet.authority();
+        let rpc_client = &self.backend.rpc_client;
+        let state: State = self.get_account_value(state_account()).await?;
+        if state.number_of_sub_accounts >= state.max_number_of_sub_accounts() {
+            return Err(SdkError::Generic(
+                "exchange sub-account limit reached".into(),
+            ));
+        }
+
+        let stats_account = Wallet::derive_stats_account(&authority);
+        let stats = rpc_client
+            .get_account_with_commitment(&stats_account, rpc_client.commitment())
+            .await?
+            .value;
+
+        let mut required_lamports = state.init_user_fee()
+            + rpc_client
+                .get_minimum_balance_for_rent_exemption(8 + std::mem::size_of::<User>())
+                .await?;
+        if stats.is_none() {
+            required_lamports += rpc_client
+                .get_minimum_balance_for_rent_exemption(8 + std::mem::size_of::<UserStats>())
+                .await?;
+        }
+        if rpc_client.get_balance(&authority).await? < required_lamports {
+            return Err(SdkError::OutOfSOL);
+        }
+
+        let sub_account_id = match &stats {
+            Some(account) => {
+                UserStats::try_deserialize(&mut account.data.as_slice())
+                    .map_err(|err| SdkError::Anchor(Box::new(err)))?
+                    .number_of_sub_accounts_created
+            }
+            None => 0,
+        };
+        let sub_account = Wallet::derive_user_account(&authority, sub_account_id);
+
+        let mut builder = TransactionBuilder::new(
+            self.program_data(),
+            sub_account,
+            Cow::Owned(User {
+                authority,
+                sub_account_id,
+                ..Default::default()
+            }),
+            false,
+        );
+        if stats.is_none() {
+            builder = builder.initialize_user_stats();
+        }
+        let tx = builder.initialize_user(sub_account_id, name, None).build();
+
+        let signature = self.sign_and_send(tx).await?;
+        Ok((sub_account, signature))
+    }
+
+    /// Create and fund a durable nonce account derived from the wallet authority and `seed`
+    ///
+    /// The wallet authority pays for and is the nonce authority of the new account
+    ///
+    /// * `seed` - seed to derive the nonce account address (max. 32 bytes)
+    /// * `lamports` - initial balance, defaults to the min. rent exempt balance
+    ///
+    /// Returns the new nonce account address and tx signature on success
+    pub async fn create_nonce_account(
+        &self,
+        seed: &str,
+        lamports: Option<u64>,
+    ) -> SdkResult<(Pubkey, Signature)> {
+        let rent_exempt_lamports = self
+            .backend
+            .rpc_client
+            .get_minimum_balance_for_rent_exemption(solana_sdk::nonce::State::size())
+            .await?;
+        let (nonce_account, ixs) = nonce::create_nonce_account_ixs(
+            self.wallet.authority(),
+            seed,
+            lamports.unwrap_or_default().max(rent_exempt_lamports),
+        )?;
+        let tx = VersionedMessage::Legacy(Message::new(&ixs, Some(self.wallet.authority())));
+
+        let signature = self.sign_and_send(tx).await?;
+        Ok((nonce_account, signature))
+    }
+
+    /// Fetch and decode the durable nonce account `nonce_account`
+    ///
+    /// Always queries the network as the nonce changes on each use
+    pub async fn get_nonce_account(&self, nonce_account: &Pubkey) -> SdkResult<NonceData> {
+        let account = self
+            .backend
+            .rpc_client
+            .get_account_with_commitment(nonce_account, self.backend.rpc_client.commitment())
+            .await?
+            .value
+            .ok_or(SdkError::InvalidAccount)?;
+        nonce::decode_nonce_account(&account)
+    }
+
+    /// Sign and send a tx using the current nonce of `nonce_account` in place of a recent blockhash
+    ///
+    /// `tx` should be built with `TransactionBuilder::with_nonce`
+    ///
+    /// Returns the signature on success
+    pub async fn sign_and_send_with_nonce(
+        &self,
+        tx: VersionedMessage,
+        nonce_account: &Pubkey,
+    ) -> SdkResult<Signature> {
+        let nonce = self.get_nonce_account(nonce_account).await?;
+        self.sign_and_send_with_config(
+            tx,
+            Some(nonce.blockhash()),
+            RpcSendTransactionConfig {
+                preflight_commitment: Some(self.backend.rpc_client.commitment().commitment),
+                ..Default::default()
+            },
+        )
+        .await
+    }
+
+    /// Calculate the max. amount of a spot deposit that can be moved out of `account`
+    /// without breaching its initial margin requirement
+    ///
+    /// Only computes the amount, it does not send a tx. Move the deposit to another sub-account
+    /// with `TransactionBuilder::transfer_deposit`
+    ///
+    /// requires the client is subscribed to `account`'s markets and oracles
+    ///
+    /// * `account` - the drift user PDA (subaccount) to move the deposit from
+    /// * `market_index` - spot market of the deposit
+    ///
+    /// Returns the token amount (in token precision)
+    pub async fn max_transfer_deposit_amount(
+        &self,
+        account: &Pubkey,
+        market_index: u16,
+    ) -> SdkResult<u64> {
+        let user = self.get_user_account(account).await?;
+        let spot_market = self.try_get_spot_market_account(market_index)?;
+        let oracle_price = self
+            .try_get_oracle_price_data_and_slot(MarketId::spot(market_index))
+            .ok_or(SdkError::NoMarketData(MarketId::spot(market_index)))?
+            .data
+            .price;
+
+        let deposit_amount = match user.get_spot_position(market_index) {
+            Ok(p) if p.balance_type == SpotBalanceType::Deposit => {
+                p.get_token_amount(&spot_market)?
+            }
+            _ => return Ok(0),
+        };
+        let asset_weight = spot_market.get_asset_weight(
+            deposit_amount,
+            oracle_price,
+            MarginRequirementType::Initial,
+        )?;
+        let collateral =
+            math::liquidation::calculate_collateral(self, &user, MarginRequirementType::Initial)?;
+
+        let max_amount = math::liquidation::calculate_max_transfer_amount(
+            collateral.free,
+            deposit_amount,
+            spot_market.decimals,
+            oracle_price,
+            asset_weight,
+        );
+
+        Ok(max_amount.min(u64::MAX as u128) as u64)
+    }
+
     pub async fn get_recent_priority_fees(
         &self,
         writable_markets: &[MarketId],
@@ -602,11 +1170,19 @@ pub struct DriftClientBackend {
     perp_market_map: MarketMap<PerpMarket>,
     spot_market_map: MarketMap<SpotMarket>,
     oracle_map: OracleMap,
+    /// RPC endpoints failed over between, if set
+    failover: Option<Arc<RpcEndpoints>>,
 }
 
 impl DriftClientBackend {
     /// Initialize a new `DriftClientBackend`
-    async fn new(context: Context, rpc_client: Arc<RpcClient>) -> SdkResult<Self> {
+    ///
+    /// * `account_map` - serves account subscriptions
+    async fn new(
+        context: Context,
+        rpc_client: Arc<RpcClient>,
+        account_map: AccountMap,
+    ) -> SdkResult<Self> {
         let perp_market_map = MarketMap::<PerpMarket>::new(Arc::clone(&rpc_client));
         let spot_market_map = MarketMap::<SpotMarket>::new(Arc::clone(&rpc_client));
 
@@ -633,7 +1209,6 @@ impl DriftClientBackend {
         }
 
         let oracle_map = OracleMap::new(Arc::clone(&rpc_client), all_oracles.as_slice());
-        let account_map = AccountMap::new(rpc_client.url(), rpc_client.commitment());
         account_map.subscribe_account(state_account()).await?;
 
         Ok(Self {
@@ -651,9 +1226,40 @@ impl DriftClientBackend {
             perp_market_map,
             spot_market_map,
             oracle_map,
+            failover: None,
         })
     }
 
+    /// Market and oracle Ws subscriptions and blockhash polling follow the active endpoint of `endpoints`
+    fn with_failover(mut self, endpoints: Arc<RpcEndpoints>) -> Self {
+        self.blockhash_subscriber = self
+            .blockhash_subscriber
+            .with_failover(Arc::clone(&endpoints));
+        self.perp_market_map = self.perp_market_map.with_failover(Arc::clone(&endpoints));
+        self.spot_market_map = self.spot_market_map.with_failover(Arc::clone(&endpoints));
+        self.oracle_map = self.oracle_map.with_failover(Arc::clone(&endpoints));
+        self.failover = Some(endpoints);
+        self
+    }
+
+    /// Serve market and oracle subscriptions from `provider`
+    #[cfg(any(test, feature = "mock"))]
+    fn with_provider(mut self, provider: Arc<dyn account_provider::AccountProvider>) -> Self {
+        self.perp_market_map = self.perp_market_map.with_provider(Arc::clone(&provider));
+        self.spot_market_map = self.spot_market_map.with_provider(Arc::clone(&provider));
+        self.oracle_map = self.oracle_map.with_provider(provider);
+        self
+    }
+
+    /// Serve market and oracle subscriptions from the `grpc` stream
+    #[cfg(feature = "grpc")]
+    fn with_grpc(mut self, grpc: Arc<grpc::GrpcSubscriber>) -> Self {
+        self.perp_market_map = self.perp_market_map.with_grpc(Arc::clone(&grpc));
+        self.spot_market_map = self.spot_market_map.with_grpc(Arc::clone(&grpc));
+        self.oracle_map = self.oracle_map.with_grpc(grpc);
+        self
+    }
+
     /// Start subscription for latest block hashes
     async fn subscribe_blockhashes(&self) -> SdkResult<()> {
         self.blockhash_subscriber.subscribe();
@@ -684,6 +1290,8 @@ impl DriftClientBackend {
         self.perp_market_map.unsubscribe_all()?;
         self.spot_market_map.unsubscribe_all()?;
         self.account_map.unsubscribe_account(state_account());
+        self.account_map
+            .unsubscribe_account(high_leverage_mode_config_account());
         self.oracle_map.unsubscribe_all()
     }
 
@@ -845,7 +1453,7 @@ impl DriftClientBackend {
         tx: VersionedMessage,
         recent_block_hash: Hash,
     ) -> SdkResult<Signature> {
-        let tx = wallet.sign_tx(tx, recent_block_hash)?;
+        let tx = wallet.sign_tx(tx, recent_block_hash).await?;
         self.rpc_client
             .send_transaction(&tx)
             .await
@@ -863,13 +1471,49 @@ impl DriftClientBackend {
         recent_block_hash: Hash,
         config: RpcSendTransactionConfig,
     ) -> SdkResult<Signature> {
-        let tx = wallet.sign_tx(tx, recent_block_hash)?;
+        let tx = wallet.sign_tx(tx, recent_block_hash).await?;
         self.rpc_client
             .send_transaction_with_config(&tx, config)
             .await
             .map_err(Into::into)
     }
 
+    /// Simulate an unsigned `tx`
+    async fn simulate(
+        &self,
+        tx: VersionedMessage,
+    ) -> SdkResult<Response<RpcSimulateTransactionResult>> {
+        let tx = VersionedTransaction {
+            signatures: vec![Signature::default(); tx.header().num_required_signatures as usize],
+            message: tx,
+        };
+        self.rpc_client
+            .simulate_transaction_with_config(
+                &tx,
+                RpcSimulateTransactionConfig {
+                    sig_verify: false,
+                    replace_recent_blockhash: true,
+                    commitment: Some(self.rpc_client.commitment()),
+                    ..Default::default()
+                },
+            )
+            .await
+            .map_err(Into::into)
+    }
+
+    /// Simulate an unsigned `tx` returning the compute units consumed
+    async fn simulate_compute_units(&self, tx: VersionedMessage) -> SdkResult<u64> {
+        let response = self.simulate(tx).await?;
+
+        if let Some(err) = response.value.err {
+            return Err(SdkError::Rpc(err.into()));
+        }
+        response
+            .value
+            .units_consumed
+            .ok_or_else(|| SdkError::Generic("simulation returned no units consumed".into()))
+    }
+
     /// Fetch the live oracle price for `market`
     ///
     /// Uses latest local value from an `OracleMap` if subscribed, falls back to network query
@@ -918,6 +1562,39 @@ impl DriftClientBackend {
         }
     }
 
+    /// Get all user accounts matching `filter` via RPC
+    ///
+    /// Returns (address, `User`) pairs sorted by authority and sub-account id
+    async fn get_user_accounts(&self, filter: RpcFilterType) -> SdkResult<Vec<(Pubkey, User)>> {
+        let accounts = self
+            .rpc_client
+            .get_program_accounts_with_config(
+                &PROGRAM_ID,
+                RpcProgramAccountsConfig {
+                    filters: Some(vec![memcmp::get_user_filter(), filter]),
+                    account_config: RpcAccountInfoConfig {
+                        encoding: Some(UiAccountEncoding::Base64Zstd),
+                        commitment: Some(self.rpc_client.commitment()),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+            )
+            .await?;
+
+        let mut users = accounts
+            .into_iter()
+            .map(|(pubkey, account)| {
+                User::try_deserialize(&mut account.data.as_slice())
+                    .map(|user| (pubkey, user))
+                    .map_err(|err| SdkError::Anchor(Box::new(err)))
+            })
+            .collect::<SdkResult<Vec<_>>>()?;
+        users.sort_by_key(|(_, user)| (user.authority, user.sub_account_id));
+
+        Ok(users)
+    }
+
     /// Get account via rpc along with retrieved slot number
     async fn get_account_with_slot_raw(&self, pubkey: &Pubkey) -> SdkResult<(Account, Slot)> {
         match self
@@ -1003,6 +1680,10 @@ pub struct TransactionBuilder<'a> {
     lookup_tables: Vec<AddressLookupTableAccount>,
     /// some markets forced to include in the tx accounts list
     force_markets: ForceMarkets,
+    /// tip transfer ix, appended as the final ix
+    tip: Option<Instruction>,
+    /// advance nonce ix, prepended as the first ix
+    nonce: Option<Instruction>,
 }
 
 impl<'a> TransactionBuilder<'a> {
@@ -1034,6 +1715,8 @@ impl<'a> TransactionBuilder<'a> {
             lookup_tables: vec![program_data.lookup_table.clone()],
             legacy: false,
             force_markets: Default::default(),
+            tip: None,
+            nonce: None,
         }
     }
     /// force given `markets` to be included in the final tx accounts list (ensure to call before building ixs)
@@ -1067,6 +1750,32 @@ impl<'a> TransactionBuilder<'a> {
 
         self
     }
+    /// Tip `tip_account` e.g. for bundle submission to a block engine
+    ///
+    /// The tip transfer is appended as the final ix of the tx (paid by the tx authority)
+    ///
+    /// * `tip_account` - account to receive the tip
+    /// * `lamports` - tip amount
+    pub fn with_tip(mut self, tip_account: &Pubkey, lamports: u64) -> Self {
+        self.tip = Some(system_instruction::transfer(
+            &self.authority,
+            tip_account,
+            lamports,
+        ));
+        self
+    }
+
+    /// Use the durable nonce of `nonce_account` in place of a recent blockhash
+    ///
+    /// An advance nonce ix is prepended as the first ix of the tx, the tx authority must be the nonce authority.
+    /// Sign the tx with the current nonce as its blockhash (see `DriftClient::get_nonce_account`)
+    pub fn with_nonce(mut self, nonce_account: Pubkey) -> Self {
+        self.nonce = Some(system_instruction::advance_nonce_account(
+            &nonce_account,
+            &self.authority,
+        ));
+        self
+    }
 
     /// Deposit collateral into account
     pub fn deposit(
@@ -1148,17 +1857,66 @@ impl<'a> TransactionBuilder<'a> {
         self
     }
 
-    /// Place new orders for account
-    pub fn place_orders(mut self, orders: Vec<OrderParams>) -> Self {
-        let mut readable_accounts: Vec<MarketId> = orders
-            .iter()
-            .map(|o| (o.market_index, o.market_type).into())
-            .collect();
-        readable_accounts.extend(&self.force_markets.readable);
-
-        let accounts = build_accounts(
-            self.program_data,
-            types::accounts::PlaceOrders {
+    /// Transfer a spot deposit between two of the authority's sub-accounts
+    ///
+    /// The builder's sub-account should be `from_sub_account`, its positions are included for margin checks
+    ///
+    /// * `from_sub_account` - id of the sub-account to transfer from
+    /// * `to_sub_account` - id of the sub-account to transfer to
+    /// * `market_index` - spot market of the deposit
+    /// * `amount` - amount to transfer (in token precision)
+    pub fn transfer_deposit(
+        mut self,
+        from_sub_account: u16,
+        to_sub_account: u16,
+        market_index: u16,
+        amount: u64,
+    ) -> Self {
+        let accounts = build_accounts(
+            self.program_data,
+            types::accounts::TransferDeposit {
+                from_user: Wallet::derive_user_account(
+                    &self.account_data.authority,
+                    from_sub_account,
+                ),
+                to_user: Wallet::derive_user_account(&self.account_data.authority, to_sub_account),
+                user_stats: Wallet::derive_stats_account(&self.account_data.authority),
+                authority: self.authority,
+                state: *state_account(),
+                spot_market_vault: constants::derive_spot_market_vault(market_index),
+            },
+            &[self.account_data.as_ref()],
+            self.force_markets.readable.iter(),
+            [MarketId::spot(market_index)]
+                .iter()
+                .chain(self.force_markets.writeable.iter()),
+        );
+
+        let ix = Instruction {
+            program_id: constants::PROGRAM_ID,
+            accounts,
+            data: InstructionData::data(&drift_idl::instructions::TransferDeposit {
+                market_index,
+                amount,
+            }),
+        };
+
+        self.ixs.push(ix);
+
+        self
+    }
+
+    /// Place new orders for account
+    pub fn place_orders(mut self, orders: Vec<OrderParams>) -> Self {
+        let mut readable_accounts: Vec<MarketId> = orders
+            .iter()
+            .map(|o| (o.market_index, o.market_type).into())
+            .collect();
+        readable_accounts.extend(&self.force_markets.readable);
+
+        let accounts = build_accounts(
+            self.program_data,
+            types::accounts::PlaceOrders {
                 state: *state_account(),
                 authority: self.authority,
                 user: self.sub_account,
@@ -1427,83 +2185,1481 @@ impl<'a> TransactionBuilder<'a> {
         self
     }
 
-    /// Add a place and take instruction
+    /// Add a place and take instruction
+    ///
+    /// * `order` - the order to place
+    /// * `maker_info` - pubkey of the maker/counterparty to take against and account data
+    /// * `referrer` - pubkey of the maker's referrer account, if any
+    /// * `fulfillment_type` - type of fill for spot orders, ignored for perp orders
+    pub fn place_and_take(
+        mut self,
+        order: OrderParams,
+        maker_info: Option<(Pubkey, User)>,
+        referrer: Option<Pubkey>,
+        fulfillment_type: Option<SpotFulfillmentType>,
+        success_condition: Option<u32>,
+    ) -> Self {
+        let mut user_accounts = vec![self.account_data.as_ref()];
+        if let Some((ref _maker_pubkey, ref maker)) = maker_info {
+            user_accounts.push(maker);
+        }
+
+        let is_perp = order.market_type == MarketType::Perp;
+        let perp_writable = [MarketId::perp(order.market_index)];
+        let spot_writable = [MarketId::spot(order.market_index), MarketId::QUOTE_SPOT];
+
+        let mut accounts = build_accounts(
+            self.program_data,
+            types::accounts::PlaceAndTakePerpOrder {
+                state: *state_account(),
+                authority: self.authority,
+                user: self.sub_account,
+                user_stats: Wallet::derive_stats_account(&self.authority),
+            },
+            user_accounts.as_slice(),
+            self.force_markets.readable.iter(),
+            if is_perp {
+                perp_writable.iter()
+            } else {
+                spot_writable.iter()
+            }
+            .chain(self.force_markets.writeable.iter()),
+        );
+
+        if referrer.is_some_and(|r| !maker_info.is_some_and(|(m, _)| m == r)) {
+            let referrer = referrer.unwrap();
+            accounts.push(AccountMeta::new(
+                Wallet::derive_stats_account(&referrer),
+                false,
+            ));
+            accounts.push(AccountMeta::new(referrer, false));
+        }
+
+        let ix = if is_perp {
+            Instruction {
+                program_id: constants::PROGRAM_ID,
+                accounts,
+                data: InstructionData::data(&drift_idl::instructions::PlaceAndTakePerpOrder {
+                    params: order,
+                    success_condition,
+                }),
+            }
+        } else {
+            Instruction {
+                program_id: constants::PROGRAM_ID,
+                accounts,
+                data: InstructionData::data(&drift_idl::instructions::PlaceAndTakeSpotOrder {
+                    params: order,
+                    maker_order_id: None,
+                    fulfillment_type,
+                }),
+            }
+        };
+
+        self.ixs.push(ix);
+        self
+    }
+
+    /// Liquidate a user's perp position
+    ///
+    /// * `market_index` - perp market of the position to liquidate
+    /// * `liquidatee_info` - liquidatee account address and data
+    /// * `max_base_asset_amount` - max. base amount of the position to take over
+    /// * `limit_price` - optional worst price the liquidator accepts
+    pub fn liquidate_perp(
+        mut self,
+        market_index: u16,
+        liquidatee_info: &(Pubkey, User),
+        max_base_asset_amount: u64,
+        limit_price: Option<u64>,
+    ) -> Self {
+        let (liquidatee, liquidatee_account) = liquidatee_info;
+        let accounts = build_accounts(
+            self.program_data,
+            types::accounts::LiquidatePerp {
+                state: *state_account(),
+                authority: self.authority,
+                liquidator: self.sub_account,
+                liquidator_stats: Wallet::derive_stats_account(&self.authority),
+                user: *liquidatee,
+                user_stats: Wallet::derive_stats_account(&liquidatee_account.authority),
+            },
+            &[self.account_data.as_ref(), liquidatee_account],
+            self.force_markets.readable.iter(),
+            [MarketId::perp(market_index)]
+                .iter()
+                .chain(self.force_markets.writeable.iter()),
+        );
+
+        let ix = Instruction {
+            program_id: constants::PROGRAM_ID,
+            accounts,
+            data: InstructionData::data(&drift_idl::instructions::LiquidatePerp {
+                market_index,
+                liquidator_max_base_asset_amount: max_base_asset_amount,
+                limit_price,
+            }),
+        };
+        self.ixs.push(ix);
+
+        self
+    }
+
+    /// Liquidate a user's perp position by filling it against maker orders
+    ///
+    /// * `market_index` - perp market of the position to liquidate
+    /// * `liquidatee_info` - liquidatee account address and data
+    /// * `makers` - maker accounts (address and data) to fill against
+    pub fn liquidate_perp_with_fill(
+        mut self,
+        market_index: u16,
+        liquidatee_info: &(Pubkey, User),
+        makers: &[(Pubkey, User)],
+    ) -> Self {
+        let (liquidatee, liquidatee_account) = liquidatee_info;
+        let mut user_accounts = vec![self.account_data.as_ref(), liquidatee_account];
+        user_accounts.extend(makers.iter().map(|(_, m)| m));
+
+        let mut accounts = build_accounts(
+            self.program_data,
+            types::accounts::LiquidatePerpWithFill {
+                state: *state_account(),
+                authority: self.authority,
+                liquidator: self.sub_account,
+                liquidator_stats: Wallet::derive_stats_account(&self.authority),
+                user: *liquidatee,
+                user_stats: Wallet::derive_stats_account(&liquidatee_account.authority),
+            },
+            user_accounts.as_slice(),
+            self.force_markets.readable.iter(),
+            [MarketId::perp(market_index)]
+                .iter()
+                .chain(self.force_markets.writeable.iter()),
+        );
+
+        extend_fill_accounts(&mut accounts, makers, None);
+
+        let ix = Instruction {
+            program_id: constants::PROGRAM_ID,
+            accounts,
+            data: InstructionData::data(&drift_idl::instructions::LiquidatePerpWithFill {
+                market_index,
+            }),
+        };
+        self.ixs.push(ix);
+
+        self
+    }
+
+    /// Liquidate a user's spot borrow in exchange for one of their spot deposits
+    ///
+    /// * `asset_market_index` - spot market of the deposit the liquidator receives
+    /// * `liability_market_index` - spot market of the borrow the liquidator takes over
+    /// * `liquidatee_info` - liquidatee account address and data
+    /// * `max_liability_transfer` - max. liability amount to transfer
+    /// * `limit_price` - optional worst price the liquidator accepts
+    pub fn liquidate_spot(
+        mut self,
+        asset_market_index: u16,
+        liability_market_index: u16,
+        liquidatee_info: &(Pubkey, User),
+        max_liability_transfer: u128,
+        limit_price: Option<u64>,
+    ) -> Self {
+        let (liquidatee, liquidatee_account) = liquidatee_info;
+        let accounts = build_accounts(
+            self.program_data,
+            types::accounts::LiquidateSpot {
+                state: *state_account(),
+                authority: self.authority,
+                liquidator: self.sub_account,
+                liquidator_stats: Wallet::derive_stats_account(&self.authority),
+                user: *liquidatee,
+                user_stats: Wallet::derive_stats_account(&liquidatee_account.authority),
+            },
+            &[self.account_data.as_ref(), liquidatee_account],
+            self.force_markets.readable.iter(),
+            [
+                MarketId::spot(liability_market_index),
+                MarketId::spot(asset_market_index),
+            ]
+            .iter()
+            .chain(self.force_markets.writeable.iter()),
+        );
+
+        let ix = Instruction {
+            program_id: constants::PROGRAM_ID,
+            accounts,
+            data: InstructionData::data(&drift_idl::instructions::LiquidateSpot {
+                asset_market_index,
+                liability_market_index,
+                liquidator_max_liability_transfer: max_liability_transfer,
+                limit_price,
+            }),
+        };
+        self.ixs.push(ix);
+
+        self
+    }
+
+    /// Liquidate a user's spot borrow in exchange for their positive perp PnL
+    ///
+    /// * `perp_market_index` - perp market with the PnL the liquidator receives
+    /// * `liability_market_index` - spot market of the borrow the liquidator takes over
+    /// * `liquidatee_info` - liquidatee account address and data
+    /// * `max_liability_transfer` - max. liability amount to transfer
+    /// * `limit_price` - optional worst price the liquidator accepts
+    pub fn liquidate_borrow_for_perp_pnl(
+        mut self,
+        perp_market_index: u16,
+        liability_market_index: u16,
+        liquidatee_info: &(Pubkey, User),
+        max_liability_transfer: u128,
+        limit_price: Option<u64>,
+    ) -> Self {
+        let (liquidatee, liquidatee_account) = liquidatee_info;
+        let accounts = build_accounts(
+            self.program_data,
+            types::accounts::LiquidateBorrowForPerpPnl {
+                state: *state_account(),
+                authority: self.authority,
+                liquidator: self.sub_account,
+                liquidator_stats: Wallet::derive_stats_account(&self.authority),
+                user: *liquidatee,
+                user_stats: Wallet::derive_stats_account(&liquidatee_account.authority),
+            },
+            &[self.account_data.as_ref(), liquidatee_account],
+            self.force_markets.readable.iter(),
+            [
+                MarketId::perp(perp_market_index),
+                MarketId::spot(liability_market_index),
+            ]
+            .iter()
+            .chain(self.force_markets.writeable.iter()),
+        );
+
+        let ix = Instruction {
+            program_id: constants::PROGRAM_ID,
+            accounts,
+            data: InstructionData::data(&drift_idl::instructions::LiquidateBorrowForPerpPnl {
+                perp_market_index,
+                spot_market_index: liability_market_index,
+                liquidator_max_liability_transfer: max_liability_transfer,
+                limit_price,
+            }),
+        };
+        self.ixs.push(ix);
+
+        self
+    }
+
+    /// Liquidate a user's negative perp PnL in exchange for one of their spot deposits
+    ///
+    /// * `perp_market_index` - perp market with the PnL the liquidator takes over
+    /// * `asset_market_index` - spot market of the deposit the liquidator receives
+    /// * `liquidatee_info` - liquidatee account address and data
+    /// * `max_pnl_transfer` - max. PnL amount to transfer
+    /// * `limit_price` - optional worst price the liquidator accepts
+    pub fn liquidate_perp_pnl_for_deposit(
+        mut self,
+        perp_market_index: u16,
+        asset_market_index: u16,
+        liquidatee_info: &(Pubkey, User),
+        max_pnl_transfer: u128,
+        limit_price: Option<u64>,
+    ) -> Self {
+        let (liquidatee, liquidatee_account) = liquidatee_info;
+        let accounts = build_accounts(
+            self.program_data,
+            types::accounts::LiquidatePerpPnlForDeposit {
+                state: *state_account(),
+                authority: self.authority,
+                liquidator: self.sub_account,
+                liquidator_stats: Wallet::derive_stats_account(&self.authority),
+                user: *liquidatee,
+                user_stats: Wallet::derive_stats_account(&liquidatee_account.authority),
+            },
+            &[self.account_data.as_ref(), liquidatee_account],
+            self.force_markets.readable.iter(),
+            [
+                MarketId::perp(perp_market_index),
+                MarketId::spot(asset_market_index),
+            ]
+            .iter()
+            .chain(self.force_markets.writeable.iter()),
+        );
+
+        let ix = Instruction {
+            program_id: constants::PROGRAM_ID,
+            accounts,
+            data: InstructionData::data(&drift_idl::instructions::LiquidatePerpPnlForDeposit {
+                perp_market_index,
+                spot_market_index: asset_market_index,
+                liquidator_max_pnl_transfer: max_pnl_transfer,
+                limit_price,
+            }),
+        };
+        self.ixs.push(ix);
+
+        self
+    }
+
+    /// Flag a user as being liquidated
+    ///
+    /// * `liquidatee_info` - liquidatee account address and data
+    pub fn set_user_status_to_being_liquidated(mut self, liquidatee_info: &(Pubkey, User)) -> Self {
+        let (liquidatee, liquidatee_account) = liquidatee_info;
+        let accounts = build_accounts(
+            self.program_data,
+            types::accounts::SetUserStatusToBeingLiquidated {
+                state: *state_account(),
+                user: *liquidatee,
+                authority: self.authority,
+            },
+            &[liquidatee_account],
+            self.force_markets.readable.iter(),
+            self.force_markets.writeable.iter(),
+        );
+
+        let ix = Instruction {
+            program_id: constants::PROGRAM_ID,
+            accounts,
+            data: InstructionData::data(
+                &drift_idl::instructions::SetUserStatusToBeingLiquidated {},
+            ),
+        };
+        self.ixs.push(ix);
+
+        self
+    }
+
+    /// Fill a taker's perp order against the AMM and/or given makers
+    ///
+    /// * `taker_info` - taker account address and data
+    /// * `order` - the taker order to fill
+    /// * `makers` - maker accounts (address and data) to fill against, if any
+    /// * `referrer` - the taker's referrer accounts, if any
+    pub fn fill_perp_order(
+        mut self,
+        taker_info: &(Pubkey, User),
+        order: &Order,
+        makers: &[(Pubkey, User)],
+        referrer: Option<ReferrerInfo>,
+    ) -> Self {
+        let (taker, taker_account) = taker_info;
+        let mut user_accounts = vec![taker_account];
+        user_accounts.extend(makers.iter().map(|(_, m)| m));
+
+        let mut accounts = build_accounts(
+            self.program_data,
+            types::accounts::FillPerpOrder {
+                state: *state_account(),
+                authority: self.authority,
+                filler: self.sub_account,
+                filler_stats: Wallet::derive_stats_account(&self.authority),
+                user: *taker,
+                user_stats: Wallet::derive_stats_account(&taker_account.authority),
+            },
+            user_accounts.as_slice(),
+            self.force_markets.readable.iter(),
+            [MarketId::perp(order.market_index)]
+                .iter()
+                .chain(self.force_markets.writeable.iter()),
+        );
+        extend_fill_accounts(&mut accounts, makers, referrer);
+
+        let ix = Instruction {
+            program_id: constants::PROGRAM_ID,
+            accounts,
+            data: InstructionData::data(&drift_idl::instructions::FillPerpOrder {
+                order_id: Some(order.order_id),
+                maker_order_id: None,
+            }),
+        };
+        self.ixs.push(ix);
+
+        self
+    }
+
+    /// Fill a taker's spot order against given makers
+    ///
+    /// * `taker_info` - taker account address and data
+    /// * `order` - the taker order to fill
+    /// * `makers` - maker accounts (address and data) to fill against, if any
+    /// * `referrer` - the taker's referrer accounts, if any
+    /// * `fulfillment_type` - type of fill, defaults to matching against drift makers
+    pub fn fill_spot_order(
+        mut self,
+        taker_info: &(Pubkey, User),
+        order: &Order,
+        makers: &[(Pubkey, User)],
+        referrer: Option<ReferrerInfo>,
+        fulfillment_type: Option<SpotFulfillmentType>,
+    ) -> Self {
+        let (taker, taker_account) = taker_info;
+        let mut user_accounts = vec![taker_account];
+        user_accounts.extend(makers.iter().map(|(_, m)| m));
+
+        let mut accounts = build_accounts(
+            self.program_data,
+            types::accounts::FillSpotOrder {
+                state: *state_account(),
+                authority: self.authority,
+                filler: self.sub_account,
+                filler_stats: Wallet::derive_stats_account(&self.authority),
+                user: *taker,
+                user_stats: Wallet::derive_stats_account(&taker_account.authority),
+            },
+            user_accounts.as_slice(),
+            self.force_markets.readable.iter(),
+            [MarketId::spot(order.market_index), MarketId::QUOTE_SPOT]
+                .iter()
+                .chain(self.force_markets.writeable.iter()),
+        );
+        extend_fill_accounts(&mut accounts, makers, referrer);
+
+        let ix = Instruction {
+            program_id: constants::PROGRAM_ID,
+            accounts,
+            data: InstructionData::data(&drift_idl::instructions::FillSpotOrder {
+                order_id: Some(order.order_id),
+                fulfillment_type,
+                maker_order_id: None,
+            }),
+        };
+        self.ixs.push(ix);
+
+        self
+    }
+
+    /// Trigger a user's trigger order (i.e. stop loss/take profit)
+    ///
+    /// * `user_info` - user account address and data
+    /// * `order` - the trigger order
+    pub fn trigger_order(mut self, user_info: &(Pubkey, User), order: &Order) -> Self {
+        let (user, user_account) = user_info;
+        let writable_markets = match order.market_type {
+            MarketType::Perp => vec![MarketId::perp(order.market_index)],
+            MarketType::Spot => vec![MarketId::spot(order.market_index), MarketId::QUOTE_SPOT],
+        };
+        let accounts = build_accounts(
+            self.program_data,
+            types::accounts::TriggerOrder {
+                state: *state_account(),
+                authority: self.authority,
+                filler: self.sub_account,
+                user: *user,
+            },
+            &[user_account],
+            self.force_markets.readable.iter(),
+            writable_markets
+                .iter()
+                .chain(self.force_markets.writeable.iter()),
+        );
+
+        let ix = Instruction {
+            program_id: constants::PROGRAM_ID,
+            accounts,
+            data: InstructionData::data(&drift_idl::instructions::TriggerOrder {
+                order_id: order.order_id,
+            }),
+        };
+        self.ixs.push(ix);
+
+        self
+    }
+
+    /// Revert the tx if the preceding fill did not succeed
+    ///
+    /// Fillers append this after a fill ix to avoid paying fees for failed fills
+    pub fn revert_fill(mut self) -> Self {
+        let accounts = types::accounts::RevertFill {
+            state: *state_account(),
+            authority: self.authority,
+            filler: self.sub_account,
+            filler_stats: Wallet::derive_stats_account(&self.authority),
+        }
+        .to_account_metas();
+
+        let ix = Instruction {
+            program_id: constants::PROGRAM_ID,
+            accounts,
+            data: InstructionData::data(&drift_idl::instructions::RevertFill {}),
+        };
+        self.ixs.push(ix);
+
+        self
+    }
+
+    /// Settle a user's PnL in a perp market
+    ///
+    /// * `settlee_info` - account address and data of the user to settle
+    /// * `market_index` - perp market to settle
+    pub fn settle_pnl(mut self, settlee_info: &(Pubkey, User), market_index: u16) -> Self {
+        let (settlee, settlee_account) = settlee_info;
+        let accounts = build_accounts(
+            self.program_data,
+            types::accounts::SettlePnl {
+                state: *state_account(),
+                user: *settlee,
+                authority: self.authority,
+                spot_market_vault: constants::derive_spot_market_vault(
+                    MarketId::QUOTE_SPOT.index(),
+                ),
+            },
+            &[settlee_account],
+            self.force_markets.readable.iter(),
+            [MarketId::perp(market_index), MarketId::QUOTE_SPOT]
+                .iter()
+                .chain(self.force_markets.writeable.iter()),
+        );
+
+        let ix = Instruction {
+            program_id: constants::PROGRAM_ID,
+            accounts,
+            data: InstructionData::data(&drift_idl::instructions::SettlePnl { market_index }),
+        };
+        self.ixs.push(ix);
+
+        self
+    }
+
+    /// Settle a user's PnL in multiple perp markets
+    ///
+    /// * `settlee_info` - account address and data of the user to settle
+    /// * `market_indexes` - perp markets to settle
+    /// * `mode` - fail the tx if any market cannot be settled (`MustSettle`) or skip it (`TrySettle`)
+    pub fn settle_multiple_pnls(
+        mut self,
+        settlee_info: &(Pubkey, User),
+        market_indexes: &[u16],
+        mode: SettlePnlMode,
+    ) -> Self {
+        let (settlee, settlee_account) = settlee_info;
+        let mut writable_markets: Vec<MarketId> = market_indexes
+            .iter()
+            .map(|idx| MarketId::perp(*idx))
+            .collect();
+        writable_markets.push(MarketId::QUOTE_SPOT);
+
+        let accounts = build_accounts(
+            self.program_data,
+            types::accounts::SettleMultiplePnls {
+                state: *state_account(),
+                user: *settlee,
+                authority: self.authority,
+                spot_market_vault: constants::derive_spot_market_vault(
+                    MarketId::QUOTE_SPOT.index(),
+                ),
+            },
+            &[settlee_account],
+            self.force_markets.readable.iter(),
+            writable_markets
+                .iter()
+                .chain(self.force_markets.writeable.iter()),
+        );
+
+        let ix = Instruction {
+            program_id: constants::PROGRAM_ID,
+            accounts,
+            data: InstructionData::data(&drift_idl::instructions::SettleMultiplePnls {
+                market_indexes: market_indexes.to_vec(),
+                mode,
+            }),
+        };
+        self.ixs.push(ix);
+
+        self
+    }
+
+    /// Initialize the authority's user stats account
+    ///
+    /// required once per authority before its first sub-account can be initialized
+    pub fn initialize_user_stats(mut self) -> Self {
+        let accounts = types::accounts::InitializeUserStats {
+            user_stats: Wallet::derive_stats_account(&self.authority),
+            state: *state_account(),
+            authority: self.authority,
+            payer: self.authority,
+            rent: solana_sdk::sysvar::rent::ID,
+            system_program: solana_sdk::system_program::ID,
+        }
+        .to_account_metas();
+
+        let ix = Instruction {
+            program_id: constants::PROGRAM_ID,
+            accounts,
+            data: InstructionData::data(&drift_idl::instructions::InitializeUserStats {}),
+        };
+        self.ixs.push(ix);
+
+        self
+    }
+
+    /// Initialize a new sub-account for the authority
+    ///
+    /// * `sub_account_id` - id of the new sub-account, must be the authority's next id (i.e. `UserStats::number_of_sub_accounts_created`)
+    /// * `name` - display name of the sub-account (max. 32 bytes)
+    /// * `referrer` - referrer accounts, only effective for the authority's first sub-account
+    ///
+    /// The program may charge an additional init fee when the exchange is near its account limit, see `State::init_user_fee`
+    pub fn initialize_user(
+        mut self,
+        sub_account_id: u16,
+        name: &str,
+        referrer: Option<ReferrerInfo>,
+    ) -> Self {
+        let mut accounts = types::accounts::InitializeUser {
+            user: Wallet::derive_user_account(&self.authority, sub_account_id),
+            user_stats: Wallet::derive_stats_account(&self.authority),
+            state: *state_account(),
+            authority: self.authority,
+            payer: self.authority,
+            rent: solana_sdk::sysvar::rent::ID,
+            system_program: solana_sdk::system_program::ID,
+        }
+        .to_account_metas();
+
+        if let Some(referrer) = referrer {
+            accounts.push(AccountMeta::new(referrer.referrer(), false));
+            accounts.push(AccountMeta::new(referrer.referrer_stats(), false));
+        }
+
+        let ix = Instruction {
+            program_id: constants::PROGRAM_ID,
+            accounts,
+            data: InstructionData::data(&drift_idl::instructions::InitializeUser {
+                sub_account_id,
+                name: utils::encode_name(name),
+            }),
+        };
+        self.ixs.push(ix);
+
+        self
+    }
+
+    /// Delete the sub-account, returning its rent to the authority
+    ///
+    /// the sub-account must have no open positions or orders
+    pub fn delete_user(mut self) -> Self {
+        let accounts = types::accounts::DeleteUser {
+            user: self.sub_account,
+            user_stats: Wallet::derive_stats_account(&self.authority),
+            state: *state_account(),
+            authority: self.authority,
+        }
+        .to_account_metas();
+
+        let ix = Instruction {
+            program_id: constants::PROGRAM_ID,
+            accounts,
+            data: InstructionData::data(&drift_idl::instructions::DeleteUser {}),
+        };
+        self.ixs.push(ix);
+
+        self
+    }
+
+    /// Reclaim any excess rent lamports held by the sub-account
+    pub fn reclaim_rent(mut self) -> Self {
+        let accounts = types::accounts::ReclaimRent {
+            user: self.sub_account,
+            user_stats: Wallet::derive_stats_account(&self.authority),
+            state: *state_account(),
+            authority: self.authority,
+            rent: solana_sdk::sysvar::rent::ID,
+        }
+        .to_account_metas();
+
+        let ix = Instruction {
+            program_id: constants::PROGRAM_ID,
+            accounts,
+            data: InstructionData::data(&drift_idl::instructions::ReclaimRent {}),
+        };
+        self.ixs.push(ix);
+
+        self
+    }
+
+    /// Update the sub-account's display name (max. 32 bytes)
+    pub fn update_user_name(mut self, name: &str) -> Self {
+        let ix = Instruction {
+            program_id: constants::PROGRAM_ID,
+            accounts: self.update_user_accounts(),
+            data: InstructionData::data(&drift_idl::instructions::UpdateUserName {
+                sub_account_id: self.account_data.sub_account_id,
+                name: utils::encode_name(name),
+            }),
+        };
+        self.ixs.push(ix);
+
+        self
+    }
+
+    /// Set the sub-account's delegate, `Pubkey::default()` removes any existing delegate
+    pub fn update_user_delegate(mut self, delegate: Pubkey) -> Self {
+        let ix = Instruction {
+            program_id: constants::PROGRAM_ID,
+            accounts: self.update_user_accounts(),
+            data: InstructionData::data(&drift_idl::instructions::UpdateUserDelegate {
+                sub_account_id: self.account_data.sub_account_id,
+                delegate,
+            }),
+        };
+        self.ixs.push(ix);
+
+        self
+    }
+
+    /// Enable/disable margin trading for the sub-account
+    pub fn update_user_margin_trading_enabled(mut self, enabled: bool) -> Self {
+        let ix = Instruction {
+            program_id: constants::PROGRAM_ID,
+            accounts: self.update_user_accounts(),
+            data: InstructionData::data(&drift_idl::instructions::UpdateUserMarginTradingEnabled {
+                sub_account_id: self.account_data.sub_account_id,
+                margin_trading_enabled: enabled,
+            }),
+        };
+        self.ixs.push(ix);
+
+        self
+    }
+
+    /// Set a custom (minimum) margin ratio for the sub-account
+    ///
+    /// * `margin_ratio` - MARGIN_PRECISION e.g. 2_000 limits the account to 5x leverage, 0 to reset
+    pub fn update_user_custom_margin_ratio(mut self, margin_ratio: u32) -> Self {
+        let ix = Instruction {
+            program_id: constants::PROGRAM_ID,
+            accounts: self.update_user_accounts(),
+            data: InstructionData::data(&drift_idl::instructions::UpdateUserCustomMarginRatio {
+                sub_account_id: self.account_data.sub_account_id,
+                margin_ratio,
+            }),
+        };
+        self.ixs.push(ix);
+
+        self
+    }
+
+    /// Accounts for `update_user_*` ixs, which share the same layout
+    fn update_user_accounts(&self) -> Vec<AccountMeta> {
+        types::accounts::UpdateUserName {
+            user: self.sub_account,
+            authority: self.authority,
+        }
+        .to_account_metas()
+    }
+
+    /// Initialize the authority's insurance fund stake account for a spot market
+    ///
+    /// * `market_index` - spot market of the insurance fund
+    pub fn initialize_insurance_fund_stake(mut self, market_index: u16) -> Self {
+        let accounts = types::accounts::InitializeInsuranceFundStake {
+            spot_market: constants::derive_spot_market_account(market_index),
+            insurance_fund_stake: Wallet::derive_insurance_fund_stake(
+                &self.authority,
+                market_index,
+            ),
+            user_stats: Wallet::derive_stats_account(&self.authority),
+            state: *state_account(),
+            authority: self.authority,
+            payer: self.authority,
+            rent: solana_sdk::sysvar::rent::ID,
+            system_program: solana_sdk::system_program::ID,
+        }
+        .to_account_metas();
+
+        let ix = Instruction {
+            program_id: constants::PROGRAM_ID,
+            accounts,
+            data: InstructionData::data(&drift_idl::instructions::InitializeInsuranceFundStake {
+                market_index,
+            }),
+        };
+        self.ixs.push(ix);
+
+        self
+    }
+
+    /// Stake tokens into a spot market's insurance fund
+    ///
+    /// * `market_index` - spot market of the insurance fund
+    /// * `amount` - token amount to stake (in token precision)
+    /// * `user_token_account` - token account to pay the stake from
+    pub fn add_insurance_fund_stake(
+        mut self,
+        market_index: u16,
+        amount: u64,
+        user_token_account: Pubkey,
+    ) -> Self {
+        let accounts = types::accounts::AddInsuranceFundStake {
+            state: *state_account(),
+            spot_market: constants::derive_spot_market_account(market_index),
+            insurance_fund_stake: Wallet::derive_insurance_fund_stake(
+                &self.authority,
+                market_index,
+            ),
+            user_stats: Wallet::derive_stats_account(&self.authority),
+            authority: self.authority,
+            spot_market_vault: constants::derive_spot_market_vault(market_index),
+            insurance_fund_vault: constants::derive_insurance_fund_vault(market_index),
+            drift_signer: constants::derive_drift_signer(),
+            user_token_account,
+            token_program: constants::TOKEN_PROGRAM_ID,
+        }
+        .to_account_metas();
+
+        let ix = Instruction {
+            program_id: constants::PROGRAM_ID,
+            accounts,
+            data: InstructionData::data(&drift_idl::instructions::AddInsuranceFundStake {
+                market_index,
+                amount,
+            }),
+        };
+        self.ixs.push(ix);
+
+        self
+    }
+
+    /// Request to unstake tokens from a spot market's insurance fund
+    ///
+    /// The tokens may be withdrawn with `remove_insurance_fund_stake` once the market's unstaking period has elapsed
+    ///
+    /// * `market_index` - spot market of the insurance fund
+    /// * `amount` - token amount to unstake (in token precision)
+    pub fn request_remove_insurance_fund_stake(mut self, market_index: u16, amount: u64) -> Self {
+        let accounts = types::accounts::RequestRemoveInsuranceFundStake {
+            spot_market: constants::derive_spot_market_account(market_index),
+            insurance_fund_stake: Wallet::derive_insurance_fund_stake(
+                &self.authority,
+                market_index,
+            ),
+            user_stats: Wallet::derive_stats_account(&self.authority),
+            authority: self.authority,
+            insurance_fund_vault: constants::derive_insurance_fund_vault(market_index),
+        }
+        .to_account_metas();
+
+        let ix = Instruction {
+            program_id: constants::PROGRAM_ID,
+            accounts,
+            data: InstructionData::data(
+                &drift_idl::instructions::RequestRemoveInsuranceFundStake {
+                    market_index,
+                    amount,
+                },
+            ),
+        };
+        self.ixs.push(ix);
+
+        self
+    }
+
+    /// Cancel a pending unstake request from a spot market's insurance fund
+    ///
+    /// * `market_index` - spot market of the insurance fund
+    pub fn cancel_request_remove_insurance_fund_stake(mut self, market_index: u16) -> Self {
+        let accounts = types::accounts::CancelRequestRemoveInsuranceFundStake {
+            spot_market: constants::derive_spot_market_account(market_index),
+            insurance_fund_stake: Wallet::derive_insurance_fund_stake(
+                &self.authority,
+                market_index,
+            ),
+            user_stats: Wallet::derive_stats_account(&self.authority),
+            authority: self.authority,
+            insurance_fund_vault: constants::derive_insurance_fund_vault(market_index),
+        }
+        .to_account_metas();
+
+        let ix = Instruction {
+            program_id: constants::PROGRAM_ID,
+            accounts,
+            data: InstructionData::data(
+                &drift_idl::instructions::CancelRequestRemoveInsuranceFundStake { market_index },
+            ),
+        };
+        self.ixs.push(ix);
+
+        self
+    }
+
+    /// Withdraw previously requested unstaked tokens from a spot market's insurance fund
+    ///
+    /// * `market_index` - spot market of the insurance fund
+    /// * `user_token_account` - token account to receive the unstaked tokens
+    pub fn remove_insurance_fund_stake(
+        mut self,
+        market_index: u16,
+        user_token_account: Pubkey,
+    ) -> Self {
+        let accounts = types::accounts::RemoveInsuranceFundStake {
+            state: *state_account(),
+            spot_market: constants::derive_spot_market_account(market_index),
+            insurance_fund_stake: Wallet::derive_insurance_fund_stake(
+                &self.authority,
+                market_index,
+            ),
+            user_stats: Wallet::derive_stats_account(&self.authority),
+            authority: self.authority,
+            insurance_fund_vault: constants::derive_insurance_fund_vault(market_index),
+            drift_signer: constants::derive_drift_signer(),
+            user_token_account,
+            token_program: constants::TOKEN_PROGRAM_ID,
+        }
+        .to_account_metas();
+
+        let ix = Instruction {
+            program_id: constants::PROGRAM_ID,
+            accounts,
+            data: InstructionData::data(&drift_idl::instructions::RemoveInsuranceFundStake {
+                market_index,
+            }),
+        };
+        self.ixs.push(ix);
+
+        self
+    }
+
+    /// Swap spot tokens using the account's deposits
+    ///
+    /// Wraps `external_ixs` between begin/end swap ixs. Drift lends `amount_in` of the in token to
+    /// the authority's token account, the external ixs perform the swap (e.g. from an aggregator, see `swap::SwapQuoteProvider`)
+    /// and any out tokens received are deposited back into the account
+    ///
+    /// * `in_market` - spot market index of the token to sell
+    /// * `out_market` - spot market index of the token to buy
+    /// * `amount_in` - amount of the in token to sell (in token precision)
+    /// * `external_ixs` - ixs performing the swap, from the authority's in token account to its out token account
+    ///
+    /// The authority's associated token accounts for both markets must exist
+    pub fn swap(
+        mut self,
+        in_market: u16,
+        out_market: u16,
+        amount_in: u64,
+        external_ixs: Vec<Instruction>,
+    ) -> Self {
+        let in_spot_market = self
+            .program_data
+            .spot_market_config_by_index(in_market)
+            .expect("in market exists");
+        let out_spot_market = self
+            .program_data
+            .spot_market_config_by_index(out_market)
+            .expect("out market exists");
+        let in_token_program = constants::spot_market_token_program(in_spot_market);
+        let out_token_program = constants::spot_market_token_program(out_spot_market);
+
+        let mut accounts = build_accounts(
+            self.program_data,
+            types::accounts::BeginSwap {
+                state: *state_account(),
+                user: self.sub_account,
+                user_stats: Wallet::derive_stats_account(&self.authority),
+                authority: self.authority,
+                out_spot_market_vault: constants::derive_spot_market_vault(out_market),
+                in_spot_market_vault: constants::derive_spot_market_vault(in_market),
+                out_token_account: constants::derive_associated_token_account(
+                    &self.authority,
+                    &out_spot_market.mint,
+                    &out_token_program,
+                ),
+                in_token_account: constants::derive_associated_token_account(
+                    &self.authority,
+                    &in_spot_market.mint,
+                    &in_token_program,
+                ),
+                token_program: in_token_program,
+                drift_signer: constants::derive_drift_signer(),
+                instructions: solana_sdk::sysvar::instructions::ID,
+            },
+            &[self.account_data.as_ref()],
+            self.force_markets.readable.iter(),
+            [MarketId::spot(out_market), MarketId::spot(in_market)]
+                .iter()
+                .chain(self.force_markets.writeable.iter()),
+        );
+        if out_token_program != in_token_program {
+            accounts.push(AccountMeta::new_readonly(out_token_program, false));
+        }
+        if in_token_program == constants::TOKEN_2022_PROGRAM_ID
+            || out_token_program == constants::TOKEN_2022_PROGRAM_ID
+        {
+            accounts.push(AccountMeta::new_readonly(in_spot_market.mint, false));
+            accounts.push(AccountMeta::new_readonly(out_spot_market.mint, false));
+        }
+
+        // begin and end swap share the same accounts list
+        let begin_ix = Instruction {
+            program_id: constants::PROGRAM_ID,
+            accounts: accounts.clone(),
+            data: InstructionData::data(&drift_idl::instructions::BeginSwap {
+                in_market_index: in_market,
+                out_market_index: out_market,
+                amount_in,
+            }),
+        };
+        let end_ix = Instruction {
+            program_id: constants::PROGRAM_ID,
+            accounts,
+            data: InstructionData::data(&drift_idl::instructions::EndSwap {
+                in_market_index: in_market,
+                out_market_index: out_market,
+                limit_price: None,
+                reduce_only: None,
+            }),
+        };
+
+        self.ixs.push(begin_ix);
+        self.ixs.extend(external_ixs);
+        self.ixs.push(end_ix);
+
+        self
+    }
+
+    /// Provide liquidity to a perp market's AMM by minting LP shares
+    ///
+    /// * `market_index` - perp market to provide liquidity to
+    /// * `n_shares` - number of LP shares to mint (AMM_RESERVE_PRECISION)
+    pub fn add_perp_lp_shares(mut self, market_index: u16, n_shares: u64) -> Self {
+        let accounts = build_accounts(
+            self.program_data,
+            types::accounts::AddPerpLpShares {
+                state: *state_account(),
+                user: self.sub_account,
+                authority: self.authority,
+            },
+            &[self.account_data.as_ref()],
+            self.force_markets.readable.iter(),
+            [MarketId::perp(market_index)]
+                .iter()
+                .chain(self.force_markets.writeable.iter()),
+        );
+
+        let ix = Instruction {
+            program_id: constants::PROGRAM_ID,
+            accounts,
+            data: InstructionData::data(&drift_idl::instructions::AddPerpLpShares {
+                n_shares,
+                market_index,
+            }),
+        };
+        self.ixs.push(ix);
+
+        self
+    }
+
+    /// Withdraw liquidity from a perp market's AMM by burning LP shares
+    ///
+    /// * `market_index` - perp market to withdraw liquidity from
+    /// * `shares_to_burn` - number of LP shares to burn (AMM_RESERVE_PRECISION)
+    pub fn remove_perp_lp_shares(mut self, market_index: u16, shares_to_burn: u64) -> Self {
+        let accounts = build_accounts(
+            self.program_data,
+            types::accounts::RemovePerpLpShares {
+                state: *state_account(),
+                user: self.sub_account,
+                authority: self.authority,
+            },
+            &[self.account_data.as_ref()],
+            self.force_markets.readable.iter(),
+            [MarketId::perp(market_index)]
+                .iter()
+                .chain(self.force_markets.writeable.iter()),
+        );
+
+        let ix = Instruction {
+            program_id: constants::PROGRAM_ID,
+            accounts,
+            data: InstructionData::data(&drift_idl::instructions::RemovePerpLpShares {
+                shares_to_burn,
+                market_index,
+            }),
+        };
+        self.ixs.push(ix);
+
+        self
+    }
+
+    /// Burn a user's LP shares in a perp market that is expiring
+    ///
+    /// permissionless, may be called for any user
+    ///
+    /// * `user_info` - account address and data of the LP user
+    /// * `market_index` - the expiring perp market
+    /// * `shares_to_burn` - number of LP shares to burn (AMM_RESERVE_PRECISION)
+    pub fn remove_perp_lp_shares_in_expiring_market(
+        mut self,
+        user_info: &(Pubkey, User),
+        market_index: u16,
+        shares_to_burn: u64,
+    ) -> Self {
+        let (user, user_account) = user_info;
+        let accounts = build_accounts(
+            self.program_data,
+            types::accounts::RemovePerpLpSharesInExpiringMarket {
+                state: *state_account(),
+                user: *user,
+            },
+            &[user_account],
+            self.force_markets.readable.iter(),
+            [MarketId::perp(market_index)]
+                .iter()
+                .chain(self.force_markets.writeable.iter()),
+        );
+
+        let ix = Instruction {
+            program_id: constants::PROGRAM_ID,
+            accounts,
+            data: InstructionData::data(
+                &drift_idl::instructions::RemovePerpLpSharesInExpiringMarket {
+                    shares_to_burn,
+                    market_index,
+                },
+            ),
+        };
+        self.ixs.push(ix);
+
+        self
+    }
+
+    /// Settle a user's LP position into their perp position
+    ///
+    /// permissionless, may be called for any user
+    ///
+    /// * `settlee_info` - account address and data of the LP user
+    /// * `market_index` - perp market of the LP position
+    pub fn settle_lp(mut self, settlee_info: &(Pubkey, User), market_index: u16) -> Self {
+        let (settlee, settlee_account) = settlee_info;
+        let accounts = build_accounts(
+            self.program_data,
+            types::accounts::SettleLp {
+                state: *state_account(),
+                user: *settlee,
+            },
+            &[settlee_account],
+            self.force_markets.readable.iter(),
+            [MarketId::perp(market_index)]
+                .iter()
+                .chain(self.force_markets.writeable.iter()),
+        );
+
+        let ix = Instruction {
+            program_id: constants::PROGRAM_ID,
+            accounts,
+            data: InstructionData::data(&drift_idl::instructions::SettleLp { market_index }),
+        };
+        self.ixs.push(ix);
+
+        self
+    }
+
+    /// Post Pyth pull oracle price updates
+    ///
+    /// Add before ixs reading the updated oracles (e.g. `place_orders`, `fill_perp_order`) to use fresh prices in the same tx
+    ///
+    /// * `update` - price updates for one or more feeds (see `pyth_pull::PriceUpdateSource`)
+    pub fn post_pyth_pull_update(mut self, update: &pyth_pull::AccumulatorUpdate) -> Self {
+        let guardian_set = pyth_pull::derive_guardian_set(update.guardian_set_index());
+
+        let ix = if let [(feed_id, price_update)] = update.updates() {
+            Instruction {
+                program_id: constants::PROGRAM_ID,
+                accounts: types::accounts::PostPythPullOracleUpdateAtomic {
+                    keeper: self.authority,
+                    pyth_solana_receiver: constants::ids::pyth_solana_receiver_program::ID,
+                    guardian_set,
+                    price_feed: pyth_pull::derive_pyth_pull_oracle(feed_id),
+                }
+                .to_account_metas(),
+                data: InstructionData::data(
+                    &drift_idl::instructions::PostPythPullOracleUpdateAtomic {
+                        feed_id: *feed_id,
+                        params: update.encode_atomic_params(price_update),
+                    },
+                ),
+            }
+        } else {
+            let mut accounts = types::accounts::PostMultiPythPullOracleUpdatesAtomic {
+                keeper: self.authority,
+                pyth_solana_receiver: constants::ids::pyth_solana_receiver_program::ID,
+                guardian_set,
+            }
+            .to_account_metas();
+            accounts.extend(update.updates().iter().map(|(feed_id, _)| {
+                AccountMeta::new(pyth_pull::derive_pyth_pull_oracle(feed_id), false)
+            }));
+            Instruction {
+                program_id: constants::PROGRAM_ID,
+                accounts,
+                data: InstructionData::data(
+                    &drift_idl::instructions::PostMultiPythPullOracleUpdatesAtomic {
+                        params: update.encode_multi_atomic_params(),
+                    },
+                ),
+            }
+        };
+        self.ixs.push(ix);
+
+        self
+    }
+
+    /// Initialize the authority's swift user orders account, required to place swift orders as taker
+    ///
+    /// * `num_orders` - max. number of swift orders tracked by the account
+    pub fn initialize_swift_user_orders(mut self, num_orders: u16) -> Self {
+        let accounts = types::accounts::InitializeSwiftUserOrders {
+            swift_user_orders: swift::derive_swift_user_orders(&self.authority),
+            authority: self.authority,
+            user: self.sub_account,
+            payer: self.authority,
+            rent: solana_sdk::sysvar::rent::ID,
+            system_program: solana_sdk::system_program::ID,
+        }
+        .to_account_metas();
+
+        let ix = Instruction {
+            program_id: constants::PROGRAM_ID,
+            accounts,
+            data: InstructionData::data(&drift_idl::instructions::InitializeSwiftUserOrders {
+                num_orders,
+            }),
+        };
+        self.ixs.push(ix);
+
+        self
+    }
+
+    /// Place a taker's signed swift order
+    ///
+    /// Adds an ed25519 verify ix authenticating the order followed by the place ix
+    ///
+    /// * `taker_info` - taker account address and data
+    /// * `signed_order` - the taker's signed swift order
+    pub fn place_swift_taker_order(
+        mut self,
+        taker_info: &(Pubkey, User),
+        signed_order: &swift::SignedSwiftOrder,
+    ) -> Self {
+        let (taker, taker_account) = taker_info;
+        let market_index = signed_order.message.swift_order_params.market_index;
+        let accounts = build_accounts(
+            self.program_data,
+            types::accounts::PlaceSwiftTakerOrder {
+                state: *state_account(),
+                user: *taker,
+                user_stats: Wallet::derive_stats_account(&taker_account.authority),
+                swift_user_orders: swift::derive_swift_user_orders(&taker_account.authority),
+                authority: self.authority,
+                ix_sysvar: solana_sdk::sysvar::instructions::ID,
+            },
+            &[taker_account],
+            [MarketId::perp(market_index)]
+                .iter()
+                .chain(self.force_markets.readable.iter()),
+            self.force_markets.writeable.iter(),
+        );
+
+        let ix = Instruction {
+            program_id: constants::PROGRAM_ID,
+            accounts,
+            data: InstructionData::data(&drift_idl::instructions::PlaceSwiftTakerOrder {
+                swift_order_params_message_bytes: signed_order.message_bytes.clone(),
+            }),
+        };
+        self.ixs.push(signed_order.to_ed25519_verify_ix());
+        self.ixs.push(ix);
+
+        self
+    }
+
+    /// Place a taker's signed swift order and fill it against a new maker order for this account
+    ///
+    /// * `taker_info` - taker account address and data
+    /// * `signed_order` - the taker's signed swift order
+    /// * `maker_order` - the maker's order params
+    pub fn place_and_make_swift_perp_order(
+        self,
+        taker_info: &(Pubkey, User),
+        signed_order: &swift::SignedSwiftOrder,
+        maker_order: OrderParams,
+    ) -> Self {
+        let mut this = self.place_swift_taker_order(taker_info, signed_order);
+        let (taker, taker_account) = taker_info;
+        let accounts = build_accounts(
+            this.program_data,
+            types::accounts::PlaceAndMakeSwiftPerpOrder {
+                state: *state_account(),
+                user: this.sub_account,
+                user_stats: Wallet::derive_stats_account(&this.authority),
+                taker: *taker,
+                taker_stats: Wallet::derive_stats_account(&taker_account.authority),
+                taker_swift_user_orders: swift::derive_swift_user_orders(&taker_account.authority),
+                authority: this.authority,
+            },
+            &[this.account_data.as_ref(), taker_account],
+            this.force_markets.readable.iter(),
+            [MarketId::perp(maker_order.market_index)]
+                .iter()
+                .chain(this.force_markets.writeable.iter()),
+        );
+
+        let ix = Instruction {
+            program_id: constants::PROGRAM_ID,
+            accounts,
+            data: InstructionData::data(&drift_idl::instructions::PlaceAndMakeSwiftPerpOrder {
+                params: maker_order,
+                swift_order_uuid: signed_order.message.uuid,
+            }),
+        };
+        this.ixs.push(ix);
+
+        this
+    }
+
+    /// Enable high leverage mode for this sub-account
+    ///
+    /// requires an open slot, see `DriftClient::high_leverage_mode_remaining_slots`
+    pub fn enable_high_leverage_mode(mut self) -> Self {
+        let accounts = build_accounts(
+            self.program_data,
+            types::accounts::EnableUserHighLeverageMode {
+                state: *state_account(),
+                user: self.sub_account,
+                authority: self.authority,
+                high_leverage_mode_config: *high_leverage_mode_config_account(),
+            },
+            &[self.account_data.as_ref()],
+            self.force_markets.readable.iter(),
+            self.force_markets.writeable.iter(),
+        );
+
+        let ix = Instruction {
+            program_id: constants::PROGRAM_ID,
+            accounts,
+            data: InstructionData::data(&drift_idl::instructions::EnableUserHighLeverageMode {
+                sub_account_id: self.account_data.sub_account_id,
+            }),
+        };
+        self.ixs.push(ix);
+
+        self
+    }
+
+    /// Disable high leverage mode for this sub-account, freeing its slot
+    pub fn disable_high_leverage_mode(mut self) -> Self {
+        let accounts = build_accounts(
+            self.program_data,
+            types::accounts::DisableUserHighLeverageMode {
+                state: *state_account(),
+                authority: self.authority,
+                user: self.sub_account,
+                high_leverage_mode_config: *high_leverage_mode_config_account(),
+            },
+            &[self.account_data.as_ref()],
+            self.force_markets.readable.iter(),
+            self.force_markets.writeable.iter(),
+        );
+
+        let ix = Instruction {
+            program_id: constants::PROGRAM_ID,
+            accounts,
+            data: InstructionData::data(&drift_idl::instructions::DisableUserHighLeverageMode {}),
+        };
+        self.ixs.push(ix);
+
+        self
+    }
+
+    /// Initialize the RFQ user account of this sub-account
     ///
-    /// * `order` - the order to place
-    /// * `maker_info` - pubkey of the maker/counterparty to take against and account data
-    /// * `referrer` - pubkey of the maker's referrer account, if any
-    /// * `fulfillment_type` - type of fill for spot orders, ignored for perp orders
-    pub fn place_and_take(
-        mut self,
-        order: OrderParams,
-        maker_info: Option<(Pubkey, User)>,
-        referrer: Option<Pubkey>,
-        fulfillment_type: Option<SpotFulfillmentType>,
-        success_condition: Option<u32>,
-    ) -> Self {
-        let mut user_accounts = vec![self.account_data.as_ref()];
-        if let Some((ref _maker_pubkey, ref maker)) = maker_info {
-            user_accounts.push(maker);
+    /// required before quoting RFQs as a maker
+    pub fn initialize_rfq_user(mut self) -> Self {
+        let accounts = types::accounts::InitializeRfqUser {
+            rfq_user: rfq::derive_rfq_user(&self.sub_account),
+            authority: self.authority,
+            user: self.sub_account,
+            payer: self.authority,
+            rent: solana_sdk::sysvar::rent::ID,
+            system_program: solana_sdk::system_program::ID,
         }
+        .to_account_metas();
 
-        let is_perp = order.market_type == MarketType::Perp;
-        let perp_writable = [MarketId::perp(order.market_index)];
-        let spot_writable = [MarketId::spot(order.market_index), MarketId::QUOTE_SPOT];
+        let ix = Instruction {
+            program_id: constants::PROGRAM_ID,
+            accounts,
+            data: InstructionData::data(&drift_idl::instructions::InitializeRfqUser {}),
+        };
+        self.ixs.push(ix);
+
+        self
+    }
 
+    /// Fill signed RFQ maker quotes with this account as taker
+    ///
+    /// Adds an ed25519 verify ix authenticating each maker quote followed by the match ix
+    ///
+    /// * `rfq_matches` - signed maker quotes and amounts to fill, see `RFQMakerMessage::to_match`
+    pub fn place_and_match_rfq_orders(mut self, rfq_matches: &[RFQMatch]) -> Self {
+        let markets: Vec<MarketId> = rfq_matches
+            .iter()
+            .map(|m| {
+                MarketId::new(
+                    m.maker_order_params.market_index,
+                    m.maker_order_params.market_type,
+                )
+            })
+            .collect();
         let mut accounts = build_accounts(
             self.program_data,
-            types::accounts::PlaceAndTakePerpOrder {
+            types::accounts::PlaceAndMatchRfqOrders {
                 state: *state_account(),
-                authority: self.authority,
                 user: self.sub_account,
                 user_stats: Wallet::derive_stats_account(&self.authority),
+                authority: self.authority,
+                ix_sysvar: solana_sdk::sysvar::instructions::ID,
             },
-            user_accounts.as_slice(),
+            &[self.account_data.as_ref()],
             self.force_markets.readable.iter(),
-            if is_perp {
-                perp_writable.iter()
-            } else {
-                spot_writable.iter()
-            }
-            .chain(self.force_markets.writeable.iter()),
+            markets.iter().chain(self.force_markets.writeable.iter()),
         );
 
-        if referrer.is_some_and(|r| !maker_info.is_some_and(|(m, _)| m == r)) {
-            let referrer = referrer.unwrap();
+        // makers must be included as writable (user, user stats, rfq user) triples
+        for rfq_match in rfq_matches {
+            let maker = rfq_match.maker();
+            accounts.push(AccountMeta::new(maker, false));
             accounts.push(AccountMeta::new(
-                Wallet::derive_stats_account(&referrer),
+                Wallet::derive_stats_account(&rfq_match.maker_order_params.authority),
                 false,
             ));
-            accounts.push(AccountMeta::new(referrer, false));
+            accounts.push(AccountMeta::new(rfq::derive_rfq_user(&maker), false));
         }
 
-        let ix = if is_perp {
-            Instruction {
-                program_id: constants::PROGRAM_ID,
-                accounts,
-                data: InstructionData::data(&drift_idl::instructions::PlaceAndTakePerpOrder {
-                    params: order,
-                    success_condition,
-                }),
-            }
-        } else {
-            Instruction {
-                program_id: constants::PROGRAM_ID,
-                accounts,
-                data: InstructionData::data(&drift_idl::instructions::PlaceAndTakeSpotOrder {
-                    params: order,
-                    maker_order_id: None,
-                    fulfillment_type,
-                }),
-            }
+        let ix = Instruction {
+            program_id: constants::PROGRAM_ID,
+            accounts,
+            data: InstructionData::data(&drift_idl::instructions::PlaceAndMatchRfqOrders {
+                rfq_matches: rfq_matches.to_vec(),
+            }),
         };
-
+        self.ixs
+            .extend(rfq_matches.iter().map(RFQMatch::to_ed25519_verify_ix));
         self.ixs.push(ix);
+
         self
     }
 
     /// Build the transaction message ready for signing and sending
-    pub fn build(self) -> VersionedMessage {
+    pub fn build(mut self) -> VersionedMessage {
+        if let Some(tip) = self.tip.take() {
+            self.ixs.push(tip);
+        }
+        if let Some(nonce) = self.nonce.take() {
+            self.ixs.insert(0, nonce);
+        }
         if self.legacy {
             let message = Message::new(self.ixs.as_ref(), Some(&self.authority));
             VersionedMessage::Legacy(message)
@@ -1619,11 +3775,36 @@ pub fn build_accounts<'a>(
     account_metas
 }
 
+/// Append maker and referrer accounts for a fill ix
+///
+/// makers must be included as writable (user, user stats) pairs, the referrer is
+/// skipped if it is already included as a maker
+fn extend_fill_accounts(
+    accounts: &mut Vec<AccountMeta>,
+    makers: &[(Pubkey, User)],
+    referrer: Option<ReferrerInfo>,
+) {
+    for (maker, maker_account) in makers {
+        accounts.push(AccountMeta::new(*maker, false));
+        accounts.push(AccountMeta::new(
+            Wallet::derive_stats_account(&maker_account.authority),
+            false,
+        ));
+    }
+
+    if let Some(referrer) = referrer {
+        if !makers.iter().any(|(m, _)| *m == referrer.referrer()) {
+            accounts.push(AccountMeta::new(referrer.referrer(), false));
+            accounts.push(AccountMeta::new(referrer.referrer_stats(), false));
+        }
+    }
+}
+
 /// Drift wallet
 #[derive(Clone, Debug)]
 pub struct Wallet {
-    /// The signing keypair, it could be authority or delegate
-    signer: Arc<Keypair>,
+    /// The tx signer, it could be authority or delegate
+    signer: Arc<dyn signer::DriftSigner>,
     /// The drift 'authority' account
     /// user (sub)accounts are derived from this
     authority: Pubkey,
@@ -1672,6 +3853,18 @@ impl Wallet {
             signer: Arc::new(authority),
         }
     }
+    /// Init wallet with a custom signer e.g. a remote signing service, uses default sub-account
+    ///
+    /// * `signer` - signer for txs and messages, its (primary) pubkey is the wallet authority
+    pub fn from_signer(signer: impl signer::DriftSigner + 'static) -> Self {
+        let signer: Arc<dyn signer::DriftSigner> = Arc::new(signer);
+        let authority = signer.pubkey();
+        Self {
+            stats: Wallet::derive_stats_account(&authority),
+            authority,
+            signer,
+        }
+    }
     /// Convert the wallet into a delegated one by providing the `authority` public key
     pub fn to_delegated(&mut self, authority: Pubkey) {
         self.stats = Wallet::derive_stats_account(&authority);
@@ -1699,21 +3892,53 @@ impl Wallet {
         account_drift_pda
     }
 
+    /// Calculate the address of a drift insurance fund stake account
+    pub fn derive_insurance_fund_stake(authority: &Pubkey, market_index: u16) -> Pubkey {
+        let (account_drift_pda, _seed) = Pubkey::find_program_address(
+            &[
+                &b"insurance_fund_stake"[..],
+                authority.as_ref(),
+                &market_index.to_le_bytes(),
+            ],
+            &constants::PROGRAM_ID,
+        );
+        account_drift_pda
+    }
+
     /// Signs the given tx `message` returning the tx on success
-    pub fn sign_tx(
+    ///
+    /// Returns error if the wallet signer does not provide exactly the tx's required signatures
+    pub async fn sign_tx(
         &self,
         mut message: VersionedMessage,
         recent_block_hash: Hash,
     ) -> SdkResult<VersionedTransaction> {
         message.set_recent_blockhash(recent_block_hash);
-        let signer: &dyn Signer = self.signer.as_ref();
-        VersionedTransaction::try_new(message, &[signer]).map_err(Into::into)
+        let num_signers = message.header().num_required_signatures as usize;
+        let signatures = self.signer.sign_tx_message(&message).await?;
+
+        let signer_keys = &message.static_account_keys()[..num_signers];
+        let mut tx_signatures = vec![Signature::default(); num_signers];
+        for (pubkey, signature) in signatures {
+            let idx = signer_keys
+                .iter()
+                .position(|k| *k == pubkey)
+                .ok_or(SignerError::KeypairPubkeyMismatch)?;
+            tx_signatures[idx] = signature;
+        }
+        if tx_signatures.contains(&Signature::default()) {
+            return Err(SignerError::NotEnoughSigners.into());
+        }
+
+        Ok(VersionedTransaction {
+            signatures: tx_signatures,
+            message,
+        })
     }
 
     /// Sign message with the wallet's signer
-    pub fn sign_message(&self, message: &[u8]) -> SdkResult<Signature> {
-        let signer: &dyn Signer = self.signer.as_ref();
-        Ok(signer.sign_message(message))
+    pub async fn sign_message(&self, message: &[u8]) -> SdkResult<Signature> {
+        self.signer.sign_message(message).await
     }
     /// Return the wallet authority address
     pub fn authority(&self) -> &Pubkey {
@@ -1754,6 +3979,7 @@ mod tests {
         rpc_request::RpcRequest,
         rpc_response::{Response, RpcResponseContext},
     };
+    use solana_sdk::native_token::LAMPORTS_PER_SOL;
     use types::accounts::PerpMarket;
 
     use super::*;
@@ -1786,12 +4012,14 @@ mod tests {
                 DEVNET_ENDPOINT.to_string(),
                 CommitmentConfig::processed(),
             ),
+            failover: None,
         };
 
         DriftClient {
             context: Context::DevNet,
             backend: Box::leak(Box::new(backend)),
             wallet: Wallet::new(keypair),
+            send_endpoints: None,
         }
     }
 
@@ -1837,6 +4065,70 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn estimate_compute_units() {
+        let mut rpc_mocks = Mocks::default();
+        rpc_mocks.insert(
+            RpcRequest::SimulateTransaction,
+            json!({
+                "context": { "slot": 12_345 },
+                "value": { "err": null, "logs": [], "unitsConsumed": 100_000 },
+            }),
+        );
+        let keypair = Keypair::new();
+        let payer = keypair.pubkey();
+        let client = setup(rpc_mocks, keypair).await;
+
+        let ixs = [
+            ComputeBudgetInstruction::set_compute_unit_price(1_000),
+            Instruction::new_with_bytes(Pubkey::new_unique(), &[1], vec![]),
+        ];
+        let tx = VersionedMessage::Legacy(Message::new(&ixs, Some(&payer)));
+        let tx = client
+            .with_estimated_compute_units(tx, ComputeUnitMargin::new(20, 0))
+            .await
+            .unwrap();
+
+        assert_eq!(compute_units::compute_unit_limit(&tx), Some(120_000));
+    }
+
+    #[tokio::test]
+    async fn simulate_report() {
+        let cases = [
+            (PROGRAM_ID, Some(errors::ErrorCode::InsufficientCollateral)),
+            // custom error of a non-drift program
+            (Pubkey::new_unique(), None),
+        ];
+        for (program_id, expected_code) in cases {
+            let mut rpc_mocks = Mocks::default();
+            rpc_mocks.insert(
+                RpcRequest::SimulateTransaction,
+                json!({
+                    "context": { "slot": 12_345 },
+                    "value": {
+                        "err": { "InstructionError": [0, { "Custom": 6_003 }] },
+                        "logs": ["Program log: Instruction: PlaceOrders"],
+                        "unitsConsumed": 80_000,
+                    },
+                }),
+            );
+            let keypair = Keypair::new();
+            let payer = keypair.pubkey();
+            let client = setup(rpc_mocks, keypair).await;
+
+            let ix = Instruction::new_with_bytes(program_id, &[1], vec![]);
+            let tx = VersionedMessage::Legacy(Message::new(&[ix], Some(&payer)));
+            let report = client.simulate(&tx).await.unwrap();
+
+            assert_eq!(report.slot, 12_345);
+            assert_eq!(report.units_consumed, Some(80_000));
+            assert!(!report.is_success());
+            assert_eq!(report.error_code, expected_code);
+            assert_eq!(report.logs.len(), 1);
+            assert!(report.events.is_empty());
+        }
+    }
+
     #[tokio::test]
     async fn get_orders() {
         let user = Pubkey::from_str("9JtczxrJjPM4J1xooxr2rFXmRivarb4BwjNiBgXDwe2p").unwrap();
@@ -1865,6 +4157,140 @@ mod tests {
         assert_eq!(orders.len(), 3);
     }
 
+    /// Init a `DriftClient` served by `MockAccounts` with users of `authority_a`, `authority_b`
+    /// and some sub-accounts delegated to `delegate`
+    async fn setup_users(
+        authority_a: Pubkey,
+        authority_b: Pubkey,
+        delegate: Pubkey,
+    ) -> DriftClient {
+        let accounts = Arc::new(mock::MockAccounts::default());
+        for (authority, sub_account_id, delegate) in [
+            (authority_a, 1, delegate),
+            (authority_b, 0, delegate),
+            (authority_a, 0, Pubkey::default()),
+            (authority_b, 1, Pubkey::default()),
+        ] {
+            accounts.set_user(
+                Wallet::derive_user_account(&authority, sub_account_id),
+                &User {
+                    authority,
+                    delegate,
+                    sub_account_id,
+                    ..Default::default()
+                },
+            );
+        }
+
+        DriftClient::new_mock(Context::DevNet, accounts, Wallet::read_only(authority_a))
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn get_user_accounts_by_authority() {
+        let authority = Pubkey::new_unique();
+        let client = setup_users(authority, Pubkey::new_unique(), Pubkey::new_unique()).await;
+
+        let users = client
+            .get_user_accounts_by_authority(&authority)
+            .await
+            .unwrap();
+        assert_eq!(users.len(), 2);
+        for (expected_id, (pubkey, user)) in users.iter().enumerate() {
+            assert_eq!(user.authority, authority);
+            assert_eq!(user.sub_account_id, expected_id as u16);
+            assert_eq!(
+                *pubkey,
+                Wallet::derive_user_account(&authority, expected_id as u16)
+            );
+        }
+
+        assert!(client
+            .get_user_accounts_by_authority(&Pubkey::new_unique())
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn get_delegated_accounts() {
+        let authority_a = Pubkey::new_unique();
+        let authority_b = Pubkey::new_unique();
+        let delegate = Pubkey::new_unique();
+        let client = setup_users(authority_a, authority_b, delegate).await;
+
+        let users = client.get_delegated_accounts(&delegate).await.unwrap();
+        let mut expected = vec![
+            (Wallet::derive_user_account(&authority_a, 1), authority_a, 1),
+            (Wallet::derive_user_account(&authority_b, 0), authority_b, 0),
+        ];
+        expected.sort_by_key(|(_, authority, id)| (*authority, *id));
+        assert_eq!(
+            users
+                .iter()
+                .map(|(pubkey, user)| (*pubkey, user.authority, user.sub_account_id))
+                .collect::<Vec<_>>(),
+            expected
+        );
+        assert!(users.iter().all(|(_, user)| user.delegate == delegate));
+
+        // authority is not a delegate
+        assert!(client
+            .get_delegated_accounts(&authority_a)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn create_next_sub_account() {
+        let accounts = Arc::new(mock::MockAccounts::default());
+        let mut state = State {
+            max_number_of_sub_accounts: 10,
+            max_initialize_user_fee: 100,
+            number_of_sub_accounts: 1_000,
+            ..Default::default()
+        };
+        accounts.set_state(&state);
+        let keypair = Keypair::new();
+        let authority = keypair.pubkey();
+        let fund = |lamports: u64| {
+            accounts.set_account(
+                authority,
+                Account {
+                    lamports,
+                    owner: solana_sdk::system_program::ID,
+                    ..Default::default()
+                },
+            );
+        };
+        let client = DriftClient::new_mock(Context::DevNet, Arc::clone(&accounts), keypair.into())
+            .await
+            .unwrap();
+
+        // exchange is full
+        assert!(client.create_next_sub_account("test").await.is_err());
+
+        // init fee is 0.5 SOL
+        state.number_of_sub_accounts = 900;
+        accounts.set_state(&state);
+        fund(LAMPORTS_PER_SOL / 2);
+        assert!(matches!(
+            client.create_next_sub_account("test").await,
+            Err(SdkError::OutOfSOL)
+        ));
+        assert!(accounts.sent_transactions().is_empty());
+
+        fund(LAMPORTS_PER_SOL);
+        let (sub_account, _) = client.create_next_sub_account("test").await.unwrap();
+        assert_eq!(sub_account, Wallet::derive_user_account(&authority, 0));
+        let sent = accounts.sent_transactions();
+        assert_eq!(sent.len(), 1);
+        // initializes user stats then the user
+        assert_eq!(sent[0].message.instructions().len(), 2);
+    }
+
     #[tokio::test]
     async fn get_positions() {
         let user = Pubkey::from_str("9JtczxrJjPM4J1xooxr2rFXmRivarb4BwjNiBgXDwe2p").unwrap();
@@ -1893,6 +4319,400 @@ mod tests {
         assert_eq!(perp.len(), 1);
     }
 
+    #[test]
+    fn fill_accounts_dedup_referrer() {
+        let maker_authority = Pubkey::new_unique();
+        let maker = Wallet::derive_user_account(&maker_authority, 0);
+        let maker_account = User {
+            authority: maker_authority,
+            ..Default::default()
+        };
+        let makers = [(maker, maker_account)];
+
+        // referrer is also a maker
+        let mut accounts = vec![];
+        let referrer = ReferrerInfo::new(maker, Wallet::derive_stats_account(&maker_authority));
+        extend_fill_accounts(&mut accounts, &makers, Some(referrer));
+        assert_eq!(
+            accounts,
+            vec![
+                AccountMeta::new(maker, false),
+                AccountMeta::new(Wallet::derive_stats_account(&maker_authority), false),
+            ]
+        );
+
+        // distinct referrer
+        let mut accounts = vec![];
+        let referrer_authority = Pubkey::new_unique();
+        let referrer = ReferrerInfo::new(
+            Wallet::derive_user_account(&referrer_authority, 0),
+            Wallet::derive_stats_account(&referrer_authority),
+        );
+        extend_fill_accounts(&mut accounts, &makers, Some(referrer));
+        assert_eq!(accounts.len(), 4);
+        assert!(accounts.iter().all(|a| a.is_writable && !a.is_signer));
+        assert_eq!(accounts[2].pubkey, referrer.referrer());
+    }
+
+    #[test]
+    fn swap_wraps_external_ixs() {
+        let spot_markets = vec![
+            SpotMarket {
+                market_index: 0,
+                mint: Pubkey::new_unique(),
+                ..Default::default()
+            },
+            SpotMarket {
+                market_index: 1,
+                mint: Pubkey::new_unique(),
+                ..Default::default()
+            },
+        ];
+        let program_data = ProgramData::new(
+            spot_markets,
+            vec![],
+            AddressLookupTableAccount {
+                key: Pubkey::new_unique(),
+                addresses: vec![],
+            },
+        );
+        let authority = Pubkey::new_unique();
+        let user = User {
+            authority,
+            ..Default::default()
+        };
+        let swap_ix = Instruction::new_with_bytes(Pubkey::new_unique(), &[1], vec![]);
+
+        let builder = TransactionBuilder::new(
+            &program_data,
+            Wallet::derive_user_account(&authority, 0),
+            Cow::Owned(user),
+            false,
+        )
+        .swap(1, 0, 1_000, vec![swap_ix.clone()]);
+
+        assert_eq!(builder.ixs.len(), 3);
+        assert_eq!(builder.ixs[1], swap_ix);
+        assert_eq!(builder.ixs[0].accounts, builder.ixs[2].accounts);
+        let in_token_account = constants::derive_associated_token_account(
+            &authority,
+            &program_data.spot_market_config_by_index(1).unwrap().mint,
+            &constants::TOKEN_PROGRAM_ID,
+        );
+        assert_eq!(builder.ixs[0].accounts[7].pubkey, in_token_account);
+    }
+
+    #[test]
+    fn post_pyth_pull_update_ixs() {
+        let program_data = ProgramData::uninitialized();
+        let user = User::default();
+        let builder = |feed_ids: &[pyth_pull::FeedId]| {
+            let update = pyth_pull::AccumulatorUpdate::try_from_bytes(
+                &pyth_pull::tests::accumulator_update_data(feed_ids),
+            )
+            .unwrap();
+            TransactionBuilder::new(
+                &program_data,
+                Pubkey::new_unique(),
+                Cow::Borrowed(&user),
+                false,
+            )
+            .post_pyth_pull_update(&update)
+        };
+
+        let single = builder(&[[1_u8; 32]]);
+        assert_eq!(
+            single.ixs[0].accounts[3].pubkey,
+            pyth_pull::derive_pyth_pull_oracle(&[1_u8; 32])
+        );
+
+        let multi = builder(&[[1_u8; 32], [2_u8; 32]]);
+        let accounts = &multi.ixs[0].accounts;
+        assert_eq!(accounts.len(), 5);
+        assert!(accounts[3..].iter().all(|a| a.is_writable));
+        assert_eq!(
+            accounts[4].pubkey,
+            pyth_pull::derive_pyth_pull_oracle(&[2_u8; 32])
+        );
+    }
+
+    #[tokio::test]
+    async fn place_and_match_rfq_orders_ixs() {
+        let program_data = ProgramData::new(
+            vec![SpotMarket {
+                market_index: 0,
+                ..Default::default()
+            }],
+            vec![PerpMarket {
+                market_index: 0,
+                pubkey: Pubkey::new_unique(),
+                ..Default::default()
+            }],
+            AddressLookupTableAccount {
+                key: Pubkey::new_unique(),
+                addresses: vec![],
+            },
+        );
+        let maker = Wallet::new(Keypair::new());
+        let order_params = rfq::maker_order_params(
+            &maker,
+            1,
+            MarketId::perp(0),
+            1_000,
+            100,
+            1_000,
+            *b"rfq-uuid",
+        );
+        let rfq_match = rfq::sign_maker_order(&maker, order_params)
+            .await
+            .unwrap()
+            .to_match(500);
+
+        let user = User::default();
+        let builder = TransactionBuilder::new(
+            &program_data,
+            Pubkey::new_unique(),
+            Cow::Borrowed(&user),
+            false,
+        )
+        .place_and_match_rfq_orders(&[rfq_match]);
+
+        assert_eq!(builder.ixs.len(), 2);
+        assert_eq!(builder.ixs[0].program_id, solana_sdk::ed25519_program::ID);
+        let accounts = &builder.ixs[1].accounts;
+        let maker_account = Wallet::derive_user_account(maker.authority(), 1);
+        let n = accounts.len();
+        assert_eq!(accounts[n - 3].pubkey, maker_account);
+        assert_eq!(accounts[n - 2].pubkey, maker.stats);
+        assert_eq!(accounts[n - 1].pubkey, rfq::derive_rfq_user(&maker_account));
+        assert!(accounts[n - 3..].iter().all(|a| a.is_writable));
+        let perp_market = program_data.perp_market_config_by_index(0).unwrap().pubkey;
+        assert!(accounts
+            .iter()
+            .any(|a| a.pubkey == perp_market && a.is_writable));
+    }
+
+    #[test]
+    fn transfer_deposit_accounts() {
+        let spot_markets: Vec<SpotMarket> = (0..2)
+            .map(|market_index| SpotMarket {
+                market_index,
+                pubkey: Pubkey::new_unique(),
+                oracle: Pubkey::new_unique(),
+                ..Default::default()
+            })
+            .collect();
+        let program_data = ProgramData::new(
+            spot_markets.clone(),
+            vec![],
+            AddressLookupTableAccount {
+                key: Pubkey::new_unique(),
+                addresses: vec![],
+            },
+        );
+        let authority = Pubkey::new_unique();
+        let mut user = User {
+            authority,
+            sub_account_id: 1,
+            ..Default::default()
+        };
+        user.spot_positions[0] = SpotPosition {
+            market_index: 1,
+            scaled_balance: 1_000,
+            balance_type: SpotBalanceType::Deposit,
+            ..Default::default()
+        };
+        let sub_account = Wallet::derive_user_account(&authority, 1);
+
+        let tx = TransactionBuilder::new(&program_data, sub_account, Cow::Borrowed(&user), false)
+            .transfer_deposit(1, 0, 0, 1_000)
+            .legacy()
+            .build();
+
+        let ix = &tx.instructions()[0];
+        let keys = tx.static_account_keys();
+        let accounts: Vec<Pubkey> = ix.accounts.iter().map(|idx| keys[*idx as usize]).collect();
+        assert_eq!(accounts[0], sub_account);
+        assert_eq!(accounts[1], Wallet::derive_user_account(&authority, 0));
+        // the source account's positions and the transferred market
+        for market in &spot_markets {
+            assert!(accounts.contains(&market.pubkey));
+            assert!(accounts.contains(&market.oracle));
+        }
+    }
+
+    #[test]
+    fn tip_ix_is_last() {
+        let program_data = ProgramData::new(
+            vec![],
+            vec![],
+            AddressLookupTableAccount {
+                key: Pubkey::new_unique(),
+                addresses: vec![],
+            },
+        );
+        let user = User {
+            authority: Pubkey::new_unique(),
+            ..Default::default()
+        };
+        let tip_account = Pubkey::new_unique();
+        let tx = TransactionBuilder::new(
+            &program_data,
+            Pubkey::new_unique(),
+            Cow::Borrowed(&user),
+            false,
+        )
+        .with_tip(&tip_account, 1_000)
+        .with_priority_fee(1_000, None)
+        .cancel_all_orders()
+        .legacy()
+        .build();
+
+        let ixs = tx.instructions();
+        assert_eq!(ixs.len(), 3);
+        let tip_ix = ixs.last().unwrap();
+        let keys = tx.static_account_keys();
+        assert_eq!(
+            keys[tip_ix.program_id_index as usize],
+            solana_sdk::system_program::ID
+        );
+        assert_eq!(keys[tip_ix.accounts[0] as usize], user.authority);
+        assert_eq!(keys[tip_ix.accounts[1] as usize], tip_account);
+    }
+
+    #[test]
+    fn nonce_ix_is_first() {
+        let program_data = ProgramData::new(
+            vec![],
+            vec![],
+            AddressLookupTableAccount {
+                key: Pubkey::new_unique(),
+                addresses: vec![],
+            },
+        );
+        let user = User {
+            authority: Pubkey::new_unique(),
+            ..Default::default()
+        };
+        let nonce_account = Pubkey::new_unique();
+        let tx = TransactionBuilder::new(
+            &program_data,
+            Pubkey::new_unique(),
+            Cow::Borrowed(&user),
+            false,
+        )
+        .with_nonce(nonce_account)
+        .with_priority_fee(1_000, Some(200_000))
+        .cancel_all_orders()
+        .legacy()
+        .build();
+
+        let ixs = tx.instructions();
+        assert_eq!(ixs.len(), 4);
+        let keys = tx.static_account_keys();
+        assert_eq!(
+            keys[ixs[0].program_id_index as usize],
+            solana_sdk::system_program::ID
+        );
+        assert_eq!(keys[ixs[0].accounts[0] as usize], nonce_account);
+        // nonce authority
+        assert_eq!(keys[ixs[0].accounts[2] as usize], user.authority);
+    }
+
+    #[tokio::test]
+    async fn nonce_ix_is_first_with_estimated_compute_units() {
+        let mut rpc_mocks = Mocks::default();
+        rpc_mocks.insert(
+            RpcRequest::SimulateTransaction,
+            json!({
+                "context": { "slot": 12_345 },
+                "value": { "err": null, "logs": [], "unitsConsumed": 100_000 },
+            }),
+        );
+        let keypair = Keypair::new();
+        let user = User {
+            authority: keypair.pubkey(),
+            ..Default::default()
+        };
+        let client = setup(rpc_mocks, keypair).await;
+        let program_data = ProgramData::new(
+            vec![],
+            vec![],
+            AddressLookupTableAccount {
+                key: Pubkey::new_unique(),
+                addresses: vec![],
+            },
+        );
+        let nonce_account = Pubkey::new_unique();
+        let tx = TransactionBuilder::new(
+            &program_data,
+            Pubkey::new_unique(),
+            Cow::Borrowed(&user),
+            false,
+        )
+        .with_nonce(nonce_account)
+        .cancel_all_orders()
+        .legacy()
+        .build();
+        let tx = client
+            .with_estimated_compute_units(tx, ComputeUnitMargin::new(20, 0))
+            .await
+            .unwrap();
+
+        assert_eq!(compute_units::compute_unit_limit(&tx), Some(120_000));
+        let ixs = tx.instructions();
+        assert_eq!(ixs.len(), 3);
+        let keys = tx.static_account_keys();
+        assert_eq!(
+            keys[ixs[0].program_id_index as usize],
+            solana_sdk::system_program::ID
+        );
+        assert_eq!(keys[ixs[0].accounts[0] as usize], nonce_account);
+        assert_eq!(
+            keys[ixs[1].program_id_index as usize],
+            solana_sdk::compute_budget::ID
+        );
+    }
+
+    #[tokio::test]
+    async fn wallet_sign_tx_with_signer() {
+        let payer = Keypair::new();
+        let co_signer = Keypair::new();
+        let ix = Instruction::new_with_bytes(
+            Pubkey::new_unique(),
+            &[1],
+            vec![AccountMeta::new_readonly(co_signer.pubkey(), true)],
+        );
+        let message = VersionedMessage::Legacy(Message::new(&[ix], Some(&payer.pubkey())));
+
+        // missing co-signer
+        let wallet = Wallet::new(Keypair::from_bytes(&payer.to_bytes()).unwrap());
+        assert!(wallet
+            .sign_tx(message.clone(), Hash::new_unique())
+            .await
+            .is_err());
+
+        let wallet = Wallet::from_signer(signer::MultiSigner::new(vec![
+            Arc::new(payer),
+            Arc::new(co_signer),
+        ]));
+        let tx = wallet.sign_tx(message, Hash::new_unique()).await.unwrap();
+        assert!(tx.verify_with_results().iter().all(|ok| *ok));
+
+        // ordinary single signer tx
+        let message = VersionedMessage::Legacy(Message::new(
+            &[Instruction::new_with_bytes(
+                Pubkey::new_unique(),
+                &[1],
+                vec![],
+            )],
+            Some(wallet.authority()),
+        ));
+        let tx = wallet.sign_tx(message, Hash::new_unique()).await.unwrap();
+        assert_eq!(tx.signatures.len(), 1);
+        assert!(tx.verify_with_results().iter().all(|ok| *ok));
+    }
+
     #[test]
     fn wallet_read_only() {
         let keypair = Keypair::new();
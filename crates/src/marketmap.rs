@@ -19,13 +19,17 @@ use solana_client::{
 };
 use solana_sdk::{clock::Slot, pubkey::Pubkey};
 
+#[cfg(feature = "grpc")]
+use crate::grpc::GrpcSubscriber;
 use crate::{
+    account_provider::AccountProvider,
     accounts::State,
     constants::{self, derive_perp_market_account, derive_spot_market_account, state_account},
     drift_idl::types::OracleSource,
+    failover::RpcEndpoints,
     memcmp::get_market_filter,
     utils::get_ws_url,
-    websocket_account_subscriber::WebsocketAccountSubscriber,
+    websocket_account_subscriber::{AccountUpdate, WebsocketAccountSubscriber},
     DataAndSlot, MarketId, MarketType, PerpMarket, SdkError, SdkResult, SpotMarket, UnsubHandle,
 };
 
@@ -78,6 +82,13 @@ pub struct MarketMap<T: AnchorDeserialize + Send> {
     subscriptions: DashMap<u16, UnsubHandle, ahash::RandomState>,
     latest_slot: Arc<AtomicU64>,
     rpc: Arc<RpcClient>,
+    /// Ws subscriptions follow the active endpoint, if set
+    failover: Option<Arc<RpcEndpoints>>,
+    /// serves subscriptions in place of Ws, if set
+    provider: Option<Arc<dyn AccountProvider>>,
+    /// serves subscriptions from a gRPC stream, if set
+    #[cfg(feature = "grpc")]
+    grpc: Option<Arc<GrpcSubscriber>>,
 }
 
 impl<T> MarketMap<T>
@@ -92,9 +103,32 @@ where
             marketmap: Arc::default(),
             latest_slot: Arc::new(AtomicU64::new(0)),
             rpc,
+            failover: None,
+            provider: None,
+            #[cfg(feature = "grpc")]
+            grpc: None,
         }
     }
 
+    /// Ws subscriptions follow the active endpoint of `endpoints`
+    pub fn with_failover(mut self, endpoints: Arc<RpcEndpoints>) -> Self {
+        self.failover = Some(endpoints);
+        self
+    }
+
+    /// Serve subscriptions from `provider` in place of Ws
+    pub fn with_provider(mut self, provider: Arc<dyn AccountProvider>) -> Self {
+        self.provider = Some(provider);
+        self
+    }
+
+    /// Serve subscriptions from the `grpc` stream in place of Ws
+    #[cfg(feature = "grpc")]
+    pub fn with_grpc(mut self, grpc: Arc<GrpcSubscriber>) -> Self {
+        self.grpc = Some(grpc);
+        self
+    }
+
     /// Subscribe to market account updates
     pub async fn subscribe(&self, markets: &[MarketId]) -> SdkResult<()> {
         log::debug!(target: LOG_TARGET, "subscribing: {:?}", T::MARKET_TYPE);
@@ -113,8 +147,11 @@ where
                 MarketType::Spot => derive_spot_market_account(market.index()),
             };
 
-            let market_subscriber =
+            let mut market_subscriber =
                 WebsocketAccountSubscriber::new(url.clone(), market_pubkey, self.rpc.commitment());
+            if let Some(ref endpoints) = self.failover {
+                market_subscriber = market_subscriber.with_failover(Arc::clone(endpoints));
+            }
 
             pending_subscriptions.push((market.index(), market_subscriber));
         }
@@ -122,24 +159,34 @@ where
         let futs_iter = pending_subscriptions.into_iter().map(|(idx, fut)| {
             let marketmap = Arc::clone(&self.marketmap);
             let latest_slot = self.latest_slot.clone();
+            let provider = self.provider.clone();
+            #[cfg(feature = "grpc")]
+            let grpc = self.grpc.clone();
             async move {
-                let unsub = fut
-                    .subscribe(Self::SUBSCRIPTION_ID, false, {
-                        move |update| {
-                            if update.slot > latest_slot.load(Ordering::Relaxed) {
-                                latest_slot.store(update.slot, Ordering::Relaxed);
-                            }
-                            marketmap.insert(
-                                idx,
-                                DataAndSlot {
-                                    slot: update.slot,
-                                    data: T::deserialize(&mut &update.data.as_slice()[8..])
-                                        .expect("valid market"),
-                                },
-                            );
-                        }
-                    })
-                    .await;
+                let on_update = move |update: &AccountUpdate| {
+                    if update.slot > latest_slot.load(Ordering::Relaxed) {
+                        latest_slot.store(update.slot, Ordering::Relaxed);
+                    }
+                    marketmap.insert(
+                        idx,
+                        DataAndSlot {
+                            slot: update.slot,
+                            data: T::deserialize(&mut &update.data.as_slice()[8..])
+                                .expect("valid market"),
+                        },
+                    );
+                };
+                if let Some(provider) = provider {
+                    let unsub = provider
+                        .watch_account(fut.pubkey, Box::new(on_update))
+                        .await;
+                    return (idx, unsub);
+                }
+                #[cfg(feature = "grpc")]
+                if let Some(grpc) = grpc {
+                    return (idx, grpc.watch_account(fut.pubkey, false, on_update).await);
+                }
+                let unsub = fut.subscribe(Self::SUBSCRIPTION_ID, false, on_update).await;
                 (idx, unsub)
             }
         });
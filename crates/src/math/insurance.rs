@@ -0,0 +1,199 @@
+//! insurance fund staking helpers
+//!
+use crate::types::accounts::{InsuranceFundStake, SpotMarket};
+
+/// A pending request to unstake from a spot market's insurance fund
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct UnstakeRequest {
+    /// insurance fund shares requested for unstaking
+    pub shares: u128,
+    /// token amount of the request, valued when the request was made (in token precision)
+    pub value: u64,
+    /// unix timestamp from which the request may be withdrawn
+    pub withdrawable_ts: i64,
+}
+
+impl UnstakeRequest {
+    /// True if the request may be withdrawn at unix timestamp `now`
+    pub fn is_withdrawable(&self, now: i64) -> bool {
+        now >= self.withdrawable_ts
+    }
+
+    /// Token amount received on withdrawal given the current insurance fund state
+    ///
+    /// Stakers receive the lesser of the value at request time and the current value of the shares
+    /// i.e. they do not benefit from revenue accrued during the unstaking period but do share any losses
+    pub fn withdraw_amount(&self, total_shares: u128, insurance_fund_vault_balance: u64) -> u64 {
+        let current_value =
+            if_shares_to_token_amount(self.shares, total_shares, insurance_fund_vault_balance);
+        current_value.min(self.value as u128) as u64
+    }
+}
+
+/// Convert insurance fund `shares` into a token amount
+///
+/// * `shares` - insurance fund shares to convert
+/// * `total_shares` - total shares of the insurance fund (`SpotMarket::insurance_fund.total_shares`)
+/// * `insurance_fund_vault_balance` - token balance of the insurance fund vault
+///
+/// Returns the token amount (in token precision)
+pub fn if_shares_to_token_amount(
+    shares: u128,
+    total_shares: u128,
+    insurance_fund_vault_balance: u64,
+) -> u128 {
+    if total_shares == 0 {
+        return 0;
+    }
+    shares * insurance_fund_vault_balance as u128 / total_shares
+}
+
+/// Get `stake`'s insurance fund shares, rebased to the spot market's current shares base
+pub fn rebased_if_shares(stake: &InsuranceFundStake, spot_market: &SpotMarket) -> u128 {
+    rebase_shares(
+        stake.if_shares.as_u128(),
+        stake.if_base.as_u128(),
+        spot_market.insurance_fund.shares_base.as_u128(),
+    )
+}
+
+/// Calculate the token amount of `stake` in the spot market's insurance fund
+///
+/// * `stake` - the authority's insurance fund stake account
+/// * `spot_market` - spot market of the insurance fund
+/// * `insurance_fund_vault_balance` - token balance of the insurance fund vault
+///
+/// Returns the token amount (in token precision)
+pub fn calculate_if_stake_token_amount(
+    stake: &InsuranceFundStake,
+    spot_market: &SpotMarket,
+    insurance_fund_vault_balance: u64,
+) -> u128 {
+    if_shares_to_token_amount(
+        rebased_if_shares(stake, spot_market),
+        spot_market.insurance_fund.total_shares.as_u128(),
+        insurance_fund_vault_balance,
+    )
+}
+
+/// Get `stake`'s pending unstake request, if any
+///
+/// * `stake` - the authority's insurance fund stake account
+/// * `spot_market` - spot market of the insurance fund, provides the unstaking period
+pub fn pending_unstake_request(
+    stake: &InsuranceFundStake,
+    spot_market: &SpotMarket,
+) -> Option<UnstakeRequest> {
+    let shares = stake.last_withdraw_request_shares.as_u128();
+    if shares == 0 {
+        return None;
+    }
+
+    Some(UnstakeRequest {
+        shares: rebase_shares(
+            shares,
+            stake.if_base.as_u128(),
+            spot_market.insurance_fund.shares_base.as_u128(),
+        ),
+        value: stake.last_withdraw_request_value,
+        withdrawable_ts: stake
+            .last_withdraw_request_ts
+            .saturating_add(spot_market.insurance_fund.unstaking_period),
+    })
+}
+
+/// Rebase `shares` issued at `if_base` to `shares_base`
+///
+/// the program rebases shares by powers of 10 after large insurance fund losses
+fn rebase_shares(shares: u128, if_base: u128, shares_base: u128) -> u128 {
+    if shares_base <= if_base {
+        return shares;
+    }
+    let expo_diff = (shares_base - if_base) as u32;
+    10_u128
+        .checked_pow(expo_diff)
+        .map(|rebase_divisor| shares / rebase_divisor)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::drift_idl::types::InsuranceFund;
+
+    fn spot_market(total_shares: u128, shares_base: u128) -> SpotMarket {
+        SpotMarket {
+            insurance_fund: InsuranceFund {
+                total_shares: total_shares.into(),
+                shares_base: shares_base.into(),
+                unstaking_period: 13 * 24 * 60 * 60,
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn stake_token_amount() {
+        let stake = InsuranceFundStake {
+            if_shares: 250_000_000_u128.into(),
+            ..Default::default()
+        };
+        // 25% of a 2,000 USDC vault
+        assert_eq!(
+            calculate_if_stake_token_amount(&stake, &spot_market(1_000_000_000, 0), 2_000_000_000),
+            500_000_000,
+        );
+        assert_eq!(
+            calculate_if_stake_token_amount(&stake, &spot_market(0, 0), 2_000_000_000),
+            0,
+        );
+    }
+
+    #[test]
+    fn stake_token_amount_rebased() {
+        let stake = InsuranceFundStake {
+            if_shares: 250_000_000_u128.into(),
+            ..Default::default()
+        };
+        // market shares were rebased by 10^2 since the stake was made
+        let market = spot_market(10_000_000, 2);
+        assert_eq!(rebased_if_shares(&stake, &market), 2_500_000);
+        assert_eq!(
+            calculate_if_stake_token_amount(&stake, &market, 2_000_000_000),
+            500_000_000,
+        );
+    }
+
+    #[test]
+    fn unstake_request() {
+        let market = spot_market(1_000_000_000, 0);
+        assert!(pending_unstake_request(&InsuranceFundStake::default(), &market).is_none());
+
+        let stake = InsuranceFundStake {
+            if_shares: 250_000_000_u128.into(),
+            last_withdraw_request_shares: 100_000_000_u128.into(),
+            last_withdraw_request_value: 200_000_000,
+            last_withdraw_request_ts: 1_000,
+            ..Default::default()
+        };
+        let request = pending_unstake_request(&stake, &market).unwrap();
+        assert_eq!(
+            request.withdrawable_ts,
+            1_000 + market.insurance_fund.unstaking_period
+        );
+        assert!(!request.is_withdrawable(request.withdrawable_ts - 1));
+        assert!(request.is_withdrawable(request.withdrawable_ts));
+
+        // vault grew, value is capped at request time
+        assert_eq!(
+            request.withdraw_amount(1_000_000_000, 3_000_000_000),
+            200_000_000
+        );
+        // vault shrank, loss is shared
+        assert_eq!(
+            request.withdraw_amount(1_000_000_000, 1_000_000_000),
+            100_000_000
+        );
+    }
+}
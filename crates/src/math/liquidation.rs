@@ -13,6 +13,7 @@ use crate::{
         constants::{
             AMM_RESERVE_PRECISION_I128, BASE_PRECISION_I128, MARGIN_PRECISION,
             QUOTE_PRECISION_I128, QUOTE_PRECISION_I64, SPOT_WEIGHT_PRECISION,
+            SPOT_WEIGHT_PRECISION_U128,
         },
     },
     types::{
@@ -359,6 +360,36 @@ fn calculate_collateral_inner(
     })
 }
 
+/// Calculate the max. amount of a spot deposit that may be withdrawn or transferred without breaching initial margin
+///
+/// * `free_collateral` - the account's initial free collateral (QUOTE_PRECISION)
+/// * `deposit_amount` - the account's deposit in the spot market (token precision)
+/// * `decimals` - decimals of the deposit token
+/// * `oracle_price` - price of the deposit token (PRICE_PRECISION)
+/// * `asset_weight` - initial asset weight of the deposit (SPOT_WEIGHT_PRECISION)
+///
+/// Returns the token amount (token precision), 0 if `oracle_price` is invalid
+pub fn calculate_max_transfer_amount(
+    free_collateral: i128,
+    deposit_amount: u128,
+    decimals: u32,
+    oracle_price: i64,
+    asset_weight: u32,
+) -> u128 {
+    if free_collateral <= 0 || deposit_amount == 0 || oracle_price <= 0 {
+        return 0;
+    }
+    // deposit does not count towards collateral
+    if asset_weight == 0 {
+        return deposit_amount;
+    }
+
+    let max_amount = (free_collateral as u128 * 10_u128.pow(decimals) * SPOT_WEIGHT_PRECISION_U128)
+        / (oracle_price as u128 * asset_weight as u128);
+
+    max_amount.min(deposit_amount)
+}
+
 #[cfg(test)]
 mod tests {
     use solana_sdk::{account::Account, pubkey::Pubkey};
@@ -368,9 +399,9 @@ mod tests {
         constants::{self, ids::pyth_program},
         drift_idl::types::{HistoricalOracleData, MarketStatus, OracleSource, SpotPosition, AMM},
         math::constants::{
-            AMM_RESERVE_PRECISION, BASE_PRECISION_I64, LIQUIDATION_FEE_PRECISION, PEG_PRECISION,
-            PRICE_PRECISION_I64, SPOT_BALANCE_PRECISION, SPOT_BALANCE_PRECISION_U64,
-            SPOT_CUMULATIVE_INTEREST_PRECISION,
+            AMM_RESERVE_PRECISION, BASE_PRECISION_I64, LAMPORTS_PER_SOL_I64,
+            LIQUIDATION_FEE_PRECISION, PEG_PRECISION, PRICE_PRECISION_I64, SPOT_BALANCE_PRECISION,
+            SPOT_BALANCE_PRECISION_U64, SPOT_CUMULATIVE_INTEREST_PRECISION,
         },
         utils::test_utils::*,
         MarketId,
@@ -888,4 +919,65 @@ mod tests {
         // entry at $80, upnl at $100
         assert_eq!(unrealized_pnl, 20_i128 * QUOTE_PRECISION_I64 as i128);
     }
+
+    #[test]
+    fn max_transfer_amount() {
+        let one_sol = LAMPORTS_PER_SOL_I64 as u128;
+        let sol_decimals = 9;
+        let sol_price = 100 * QUOTE_PRECISION_I64;
+        let asset_weight = 8 * SPOT_WEIGHT_PRECISION / 10;
+        let free_collateral = 100 * QUOTE_PRECISION_I128;
+
+        // $100 free collateral / ($100 * 0.8) = 1.25 SOL
+        assert_eq!(
+            calculate_max_transfer_amount(
+                free_collateral,
+                10 * one_sol,
+                sol_decimals,
+                sol_price,
+                asset_weight
+            ),
+            1_250_000_000,
+        );
+        // capped by deposit
+        assert_eq!(
+            calculate_max_transfer_amount(
+                free_collateral,
+                one_sol,
+                sol_decimals,
+                sol_price,
+                asset_weight
+            ),
+            one_sol,
+        );
+        // no free collateral
+        assert_eq!(
+            calculate_max_transfer_amount(0, one_sol, sol_decimals, sol_price, asset_weight),
+            0,
+        );
+        // deposit not counted as collateral
+        assert_eq!(
+            calculate_max_transfer_amount(
+                free_collateral,
+                10 * one_sol,
+                sol_decimals,
+                sol_price,
+                0
+            ),
+            10 * one_sol,
+        );
+        // invalid oracle price
+        for price in [0, -sol_price] {
+            assert_eq!(
+                calculate_max_transfer_amount(
+                    free_collateral,
+                    10 * one_sol,
+                    sol_decimals,
+                    price,
+                    asset_weight
+                ),
+                0,
+            );
+        }
+    }
 }
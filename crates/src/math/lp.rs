@@ -0,0 +1,145 @@
+//! perp LP position helpers
+//!
+use crate::{
+    marketmap::MarketMap,
+    math::constants::PERCENTAGE_PRECISION,
+    types::{
+        accounts::{PerpMarket, User},
+        PerpPosition, SdkResult,
+    },
+};
+
+/// A user's perp LP position valued as if settled
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct LpPosition {
+    /// perp market of the LP position
+    pub market_index: u16,
+    /// LP shares held (AMM_RESERVE_PRECISION)
+    pub lp_shares: u64,
+    /// share of the AMM's total liquidity (PERCENTAGE_PRECISION)
+    pub share_of_amm: u128,
+    /// base asset amount of the position after settling (BASE_PRECISION)
+    pub settled_base_asset_amount: i64,
+    /// quote asset amount of the position after settling (QUOTE_PRECISION)
+    pub settled_quote_asset_amount: i64,
+    /// base asset amount pending settlement from LP activity (BASE_PRECISION)
+    pub unsettled_base_asset_amount: i64,
+    /// quote asset amount pending settlement from LP activity (QUOTE_PRECISION)
+    pub unsettled_quote_asset_amount: i64,
+    /// base asset amount too small to settle, carried by the position (BASE_PRECISION)
+    pub remainder_base_asset_amount: i32,
+    /// unrealized PnL of the settled position (QUOTE_PRECISION)
+    pub unrealized_pnl: i128,
+}
+
+/// Calculate the settled LP position of `position`
+///
+/// * `position` - a user's perp position
+/// * `market` - perp market of the position
+/// * `oracle_price` - price to value the position at (PRICE_PRECISION)
+///
+/// Returns `None` if the position holds no LP shares
+pub fn calculate_lp_position(
+    position: &PerpPosition,
+    market: &PerpMarket,
+    oracle_price: i64,
+) -> SdkResult<Option<LpPosition>> {
+    if position.lp_shares == 0 {
+        return Ok(None);
+    }
+
+    let settled = position.simulate_settled_lp_position(market, oracle_price)?;
+    let sqrt_k = market.amm.sqrt_k.as_u128();
+    let share_of_amm = if sqrt_k == 0 {
+        0
+    } else {
+        position.lp_shares as u128 * PERCENTAGE_PRECISION / sqrt_k
+    };
+
+    Ok(Some(LpPosition {
+        market_index: position.market_index,
+        lp_shares: position.lp_shares,
+        share_of_amm,
+        settled_base_asset_amount: settled.base_asset_amount,
+        settled_quote_asset_amount: settled.quote_asset_amount,
+        unsettled_base_asset_amount: settled.base_asset_amount - position.base_asset_amount,
+        unsettled_quote_asset_amount: settled.quote_asset_amount - position.quote_asset_amount,
+        remainder_base_asset_amount: settled.remainder_base_asset_amount,
+        unrealized_pnl: settled.get_unrealized_pnl(oracle_price)?,
+    }))
+}
+
+/// Calculate all of `user`'s settled LP positions
+///
+/// * `user` - drift user account
+/// * `perp_market_map` - synced/subscribed map of perp markets
+///
+/// Positions are valued at the market's last onchain oracle price.
+/// Positions in markets missing from `perp_market_map` are skipped.
+pub fn calculate_user_lp_positions(
+    user: &User,
+    perp_market_map: &MarketMap<PerpMarket>,
+) -> SdkResult<Vec<LpPosition>> {
+    let mut lp_positions = Vec::new();
+
+    for position in user.perp_positions.iter().filter(|p| p.lp_shares > 0) {
+        let Some(market) = perp_market_map.get(&position.market_index) else {
+            continue;
+        };
+        let oracle_price = market.data.amm.historical_oracle_data.last_oracle_price;
+        if let Some(lp_position) = calculate_lp_position(position, &market.data, oracle_price)? {
+            lp_positions.push(lp_position);
+        }
+    }
+
+    Ok(lp_positions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        drift_idl::types::AMM,
+        math::constants::{AMM_RESERVE_PRECISION, BASE_PRECISION_I64, QUOTE_PRECISION_I64},
+    };
+
+    #[test]
+    fn lp_position_none_without_shares() {
+        let position = PerpPosition {
+            base_asset_amount: BASE_PRECISION_I64,
+            ..Default::default()
+        };
+        let result = calculate_lp_position(&position, &PerpMarket::default(), 0);
+        assert!(result.unwrap().is_none());
+    }
+
+    #[test]
+    fn lp_position_settled() {
+        let lp_shares = 100 * AMM_RESERVE_PRECISION as u64;
+        let position = PerpPosition {
+            lp_shares,
+            base_asset_amount: 0,
+            quote_asset_amount: 0,
+            ..Default::default()
+        };
+        // no LP activity since the position was opened
+        let market = PerpMarket {
+            amm: AMM {
+                sqrt_k: (1_000 * AMM_RESERVE_PRECISION).into(),
+                order_step_size: 1,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let lp_position = calculate_lp_position(&position, &market, 10 * QUOTE_PRECISION_I64)
+            .unwrap()
+            .unwrap();
+        assert_eq!(lp_position.lp_shares, lp_shares);
+        // 100/1,000 shares
+        assert_eq!(lp_position.share_of_amm, PERCENTAGE_PRECISION / 10);
+        assert_eq!(lp_position.unsettled_base_asset_amount, 0);
+        assert_eq!(lp_position.unsettled_quote_asset_amount, 0);
+        assert_eq!(lp_position.unrealized_pnl, 0);
+    }
+}
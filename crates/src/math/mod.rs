@@ -6,9 +6,12 @@ use crate::drift_idl::{
 pub mod account_list_builder;
 pub mod auction;
 pub mod constants;
+pub mod insurance;
 pub mod leverage;
 pub mod liquidation;
+pub mod lp;
 pub mod order;
+pub mod pnl;
 
 #[derive(Clone, Copy, Debug)]
 pub struct MarginContext {
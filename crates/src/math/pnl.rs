@@ -0,0 +1,229 @@
+//! perp PnL settlement helpers
+//!
+use std::str::FromStr;
+
+use solana_sdk::pubkey::Pubkey;
+
+use crate::{
+    marketmap::MarketMap,
+    types::accounts::{PerpMarket, SpotMarket, User},
+    usermap::GlobalUserMap,
+};
+
+/// A user's perp position with PnL pending settlement
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct UnsettledPnl {
+    /// drift user account address
+    pub user: Pubkey,
+    /// perp market of the position
+    pub market_index: u16,
+    /// PnL pending settlement (QUOTE_PRECISION)
+    pub unsettled_pnl: i128,
+    /// true if the market's PnL pool can pay out the (positive) PnL in full
+    ///
+    /// negative PnL is paid into the pool and is always covered
+    pub pnl_pool_can_cover: bool,
+}
+
+/// Find all users with unsettled perp PnL exceeding `threshold`
+///
+/// * `usermap` - synced/subscribed map of drift users
+/// * `perp_market_map` - synced/subscribed map of perp markets
+/// * `quote_spot_market` - the quote (USDC) spot market, used to value the PnL pool
+/// * `threshold` - minimum absolute PnL to include (QUOTE_PRECISION)
+///
+/// PnL is valued at the market's last onchain oracle price.
+/// Positions in markets missing from `perp_market_map` are skipped.
+pub fn find_unsettled_pnl(
+    usermap: &GlobalUserMap,
+    perp_market_map: &MarketMap<PerpMarket>,
+    quote_spot_market: &SpotMarket,
+    threshold: u128,
+) -> Vec<UnsettledPnl> {
+    let mut unsettled = Vec::new();
+
+    for entry in usermap.usermap.iter() {
+        let Ok(user) = Pubkey::from_str(entry.key()) else {
+            continue;
+        };
+        unsettled.extend(user_unsettled_pnl(
+            &user,
+            entry.value(),
+            |market_index| perp_market_map.get(&market_index).map(|m| m.data),
+            quote_spot_market,
+            threshold,
+        ));
+    }
+
+    unsettled
+}
+
+/// Calculate the unsettled PnL of each of `user_account`'s perp positions exceeding `threshold`
+fn user_unsettled_pnl(
+    user: &Pubkey,
+    user_account: &User,
+    perp_market: impl Fn(u16) -> Option<PerpMarket>,
+    quote_spot_market: &SpotMarket,
+    threshold: u128,
+) -> Vec<UnsettledPnl> {
+    user_account
+        .perp_positions
+        .iter()
+        .filter(|p| !p.is_available())
+        .filter_map(|p| {
+            let market = perp_market(p.market_index)?;
+            let oracle_price = market.amm.historical_oracle_data.last_oracle_price;
+            let unsettled_pnl = p.get_unrealized_pnl(oracle_price).ok()?;
+            if unsettled_pnl == 0 || unsettled_pnl.unsigned_abs() < threshold {
+                return None;
+            }
+            let pnl_pool = calculate_pnl_pool_token_amount(&market, quote_spot_market);
+
+            Some(UnsettledPnl {
+                user: *user,
+                market_index: p.market_index,
+                unsettled_pnl,
+                pnl_pool_can_cover: unsettled_pnl <= 0 || unsettled_pnl as u128 <= pnl_pool,
+            })
+        })
+        .collect()
+}
+
+/// Calculate the token amount held by a perp market's PnL pool
+///
+/// Returns the amount in quote token precision (i.e. QUOTE_PRECISION for USDC)
+pub fn calculate_pnl_pool_token_amount(
+    market: &PerpMarket,
+    quote_spot_market: &SpotMarket,
+) -> u128 {
+    let precision_decrease = 10_u128.pow(19 - quote_spot_market.decimals);
+    (market.pnl_pool.scaled_balance.as_u128()
+        * quote_spot_market.cumulative_deposit_interest.as_u128())
+        / precision_decrease
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        drift_idl::types::{HistoricalOracleData, PerpPosition, PoolBalance, AMM},
+        math::constants::{
+            BASE_PRECISION_I64, PRICE_PRECISION_I64, QUOTE_PRECISION, QUOTE_PRECISION_I64,
+            SPOT_BALANCE_PRECISION, SPOT_CUMULATIVE_INTEREST_PRECISION,
+        },
+    };
+
+    fn usdc_spot_market() -> SpotMarket {
+        SpotMarket {
+            market_index: 0,
+            decimals: 6,
+            cumulative_deposit_interest: (SPOT_CUMULATIVE_INTEREST_PRECISION * 11 / 10).into(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn pnl_pool_token_amount() {
+        let market = PerpMarket {
+            pnl_pool: PoolBalance {
+                scaled_balance: (1_000 * SPOT_BALANCE_PRECISION).into(),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        // 1,000 scaled balance @ 1.1 cumulative interest
+        assert_eq!(
+            calculate_pnl_pool_token_amount(&market, &usdc_spot_market()),
+            1_100 * QUOTE_PRECISION,
+        );
+    }
+
+    /// perp market at $110 with `pnl_pool` scaled balance (SPOT_BALANCE_PRECISION)
+    fn perp_market(market_index: u16, pnl_pool: u128) -> PerpMarket {
+        PerpMarket {
+            market_index,
+            amm: AMM {
+                historical_oracle_data: HistoricalOracleData {
+                    last_oracle_price: 110 * PRICE_PRECISION_I64,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            pnl_pool: PoolBalance {
+                scaled_balance: (pnl_pool * SPOT_BALANCE_PRECISION).into(),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    /// 1 unit position entered at $100
+    fn position(market_index: u16, long: bool) -> PerpPosition {
+        let sign = if long { 1 } else { -1 };
+        PerpPosition {
+            market_index,
+            base_asset_amount: sign * BASE_PRECISION_I64,
+            quote_asset_amount: -sign * 100 * QUOTE_PRECISION_I64,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn unsettled_pnl() {
+        let user = Pubkey::new_unique();
+        let mut user_account = User::default();
+        // +$10, pool has $5.5
+        user_account.perp_positions[0] = position(0, true);
+        // +$10, pool has $1,100
+        user_account.perp_positions[1] = position(1, true);
+        // -$10
+        user_account.perp_positions[2] = position(2, false);
+        // market unknown
+        user_account.perp_positions[3] = position(3, true);
+
+        let markets = [perp_market(0, 5), perp_market(1, 1_000), perp_market(2, 0)];
+        let perp_market = |market_index: u16| markets.get(market_index as usize).copied();
+
+        let unsettled = user_unsettled_pnl(
+            &user,
+            &user_account,
+            perp_market,
+            &usdc_spot_market(),
+            QUOTE_PRECISION,
+        );
+        assert_eq!(
+            unsettled,
+            vec![
+                UnsettledPnl {
+                    user,
+                    market_index: 0,
+                    unsettled_pnl: 10 * QUOTE_PRECISION as i128,
+                    pnl_pool_can_cover: false,
+                },
+                UnsettledPnl {
+                    user,
+                    market_index: 1,
+                    unsettled_pnl: 10 * QUOTE_PRECISION as i128,
+                    pnl_pool_can_cover: true,
+                },
+                UnsettledPnl {
+                    user,
+                    market_index: 2,
+                    unsettled_pnl: -10 * QUOTE_PRECISION as i128,
+                    pnl_pool_can_cover: true,
+                },
+            ]
+        );
+
+        // below threshold
+        assert!(user_unsettled_pnl(
+            &user,
+            &user_account,
+            perp_market,
+            &usdc_spot_market(),
+            20 * QUOTE_PRECISION,
+        )
+        .is_empty());
+    }
+}
@@ -1,5 +1,6 @@
 use anchor_lang::Discriminator;
 use solana_client::rpc_filter::{Memcmp, RpcFilterType};
+use solana_sdk::pubkey::Pubkey;
 
 use crate::types::{
     accounts::{PerpMarket, SpotMarket, User},
@@ -10,6 +11,16 @@ pub fn get_user_filter() -> RpcFilterType {
     RpcFilterType::Memcmp(Memcmp::new_raw_bytes(0, User::discriminator().into()))
 }
 
+/// filter `User` accounts by `authority`
+pub fn get_user_authority_filter(authority: &Pubkey) -> RpcFilterType {
+    RpcFilterType::Memcmp(Memcmp::new_raw_bytes(8, authority.to_bytes().to_vec()))
+}
+
+/// filter `User` accounts by `delegate`
+pub fn get_user_delegate_filter(delegate: &Pubkey) -> RpcFilterType {
+    RpcFilterType::Memcmp(Memcmp::new_raw_bytes(40, delegate.to_bytes().to_vec()))
+}
+
 pub fn get_non_idle_user_filter() -> RpcFilterType {
     RpcFilterType::Memcmp(Memcmp::new_raw_bytes(4_350, vec![0]))
 }
@@ -0,0 +1,620 @@
+//! In-memory accounts and RPC transport for offline testing
+//!
+//! `MockAccounts` is an in-memory account store served over a mock RPC transport. As an `AccountProvider`,
+//! account, market and oracle subscriptions receive updates as soon as accounts are set. Requires the `mock` feature.
+//!
+//! ```ignore
+//! let accounts = Arc::new(MockAccounts::default());
+//! accounts.set_perp_market(&perp_market);
+//! accounts.set_spot_market(&spot_market);
+//! accounts.set_user(user_pubkey, &user);
+//!
+//! let client = DriftClient::new_mock(Context::DevNet, Arc::clone(&accounts), wallet).await?;
+//! client.subscribe_account(&user_pubkey).await?;
+//!
+//! // push an update, subscribers see it immediately
+//! accounts.set_user(user_pubkey, &updated_user);
+//! assert_eq!(client.try_get_account::<User>(&user_pubkey)?, updated_user);
+//! ```
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use anchor_lang::AccountSerialize;
+use async_trait::async_trait;
+use base64::Engine;
+use dashmap::DashMap;
+use serde_json::{json, Value};
+use solana_account_decoder::{UiAccount, UiAccountData, UiAccountEncoding};
+use solana_client::{
+    client_error::ClientError,
+    nonblocking::rpc_client::RpcClient,
+    rpc_client::RpcClientConfig,
+    rpc_filter::RpcFilterType,
+    rpc_request::{RpcError, RpcRequest},
+    rpc_sender::{RpcSender, RpcTransportStats},
+};
+use solana_sdk::{
+    account::Account, bs58, clock::Slot, commitment_config::CommitmentConfig, hash::Hash,
+    pubkey::Pubkey, rent::Rent, transaction::VersionedTransaction,
+};
+use tokio::sync::oneshot::{self, error::TryRecvError};
+
+use crate::{
+    account_provider::{AccountProvider, OnAccountUpdate},
+    constants::{derive_perp_market_account, derive_spot_market_account, state_account},
+    types::{
+        accounts::{PerpMarket, SpotMarket, State, User},
+        SdkError, SdkResult,
+    },
+    websocket_account_subscriber::AccountUpdate,
+    UnsubHandle, PROGRAM_ID,
+};
+
+/// URL reported by the mock RPC transport
+pub const MOCK_URL: &str = "http://mock.rpc";
+
+/// An account update listener
+struct Watcher {
+    /// closed once the listener unsubscribes
+    unsub: oneshot::Receiver<()>,
+    on_update: Box<dyn Fn(&AccountUpdate) + Send>,
+}
+
+/// In-memory account store
+///
+/// Each account update advances the store's slot by 1
+pub struct MockAccounts {
+    accounts: DashMap<Pubkey, (Account, Slot)>,
+    slot: AtomicU64,
+    blockhash: Hash,
+    watchers: Mutex<HashMap<Pubkey, Vec<Watcher>>>,
+    /// txs received by the transport and the slot they 'landed'
+    sent_txs: Mutex<Vec<(VersionedTransaction, Slot)>>,
+    /// fixed responses by RPC method, takes priority over the store
+    responses: DashMap<String, Value>,
+}
+
+impl Default for MockAccounts {
+    fn default() -> Self {
+        Self {
+            accounts: Default::default(),
+            slot: AtomicU64::new(1),
+            blockhash: Hash::new_unique(),
+            watchers: Default::default(),
+            sent_txs: Default::default(),
+            responses: Default::default(),
+        }
+    }
+}
+
+impl MockAccounts {
+    /// Return an `RpcClient` served by this store
+    pub fn rpc_client(self: &Arc<Self>) -> RpcClient {
+        RpcClient::new_sender(
+            MockRpcSender {
+                accounts: Arc::clone(self),
+            },
+            RpcClientConfig::with_commitment(CommitmentConfig::confirmed()),
+        )
+    }
+
+    /// Return the current slot
+    pub fn slot(&self) -> Slot {
+        self.slot.load(Ordering::Relaxed)
+    }
+
+    /// Return `pubkey`'s account, if it exists
+    pub fn get_account(&self, pubkey: &Pubkey) -> Option<Account> {
+        self.accounts.get(pubkey).map(|a| a.0.clone())
+    }
+
+    /// Set `pubkey`'s account, notifying any subscribers
+    ///
+    /// Returns the slot of the update
+    pub fn set_account(&self, pubkey: Pubkey, account: Account) -> Slot {
+        let slot = self.slot.fetch_add(1, Ordering::Relaxed) + 1;
+        self.update_account(pubkey, account, slot);
+        slot
+    }
+
+    /// Set `pubkey`'s account as of `slot`, notifying any subscribers
+    ///
+    /// The store's slot is advanced to `slot` if it is behind
+    pub fn set_account_at(&self, pubkey: Pubkey, account: Account, slot: Slot) {
+        self.slot.fetch_max(slot, Ordering::Relaxed);
+        self.update_account(pubkey, account, slot);
+    }
+
+    fn update_account(&self, pubkey: Pubkey, account: Account, slot: Slot) {
+        let update = AccountUpdate {
+            pubkey,
+            owner: account.owner,
+            lamports: account.lamports,
+            data: account.data.clone(),
+            slot,
+        };
+        self.accounts.insert(pubkey, (account, slot));
+
+        let mut watchers = self.watchers.lock().expect("acquired");
+        if let Some(watchers) = watchers.get_mut(&pubkey) {
+            watchers.retain_mut(|w| {
+                let subscribed = matches!(w.unsub.try_recv(), Err(TryRecvError::Empty));
+                if subscribed {
+                    (w.on_update)(&update);
+                }
+                subscribed
+            });
+        }
+    }
+
+    /// Set `pubkey` to a drift program account with data `value`
+    pub fn set_program_account<T: AccountSerialize>(&self, pubkey: Pubkey, value: &T) -> Slot {
+        let mut data = Vec::new();
+        value.try_serialize(&mut data).expect("serializes");
+        self.set_account(
+            pubkey,
+            Account {
+                lamports: Rent::default().minimum_balance(data.len()),
+                data,
+                owner: PROGRAM_ID,
+                executable: false,
+                rent_epoch: 0,
+            },
+        )
+    }
+
+    /// Set the perp market account of `market`
+    pub fn set_perp_market(&self, market: &PerpMarket) -> Slot {
+        self.set_program_account(derive_perp_market_account(market.market_index), market)
+    }
+
+    /// Set the spot market account of `market`
+    pub fn set_spot_market(&self, market: &SpotMarket) -> Slot {
+        self.set_program_account(derive_spot_market_account(market.market_index), market)
+    }
+
+    /// Set the drift state account
+    pub fn set_state(&self, state: &State) -> Slot {
+        self.set_program_account(*state_account(), state)
+    }
+
+    /// Set the drift user account at `pubkey`
+    pub fn set_user(&self, pubkey: Pubkey, user: &User) -> Slot {
+        self.set_program_account(pubkey, user)
+    }
+
+    /// Set the oracle account at `pubkey`
+    ///
+    /// * `owner` - oracle program e.g. pyth
+    /// * `data` - raw oracle account data
+    pub fn set_oracle(&self, pubkey: Pubkey, owner: Pubkey, data: Vec<u8>) -> Slot {
+        self.set_account(
+            pubkey,
+            Account {
+                lamports: Rent::default().minimum_balance(data.len()),
+                data,
+                owner,
+                executable: false,
+                rent_epoch: 0,
+            },
+        )
+    }
+
+    /// Remove `pubkey`'s account
+    pub fn remove_account(&self, pubkey: &Pubkey) {
+        self.accounts.remove(pubkey);
+    }
+
+    /// Respond to RPC `method` with `value`, overriding the store e.g. for `simulateTransaction`
+    pub fn set_response(&self, method: &str, value: Value) {
+        self.responses.insert(method.to_string(), value);
+    }
+
+    /// Return all txs sent via the transport
+    pub fn sent_transactions(&self) -> Vec<VersionedTransaction> {
+        self.sent_txs
+            .lock()
+            .expect("acquired")
+            .iter()
+            .map(|(tx, _)| tx.clone())
+            .collect()
+    }
+
+    /// Call `on_update` with the current value of `pubkey` and subsequent updates
+    ///
+    /// Returns error if the account does not exist
+    fn watch(
+        &self,
+        pubkey: Pubkey,
+        on_update: impl Fn(&AccountUpdate) + Send + 'static,
+    ) -> SdkResult<UnsubHandle> {
+        let (account, slot) = self
+            .accounts
+            .get(&pubkey)
+            .map(|a| a.value().clone())
+            .ok_or(SdkError::InvalidAccount)?;
+        on_update(&AccountUpdate {
+            pubkey,
+            owner: account.owner,
+            lamports: account.lamports,
+            data: account.data,
+            slot,
+        });
+
+        let (unsub_tx, unsub_rx) = oneshot::channel();
+        self.watchers
+            .lock()
+            .expect("acquired")
+            .entry(pubkey)
+            .or_default()
+            .push(Watcher {
+                unsub: unsub_rx,
+                on_update: Box::new(on_update),
+            });
+
+        Ok(unsub_tx)
+    }
+
+    /// Handle an RPC request
+    fn handle(&self, request: RpcRequest, params: Value) -> Result<Value, ClientError> {
+        let method = request.to_string();
+        if let Some(response) = self.responses.get(&method) {
+            return Ok(response.clone());
+        }
+
+        let slot = self.slot();
+        let context = json!({ "slot": slot });
+        let response = match method.as_str() {
+            "getAccountInfo" => {
+                let pubkey = parse_pubkey(&params[0])?;
+                json!({
+                    "context": context,
+                    "value": self.get_account(&pubkey).map(|a| ui_account(&a)),
+                })
+            }
+            "getMultipleAccounts" => {
+                let accounts = params[0]
+                    .as_array()
+                    .ok_or_else(|| invalid_params(&method))?
+                    .iter()
+                    .map(|p| parse_pubkey(p).map(|p| self.get_account(&p).map(|a| ui_account(&a))))
+                    .collect::<Result<Vec<_>, _>>()?;
+                json!({ "context": context, "value": accounts })
+            }
+            "getProgramAccounts" => {
+                let program_id = parse_pubkey(&params[0])?;
+                let filters: Vec<RpcFilterType> =
+                    serde_json::from_value(params[1]["filters"].clone()).unwrap_or_default();
+                let accounts: Vec<Value> = self
+                    .accounts
+                    .iter()
+                    .filter(|a| {
+                        let (account, _) = a.value();
+                        account.owner == program_id
+                            && filters.iter().all(|f| filter_allows(f, &account.data))
+                    })
+                    .map(|a| json!({ "pubkey": a.key().to_string(), "account": ui_account(&a.value().0) }))
+                    .collect();
+                if params[1]["withContext"].as_bool() == Some(true) {
+                    json!({ "context": context, "value": accounts })
+                } else {
+                    json!(accounts)
+                }
+            }
+            "getBalance" => {
+                let pubkey = parse_pubkey(&params[0])?;
+                json!({
+                    "context": context,
+                    "value": self.get_account(&pubkey).map(|a| a.lamports).unwrap_or_default(),
+                })
+            }
+            "getLatestBlockhash" => json!({
+                "context": context,
+                "value": {
+                    "blockhash": self.blockhash.to_string(),
+                    "lastValidBlockHeight": slot + 150,
+                },
+            }),
+            "getSlot" | "getBlockHeight" => json!(slot),
+            "getVersion" => json!({ "solana-core": "2.0.20", "feature-set": 0 }),
+            "getMinimumBalanceForRentExemption" => {
+                let len = params[0].as_u64().ok_or_else(|| invalid_params(&method))?;
+                json!(Rent::default().minimum_balance(len as usize))
+            }
+            "getRecentPrioritizationFees" => json!([]),
+            "simulateTransaction" => json!({
+                "context": context,
+                "value": { "err": null, "logs": [], "unitsConsumed": 0 },
+            }),
+            "sendTransaction" => {
+                let encoded = params[0].as_str().ok_or_else(|| invalid_params(&method))?;
+                let tx_bytes = if params[1]["encoding"].as_str() == Some("base64") {
+                    base64::engine::general_purpose::STANDARD
+                        .decode(encoded)
+                        .ok()
+                } else {
+                    bs58::decode(encoded).into_vec().ok()
+                };
+                let tx: VersionedTransaction = tx_bytes
+                    .and_then(|b| bincode::deserialize(&b).ok())
+                    .ok_or_else(|| invalid_params(&method))?;
+                let signature = tx.signatures.first().copied().unwrap_or_default();
+                self.sent_txs.lock().expect("acquired").push((tx, slot));
+                json!(signature.to_string())
+            }
+            "getSignatureStatuses" => {
+                let sent_txs = self.sent_txs.lock().expect("acquired");
+                let statuses: Vec<Value> = params[0]
+                    .as_array()
+                    .ok_or_else(|| invalid_params(&method))?
+                    .iter()
+                    .map(|s| {
+                        let landed_slot = sent_txs.iter().find_map(|(tx, slot)| {
+                            (tx.signatures.first().map(|s| s.to_string()).as_deref() == s.as_str())
+                                .then_some(*slot)
+                        });
+                        match landed_slot {
+                            Some(slot) => json!({
+                                "slot": slot,
+                                "confirmations": null,
+                                "err": null,
+                                "status": { "Ok": null },
+                                "confirmationStatus": "finalized",
+                            }),
+                            None => Value::Null,
+                        }
+                    })
+                    .collect();
+                json!({ "context": context, "value": statuses })
+            }
+            _ => {
+                return Err(
+                    RpcError::RpcRequestError(format!("mock: unsupported method {method}")).into(),
+                )
+            }
+        };
+
+        Ok(response)
+    }
+}
+
+#[async_trait]
+impl AccountProvider for MockAccounts {
+    async fn watch_account(
+        &self,
+        pubkey: Pubkey,
+        on_update: OnAccountUpdate,
+    ) -> SdkResult<UnsubHandle> {
+        self.watch(pubkey, on_update)
+    }
+}
+
+/// RPC transport serving requests from `MockAccounts`
+pub struct MockRpcSender {
+    accounts: Arc<MockAccounts>,
+}
+
+#[async_trait]
+impl RpcSender for MockRpcSender {
+    async fn send(&self, request: RpcRequest, params: Value) -> Result<Value, ClientError> {
+        self.accounts.handle(request, params)
+    }
+
+    fn get_transport_stats(&self) -> RpcTransportStats {
+        RpcTransportStats::default()
+    }
+
+    fn url(&self) -> String {
+        MOCK_URL.into()
+    }
+}
+
+pub(crate) fn ui_account(account: &Account) -> UiAccount {
+    UiAccount {
+        lamports: account.lamports,
+        data: UiAccountData::Binary(
+            base64::engine::general_purpose::STANDARD.encode(&account.data),
+            UiAccountEncoding::Base64,
+        ),
+        owner: account.owner.to_string(),
+        executable: account.executable,
+        rent_epoch: account.rent_epoch,
+        space: Some(account.data.len() as u64),
+    }
+}
+
+fn filter_allows(filter: &RpcFilterType, data: &[u8]) -> bool {
+    match filter {
+        RpcFilterType::DataSize(size) => data.len() as u64 == *size,
+        RpcFilterType::Memcmp(memcmp) => memcmp.bytes_match(data),
+        RpcFilterType::TokenAccountState => false,
+    }
+}
+
+fn parse_pubkey(value: &Value) -> Result<Pubkey, ClientError> {
+    value
+        .as_str()
+        .and_then(|s| s.parse().ok())
+        .ok_or_else(|| RpcError::RpcRequestError(format!("mock: invalid pubkey {value}")).into())
+}
+
+fn invalid_params(method: &str) -> ClientError {
+    RpcError::RpcRequestError(format!("mock: invalid params for {method}")).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use anchor_lang::AccountDeserialize;
+    use solana_sdk::{message::Message, signature::Keypair, signer::Signer};
+
+    use super::*;
+    use crate::{
+        drift_idl::{accounts::PrelaunchOracle, types::OracleSource},
+        memcmp,
+        types::{Context, MarketId},
+        DriftClient, Wallet,
+    };
+
+    #[tokio::test]
+    async fn rpc_reads_accounts() {
+        let accounts = Arc::new(MockAccounts::default());
+        let user_pubkey = Pubkey::new_unique();
+        let user = User {
+            authority: Pubkey::new_unique(),
+            sub_account_id: 1,
+            ..Default::default()
+        };
+        let slot = accounts.set_user(user_pubkey, &user);
+        accounts.set_perp_market(&PerpMarket::default());
+
+        let rpc_client = accounts.rpc_client();
+        let response = rpc_client
+            .get_account_with_commitment(&user_pubkey, CommitmentConfig::confirmed())
+            .await
+            .unwrap();
+        assert_eq!(response.context.slot, slot + 1);
+        let account = response.value.unwrap();
+        assert_eq!(
+            User::try_deserialize(&mut account.data.as_slice()).unwrap(),
+            user
+        );
+        assert!(rpc_client
+            .get_account_with_commitment(&Pubkey::new_unique(), CommitmentConfig::confirmed())
+            .await
+            .unwrap()
+            .value
+            .is_none());
+
+        let users = rpc_client
+            .get_program_accounts_with_config(
+                &PROGRAM_ID,
+                solana_client::rpc_config::RpcProgramAccountsConfig {
+                    filters: Some(vec![
+                        memcmp::get_user_filter(),
+                        memcmp::get_user_authority_filter(&user.authority),
+                    ]),
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(users.len(), 1);
+        assert_eq!(users[0].0, user_pubkey);
+    }
+
+    #[tokio::test]
+    async fn client_offline() {
+        let accounts = Arc::new(MockAccounts::default());
+        accounts.set_perp_market(&PerpMarket {
+            market_index: 0,
+            ..Default::default()
+        });
+        accounts.set_spot_market(&SpotMarket {
+            market_index: 0,
+            ..Default::default()
+        });
+        let keypair = Keypair::new();
+        let user_pubkey = Wallet::derive_user_account(&keypair.pubkey(), 0);
+        let mut user = User {
+            authority: keypair.pubkey(),
+            ..Default::default()
+        };
+        accounts.set_user(user_pubkey, &user);
+
+        let client = DriftClient::new_mock(Context::DevNet, Arc::clone(&accounts), keypair.into())
+            .await
+            .unwrap();
+        assert_eq!(client.get_all_market_ids().len(), 2);
+        assert_eq!(
+            client
+                .get_perp_market_account(0)
+                .await
+                .unwrap()
+                .market_index,
+            0
+        );
+        assert!(client.state_account().is_ok());
+
+        // pushed updates are seen by subscribers
+        client.subscribe_account(&user_pubkey).await.unwrap();
+        user.sub_account_id = 5;
+        accounts.set_user(user_pubkey, &user);
+        assert_eq!(client.try_get_account::<User>(&user_pubkey).unwrap(), user);
+
+        // txs are recorded
+        let tx = solana_sdk::message::VersionedMessage::Legacy(Message::new(
+            &[],
+            Some(client.wallet().authority()),
+        ));
+        let signature = client.sign_and_send(tx).await.unwrap();
+        let sent = accounts.sent_transactions();
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].signatures[0], signature);
+    }
+
+    #[tokio::test]
+    async fn market_and_oracle_updates() {
+        let accounts = Arc::new(MockAccounts::default());
+        let oracle = Pubkey::new_unique();
+        let set_prelaunch_price = |price: i64| {
+            let mut data = Vec::new();
+            PrelaunchOracle {
+                price,
+                max_price: price * 2,
+                perp_market_index: 0,
+                ..Default::default()
+            }
+            .try_serialize(&mut data)
+            .unwrap();
+            accounts.set_oracle(oracle, PROGRAM_ID, data)
+        };
+        set_prelaunch_price(1_000_000);
+        let mut perp_market = PerpMarket {
+            market_index: 0,
+            ..Default::default()
+        };
+        perp_market.amm.oracle = oracle;
+        perp_market.amm.oracle_source = OracleSource::Prelaunch;
+        accounts.set_perp_market(&perp_market);
+        accounts.set_spot_market(&SpotMarket {
+            market_index: 0,
+            oracle_source: OracleSource::QuoteAsset,
+            ..Default::default()
+        });
+
+        let client = DriftClient::new_mock(
+            Context::DevNet,
+            Arc::clone(&accounts),
+            Keypair::new().into(),
+        )
+        .await
+        .unwrap();
+        let market = MarketId::perp(0);
+        client.subscribe_markets(&[market]).await.unwrap();
+        client.subscribe_oracles(&[market]).await.unwrap();
+        assert_eq!(client.oracle_price(market).await.unwrap(), 1_000_000);
+
+        // pushed updates are seen by market and oracle subscribers
+        perp_market.amm.base_asset_reserve = 12_345;
+        accounts.set_perp_market(&perp_market);
+        assert_eq!(
+            client
+                .try_get_perp_market_account(0)
+                .unwrap()
+                .amm
+                .base_asset_reserve,
+            12_345
+        );
+
+        let slot = set_prelaunch_price(2_000_000);
+        let oracle_data = client.try_get_oracle_price_data_and_slot(market).unwrap();
+        assert_eq!(oracle_data.data.price, 2_000_000);
+        assert_eq!(oracle_data.slot, slot);
+        assert_eq!(client.oracle_price(market).await.unwrap(), 2_000_000);
+    }
+}
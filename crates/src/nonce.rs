@@ -0,0 +1,126 @@
+//! Durable nonce txs
+//!
+//! Txs using a durable nonce in place of a recent blockhash do not expire, useful for signing offline.
+//! The nonce account's authority must be the tx authority.
+//!
+//! ```ignore
+//! // once, create a nonce account owned by the wallet
+//! let (nonce_account, _) = client.create_nonce_account("drift-nonce", None).await?;
+//! // online, fetch the current nonce
+//! let nonce = client.get_nonce_account(&nonce_account).await?;
+//! let tx = client
+//!     .init_tx(&sub_account, false)
+//!     .await?
+//!     .with_nonce(nonce_account)
+//!     .place_orders(orders)
+//!     .build();
+//! // offline, sign using the nonce as the blockhash
+//! let signed_tx = wallet.sign_tx(tx, nonce.blockhash()).await?;
+//! ```
+use solana_sdk::{
+    account::Account,
+    instruction::Instruction,
+    nonce::{
+        state::{Data, Versions},
+        State,
+    },
+    pubkey::Pubkey,
+    system_instruction, system_program,
+};
+
+use crate::types::{SdkError, SdkResult};
+
+/// Decoded durable nonce account data
+pub type NonceData = Data;
+
+/// Return the address of the nonce account derived from `authority` and `seed`
+///
+/// Returns error if `seed` is longer than 32 bytes
+pub fn derive_nonce_account(authority: &Pubkey, seed: &str) -> SdkResult<Pubkey> {
+    Pubkey::create_with_seed(authority, seed, &system_program::ID)
+        .map_err(|_| SdkError::InvalidSeed)
+}
+
+/// Build ixs creating and funding a nonce account derived from `authority` and `seed`
+///
+/// `authority` pays for the new account and is set as its nonce authority
+///
+/// * `lamports` - initial balance, should be at least rent exempt (`State::size()` bytes)
+///
+/// Returns the nonce account address and ixs or error if `seed` is invalid
+pub fn create_nonce_account_ixs(
+    authority: &Pubkey,
+    seed: &str,
+    lamports: u64,
+) -> SdkResult<(Pubkey, Vec<Instruction>)> {
+    let nonce_account = derive_nonce_account(authority, seed)?;
+    let ixs = system_instruction::create_nonce_account_with_seed(
+        authority,
+        &nonce_account,
+        authority,
+        seed,
+        authority,
+        lamports,
+    );
+
+    Ok((nonce_account, ixs))
+}
+
+/// Decode the data of an initialized nonce `account`
+pub fn decode_nonce_account(account: &Account) -> SdkResult<NonceData> {
+    if account.owner != system_program::ID {
+        return Err(SdkError::InvalidAccount);
+    }
+    let versions: Versions =
+        bincode::deserialize(&account.data).map_err(|_| SdkError::Deserializing)?;
+    match versions.state() {
+        State::Initialized(data) => Ok(data.clone()),
+        State::Uninitialized => Err(SdkError::InvalidAccount),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use solana_sdk::{hash::Hash, nonce::state::DurableNonce};
+
+    use super::*;
+
+    #[test]
+    fn create_and_decode_nonce_account() {
+        let authority = Pubkey::new_unique();
+        let (nonce_account, ixs) =
+            create_nonce_account_ixs(&authority, "nonce", 1_000_000).unwrap();
+        assert_eq!(
+            nonce_account,
+            derive_nonce_account(&authority, "nonce").unwrap()
+        );
+        assert!(create_nonce_account_ixs(&authority, &"x".repeat(33), 1_000_000).is_err());
+        // create + initialize
+        assert_eq!(ixs.len(), 2);
+        assert!(ixs.iter().all(|ix| ix.program_id == system_program::ID));
+
+        let durable_nonce = DurableNonce::from_blockhash(&Hash::new_unique());
+        let state = State::Initialized(Data::new(authority, durable_nonce, 5_000));
+        let account = Account {
+            lamports: 1_000_000,
+            data: bincode::serialize(&Versions::new(state)).unwrap(),
+            owner: system_program::ID,
+            ..Default::default()
+        };
+        let data = decode_nonce_account(&account).unwrap();
+        assert_eq!(data.authority, authority);
+        assert_eq!(data.blockhash(), *durable_nonce.as_hash());
+
+        let uninitialized = Account {
+            data: bincode::serialize(&Versions::new(State::Uninitialized)).unwrap(),
+            ..account.clone()
+        };
+        assert!(decode_nonce_account(&uninitialized).is_err());
+
+        let wrong_owner = Account {
+            owner: Pubkey::new_unique(),
+            ..account
+        };
+        assert!(decode_nonce_account(&wrong_owner).is_err());
+    }
+}
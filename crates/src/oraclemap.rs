@@ -10,8 +10,12 @@ use log::warn;
 use solana_client::nonblocking::rpc_client::RpcClient;
 use solana_sdk::{account::Account, clock::Slot, pubkey::Pubkey};
 
+#[cfg(feature = "grpc")]
+use crate::grpc::GrpcSubscriber;
 use crate::{
+    account_provider::AccountProvider,
     drift_idl::types::OracleSource,
+    failover::RpcEndpoints,
     ffi::{get_oracle_price, OraclePriceData},
     utils::get_ws_url,
     websocket_account_subscriber::{AccountUpdate, WebsocketAccountSubscriber},
@@ -43,6 +47,13 @@ pub struct OracleMap {
     oracle_by_market: ReadOnlyView<MarketId, Pubkey>,
     latest_slot: Arc<AtomicU64>,
     rpc: Arc<RpcClient>,
+    /// Ws subscriptions follow the active endpoint, if set
+    failover: Option<Arc<RpcEndpoints>>,
+    /// serves subscriptions in place of Ws, if set
+    provider: Option<Arc<dyn AccountProvider>>,
+    /// serves subscriptions from a gRPC stream, if set
+    #[cfg(feature = "grpc")]
+    grpc: Option<Arc<GrpcSubscriber>>,
 }
 
 impl OracleMap {
@@ -83,9 +94,32 @@ impl OracleMap {
             subcriptions: Default::default(),
             latest_slot: Arc::new(AtomicU64::new(0)),
             rpc: rpc_client,
+            failover: None,
+            provider: None,
+            #[cfg(feature = "grpc")]
+            grpc: None,
         }
     }
 
+    /// Ws subscriptions follow the active endpoint of `endpoints`
+    pub fn with_failover(mut self, endpoints: Arc<RpcEndpoints>) -> Self {
+        self.failover = Some(endpoints);
+        self
+    }
+
+    /// Serve subscriptions from `provider` in place of Ws
+    pub fn with_provider(mut self, provider: Arc<dyn AccountProvider>) -> Self {
+        self.provider = Some(provider);
+        self
+    }
+
+    /// Serve subscriptions from the `grpc` stream in place of Ws
+    #[cfg(feature = "grpc")]
+    pub fn with_grpc(mut self, grpc: Arc<GrpcSubscriber>) -> Self {
+        self.grpc = Some(grpc);
+        self
+    }
+
     /// Subscribe to oracle updates for given `markets`
     ///
     /// Can be called multiple times to subscribe to additional markets
@@ -115,21 +149,40 @@ impl OracleMap {
                 continue;
             }
 
-            let oracle_subscriber =
+            let mut oracle_subscriber =
                 WebsocketAccountSubscriber::new(url.clone(), *oracle_pubkey, self.rpc.commitment());
+            if let Some(ref endpoints) = self.failover {
+                oracle_subscriber = oracle_subscriber.with_failover(Arc::clone(endpoints));
+            }
 
             pending_subscriptions.push((oracle_subscriber, oracle_info.clone()));
         }
 
         let futs_iter = pending_subscriptions.into_iter().map(|(sub_fut, info)| {
             let oraclemap = Arc::clone(&self.oraclemap);
+            let provider = self.provider.clone();
+            #[cfg(feature = "grpc")]
+            let grpc = self.grpc.clone();
             async move {
+                // TODO:
+                // receive a list of all markets that share the oracle to update the data simultaneously
+                let on_update = {
+                    let (market, source) = (info.market, info.source);
+                    move |update: &AccountUpdate| update_handler(update, market, source, &oraclemap)
+                };
+                if let Some(provider) = provider {
+                    let unsub = provider
+                        .watch_account(info.pubkey, Box::new(on_update))
+                        .await;
+                    return (info, unsub);
+                }
+                #[cfg(feature = "grpc")]
+                if let Some(grpc) = grpc {
+                    let unsub = grpc.watch_account(info.pubkey, true, on_update).await;
+                    return (info, unsub);
+                }
                 let unsub = sub_fut
-                    .subscribe(Self::SUBSCRIPTION_ID, true, {
-                        // TODO:
-                        // receive a list of all markets that share the oracle to update the data simultaneously
-                        move |update| update_handler(update, info.market, info.source, &oraclemap)
-                    })
+                    .subscribe(Self::SUBSCRIPTION_ID, true, on_update)
                     .await;
                 (info, unsub)
             }
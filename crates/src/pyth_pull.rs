@@ -0,0 +1,338 @@
+//! Pyth pull oracle price updates
+//!
+//! Markets with `OracleSource::PythPull` (and variants) read prices posted by keepers.
+//! Price updates (e.g. from Hermes) can be posted atomically with other drift ixs, see `TransactionBuilder::post_pyth_pull_update`.
+//!
+//! ```ignore
+//! let update_data = source.price_update(&[feed_id]).await?;
+//! let update = AccumulatorUpdate::try_from_bytes(&update_data)?;
+//! let tx = client
+//!     .init_tx(&sub_account, false)
+//!     .await?
+//!     .post_pyth_pull_update(&update)
+//!     .place_orders(orders)
+//!     .build();
+//! ```
+use anchor_lang::AnchorSerialize;
+use async_trait::async_trait;
+use solana_sdk::pubkey::Pubkey;
+
+use crate::{
+    constants::{ids, PROGRAM_ID},
+    types::{SdkError, SdkResult},
+};
+
+/// Pyth price feed Id
+pub type FeedId = [u8; 32];
+
+/// Magic bytes of an accumulator update
+const ACCUMULATOR_MAGIC: &[u8; 4] = b"PNAU";
+/// Supported major version of accumulator updates
+const ACCUMULATOR_MAJOR_VERSION: u8 = 1;
+/// Accumulator update type for wormhole merkle proofs
+const WORMHOLE_MERKLE_UPDATE_TYPE: u8 = 0;
+/// Length of a merkle proof node
+const MERKLE_NODE_LEN: usize = 20;
+/// Length of a VAA guardian signature
+const VAA_SIGNATURE_LEN: usize = 66;
+/// Number of guardian signatures retained in a VAA (tx size constraints)
+pub const DEFAULT_REDUCED_GUARDIAN_SET_SIZE: u8 = 5;
+
+/// Provides raw Pyth price updates e.g. from a Hermes endpoint
+#[async_trait]
+pub trait PriceUpdateSource: Send + Sync {
+    /// Fetch the latest accumulator update data for `feed_ids`
+    ///
+    /// Returns the raw update bytes (i.e. 'binary' data from Hermes)
+    async fn price_update(&self, feed_ids: &[FeedId]) -> SdkResult<Vec<u8>>;
+}
+
+/// A price update message with proof of inclusion in the VAA's merkle root
+#[derive(Clone, Debug, PartialEq, AnchorSerialize)]
+pub struct MerklePriceUpdate {
+    pub message: Vec<u8>,
+    pub proof: Vec<[u8; MERKLE_NODE_LEN]>,
+}
+
+impl MerklePriceUpdate {
+    /// Return the feed Id of the price update message
+    pub fn feed_id(&self) -> Option<FeedId> {
+        // message type (u8) | feed_id ([u8; 32]) | ...
+        self.message.get(1..33)?.try_into().ok()
+    }
+}
+
+/// Params for drift's `PostPythPullOracleUpdateAtomic` ix
+#[derive(AnchorSerialize)]
+struct PostUpdateAtomicParams<'a> {
+    vaa: &'a [u8],
+    merkle_price_update: &'a MerklePriceUpdate,
+    treasury_id: u8,
+}
+
+/// Params for drift's `PostMultiPythPullOracleUpdatesAtomic` ix
+#[derive(AnchorSerialize)]
+struct PostMultiUpdatesAtomicParams<'a> {
+    vaa: &'a [u8],
+    merkle_price_updates: &'a [MerklePriceUpdate],
+}
+
+/// A Pyth accumulator update i.e. a VAA with merkle price updates for one or more feeds
+#[derive(Clone, Debug, PartialEq)]
+pub struct AccumulatorUpdate {
+    /// VAA attesting the merkle root of `updates` (signatures trimmed)
+    vaa: Vec<u8>,
+    /// wormhole guardian set index that signed the VAA
+    guardian_set_index: u32,
+    /// price updates of individual feeds, by feed Id
+    updates: Vec<(FeedId, MerklePriceUpdate)>,
+}
+
+impl AccumulatorUpdate {
+    /// Parse an accumulator update from raw bytes
+    ///
+    /// The VAA's guardian signatures are trimmed to `DEFAULT_REDUCED_GUARDIAN_SET_SIZE`
+    ///
+    /// Returns error if the data is malformed or contains no price updates
+    pub fn try_from_bytes(data: &[u8]) -> SdkResult<Self> {
+        let mut reader = Reader(data);
+        if reader.take(4)? != ACCUMULATOR_MAGIC {
+            return Err(invalid_update("bad magic"));
+        }
+        if reader.u8()? != ACCUMULATOR_MAJOR_VERSION {
+            return Err(invalid_update("unsupported version"));
+        }
+        let _minor_version = reader.u8()?;
+        let trailing_header_len = reader.u8()? as usize;
+        reader.take(trailing_header_len)?;
+        if reader.u8()? != WORMHOLE_MERKLE_UPDATE_TYPE {
+            return Err(invalid_update("unsupported update type"));
+        }
+
+        let vaa_len = reader.u16()? as usize;
+        let vaa = trim_vaa_signatures(reader.take(vaa_len)?, DEFAULT_REDUCED_GUARDIAN_SET_SIZE)?;
+        // version (u8) | guardian_set_index (u32) | ...
+        let guardian_set_index = u32::from_be_bytes(vaa[1..5].try_into().expect("4 bytes"));
+
+        let num_updates = reader.u8()?;
+        if num_updates == 0 {
+            return Err(invalid_update("no price updates"));
+        }
+        let mut updates = Vec::with_capacity(num_updates as usize);
+        for _ in 0..num_updates {
+            let message_len = reader.u16()? as usize;
+            let message = reader.take(message_len)?.to_vec();
+            let num_proofs = reader.u8()?;
+            let mut proof = Vec::with_capacity(num_proofs as usize);
+            for _ in 0..num_proofs {
+                proof.push(reader.take(MERKLE_NODE_LEN)?.try_into().expect("node len"));
+            }
+            let update = MerklePriceUpdate { message, proof };
+            let feed_id = update
+                .feed_id()
+                .ok_or_else(|| invalid_update("short message"))?;
+            updates.push((feed_id, update));
+        }
+
+        Ok(Self {
+            vaa,
+            guardian_set_index,
+            updates,
+        })
+    }
+
+    /// Return the VAA attesting the price updates (signatures trimmed)
+    pub fn vaa(&self) -> &[u8] {
+        &self.vaa
+    }
+
+    /// Return the wormhole guardian set index that signed the VAA
+    pub fn guardian_set_index(&self) -> u32 {
+        self.guardian_set_index
+    }
+
+    /// Return the price updates and their feed Ids
+    pub fn updates(&self) -> &[(FeedId, MerklePriceUpdate)] {
+        &self.updates
+    }
+
+    /// Serialize ix params to post a single price update (`PostPythPullOracleUpdateAtomic`)
+    pub(crate) fn encode_atomic_params(&self, update: &MerklePriceUpdate) -> Vec<u8> {
+        let mut data = Vec::new();
+        PostUpdateAtomicParams {
+            vaa: &self.vaa,
+            merkle_price_update: update,
+            treasury_id: 0,
+        }
+        .serialize(&mut data)
+        .expect("serializes");
+        data
+    }
+
+    /// Serialize ix params to post all price updates (`PostMultiPythPullOracleUpdatesAtomic`)
+    pub(crate) fn encode_multi_atomic_params(&self) -> Vec<u8> {
+        let mut data = Vec::new();
+        let merkle_price_updates: Vec<MerklePriceUpdate> =
+            self.updates.iter().map(|(_, u)| u.clone()).collect();
+        PostMultiUpdatesAtomicParams {
+            vaa: &self.vaa,
+            merkle_price_updates: &merkle_price_updates,
+        }
+        .serialize(&mut data)
+        .expect("serializes");
+        data
+    }
+}
+
+/// calculate the PDA of the drift price feed account for a Pyth `feed_id`
+pub fn derive_pyth_pull_oracle(feed_id: &FeedId) -> Pubkey {
+    let (account, _seed) =
+        Pubkey::find_program_address(&[&b"pyth_pull"[..], feed_id.as_ref()], &PROGRAM_ID);
+    account
+}
+
+/// calculate the PDA of a wormhole guardian set given index
+pub fn derive_guardian_set(guardian_set_index: u32) -> Pubkey {
+    let (account, _seed) = Pubkey::find_program_address(
+        &[&b"GuardianSet"[..], &guardian_set_index.to_be_bytes()],
+        &ids::wormhole_program::ID,
+    );
+    account
+}
+
+/// Trim the guardian signatures of `vaa` to at most `n`
+fn trim_vaa_signatures(vaa: &[u8], n: u8) -> SdkResult<Vec<u8>> {
+    // version (u8) | guardian_set_index (u32) | num_signatures (u8) | signatures | body
+    let num_signatures = *vaa.get(5).ok_or_else(|| invalid_update("short vaa"))?;
+    let body_offset = 6 + num_signatures as usize * VAA_SIGNATURE_LEN;
+    if vaa.len() < body_offset {
+        return Err(invalid_update("short vaa"));
+    }
+    if num_signatures <= n {
+        return Ok(vaa.to_vec());
+    }
+
+    let mut trimmed =
+        Vec::with_capacity(vaa.len() - (num_signatures - n) as usize * VAA_SIGNATURE_LEN);
+    trimmed.extend_from_slice(&vaa[..5]);
+    trimmed.push(n);
+    trimmed.extend_from_slice(&vaa[6..6 + n as usize * VAA_SIGNATURE_LEN]);
+    trimmed.extend_from_slice(&vaa[body_offset..]);
+
+    Ok(trimmed)
+}
+
+fn invalid_update(reason: &str) -> SdkError {
+    SdkError::Generic(format!("invalid pyth price update: {reason}"))
+}
+
+/// Big-endian byte reader
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> SdkResult<&'a [u8]> {
+        if self.0.len() < n {
+            return Err(invalid_update("unexpected end of data"));
+        }
+        let (head, tail) = self.0.split_at(n);
+        self.0 = tail;
+        Ok(head)
+    }
+    fn u8(&mut self) -> SdkResult<u8> {
+        Ok(self.take(1)?[0])
+    }
+    fn u16(&mut self) -> SdkResult<u16> {
+        Ok(u16::from_be_bytes(
+            self.take(2)?.try_into().expect("2 bytes"),
+        ))
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// Build a VAA with `num_signatures` and some body
+    fn vaa(num_signatures: u8) -> Vec<u8> {
+        let mut vaa = vec![1_u8];
+        vaa.extend_from_slice(&4_u32.to_be_bytes());
+        vaa.push(num_signatures);
+        for i in 0..num_signatures {
+            vaa.extend_from_slice(&[i; VAA_SIGNATURE_LEN]);
+        }
+        vaa.extend_from_slice(b"vaa body");
+        vaa
+    }
+
+    /// Build a price update message for `feed_id`
+    fn message(feed_id: FeedId) -> Vec<u8> {
+        let mut message = vec![0_u8];
+        message.extend_from_slice(&feed_id);
+        message.extend_from_slice(&[7; 52]);
+        message
+    }
+
+    /// Build raw accumulator update data for `feed_ids`
+    pub(crate) fn accumulator_update_data(feed_ids: &[FeedId]) -> Vec<u8> {
+        let vaa = vaa(13);
+        let mut data = ACCUMULATOR_MAGIC.to_vec();
+        data.extend_from_slice(&[ACCUMULATOR_MAJOR_VERSION, 0, 0, WORMHOLE_MERKLE_UPDATE_TYPE]);
+        data.extend_from_slice(&(vaa.len() as u16).to_be_bytes());
+        data.extend_from_slice(&vaa);
+        data.push(feed_ids.len() as u8);
+        for feed_id in feed_ids {
+            let message = message(*feed_id);
+            data.extend_from_slice(&(message.len() as u16).to_be_bytes());
+            data.extend_from_slice(&message);
+            data.push(2);
+            data.extend_from_slice(&[1; MERKLE_NODE_LEN]);
+            data.extend_from_slice(&[2; MERKLE_NODE_LEN]);
+        }
+        data
+    }
+
+    #[test]
+    fn parse_accumulator_update() {
+        let feed_ids = [[1_u8; 32], [2_u8; 32]];
+        let update =
+            AccumulatorUpdate::try_from_bytes(&accumulator_update_data(&feed_ids)).unwrap();
+
+        assert_eq!(update.vaa(), vaa(DEFAULT_REDUCED_GUARDIAN_SET_SIZE));
+        assert_eq!(update.guardian_set_index(), 4);
+        assert_eq!(update.updates().len(), 2);
+        assert_eq!(update.updates()[0].0, feed_ids[0]);
+        assert_eq!(update.updates()[1].0, feed_ids[1]);
+        assert_eq!(update.updates()[1].1.feed_id(), Some(feed_ids[1]));
+        assert_eq!(update.updates()[1].1.proof.len(), 2);
+
+        let mut truncated = accumulator_update_data(&feed_ids);
+        truncated.pop();
+        assert!(AccumulatorUpdate::try_from_bytes(&truncated).is_err());
+        assert!(AccumulatorUpdate::try_from_bytes(b"PNAX").is_err());
+    }
+
+    #[test]
+    fn parse_rejects_invalid_updates() {
+        // no price updates
+        assert!(AccumulatorUpdate::try_from_bytes(&accumulator_update_data(&[])).is_err());
+
+        // message too short to contain a feed Id
+        let vaa = vaa(1);
+        let mut data = ACCUMULATOR_MAGIC.to_vec();
+        data.extend_from_slice(&[ACCUMULATOR_MAJOR_VERSION, 0, 0, WORMHOLE_MERKLE_UPDATE_TYPE]);
+        data.extend_from_slice(&(vaa.len() as u16).to_be_bytes());
+        data.extend_from_slice(&vaa);
+        data.push(1);
+        data.extend_from_slice(&3_u16.to_be_bytes());
+        data.extend_from_slice(&[0, 1, 2]);
+        data.push(0);
+        assert!(AccumulatorUpdate::try_from_bytes(&data).is_err());
+    }
+
+    #[test]
+    fn trim_signatures() {
+        assert_eq!(trim_vaa_signatures(&vaa(3), 5).unwrap(), vaa(3));
+        assert_eq!(trim_vaa_signatures(&vaa(13), 5).unwrap(), vaa(5));
+        assert!(trim_vaa_signatures(&vaa(13)[..20], 5).is_err());
+    }
+}
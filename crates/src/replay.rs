@@ -0,0 +1,581 @@
+//! Record and replay RPC sessions
+//!
+//! Capture the JSON-RPC requests and account, market and oracle subscription updates of a live session
+//! into a fixture file, then replay them offline e.g. in CI. Requires the `mock` feature.
+//!
+//! ```ignore
+//! // once, against mainnet
+//! let recorder = Arc::new(Recorder::default());
+//! let client =
+//!     DriftClient::new_recording(Context::MainNet, &mainnet_endpoint(), wallet, Arc::clone(&recorder))
+//!         .await?;
+//! client.subscribe_account(&user).await?;
+//! client.subscribe_markets(&[MarketId::perp(0)]).await?;
+//! client.subscribe_oracles(&[MarketId::perp(0)]).await?;
+//! let _ = client.get_perp_market_account(1).await?;
+//! recorder.save("res/session.json")?;
+//!
+//! // offline
+//! let replay = Replay::new(Fixture::load("res/session.json")?)?;
+//! let client = DriftClient::new_replay(Context::MainNet, &replay, wallet).await?;
+//! client.subscribe_account(&user).await?; // first recorded value
+//! replay.advance(); // push the next recorded account update
+//! ```
+use std::{
+    fs,
+    path::Path,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use solana_account_decoder::UiAccount;
+use solana_client::{
+    client_error::{ClientError, ClientErrorKind},
+    http_sender::HttpSender,
+    nonblocking::rpc_client::RpcClient,
+    rpc_client::RpcClientConfig,
+    rpc_request::{RpcError, RpcRequest, RpcResponseErrorData},
+    rpc_response::RpcSimulateTransactionResult,
+    rpc_sender::{RpcSender, RpcTransportStats},
+};
+use solana_sdk::{
+    account::Account, clock::Slot, commitment_config::CommitmentConfig, pubkey::Pubkey,
+};
+
+use crate::{
+    account_provider::{AccountProvider, OnAccountUpdate},
+    mock::{ui_account, MockAccounts, MOCK_URL},
+    types::{SdkError, SdkResult},
+    utils::get_ws_url,
+    websocket_account_subscriber::{AccountUpdate, WebsocketAccountSubscriber},
+    UnsubHandle,
+};
+
+/// A recorded JSON-RPC request and its response
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RpcExchange {
+    pub method: String,
+    pub params: Value,
+    /// response result, if the request succeeded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result: Option<Value>,
+    /// error, if the request failed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<RecordedError>,
+}
+
+/// A recorded JSON-RPC request error
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum RecordedError {
+    /// JSON-RPC error response
+    Response {
+        code: i64,
+        message: String,
+        data: RecordedErrorData,
+    },
+    /// transport or other request error
+    Request(String),
+}
+
+/// Data of a recorded JSON-RPC error response
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum RecordedErrorData {
+    Empty,
+    SendTransactionPreflightFailure(RpcSimulateTransactionResult),
+    NodeUnhealthy { num_slots_behind: Option<Slot> },
+}
+
+impl RecordedError {
+    fn new(err: &ClientError) -> Self {
+        match err.kind() {
+            ClientErrorKind::RpcError(RpcError::RpcResponseError {
+                code,
+                message,
+                data,
+            }) => Self::Response {
+                code: *code,
+                message: message.clone(),
+                data: match data {
+                    RpcResponseErrorData::Empty => RecordedErrorData::Empty,
+                    RpcResponseErrorData::SendTransactionPreflightFailure(result) => {
+                        RecordedErrorData::SendTransactionPreflightFailure(result.clone())
+                    }
+                    RpcResponseErrorData::NodeUnhealthy { num_slots_behind } => {
+                        RecordedErrorData::NodeUnhealthy {
+                            num_slots_behind: *num_slots_behind,
+                        }
+                    }
+                },
+            },
+            _ => Self::Request(err.to_string()),
+        }
+    }
+
+    /// Return the error as originally received
+    fn to_rpc_error(&self) -> RpcError {
+        match self {
+            Self::Response {
+                code,
+                message,
+                data,
+            } => RpcError::RpcResponseError {
+                code: *code,
+                message: message.clone(),
+                data: match data {
+                    RecordedErrorData::Empty => RpcResponseErrorData::Empty,
+                    RecordedErrorData::SendTransactionPreflightFailure(result) => {
+                        RpcResponseErrorData::SendTransactionPreflightFailure(result.clone())
+                    }
+                    RecordedErrorData::NodeUnhealthy { num_slots_behind } => {
+                        RpcResponseErrorData::NodeUnhealthy {
+                            num_slots_behind: *num_slots_behind,
+                        }
+                    }
+                },
+            },
+            Self::Request(message) => RpcError::RpcRequestError(message.clone()),
+        }
+    }
+}
+
+/// A recorded account subscription update
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RecordedUpdate {
+    pub pubkey: String,
+    pub slot: Slot,
+    pub account: UiAccount,
+}
+
+impl RecordedUpdate {
+    fn new(update: &AccountUpdate) -> Self {
+        Self {
+            pubkey: update.pubkey.to_string(),
+            slot: update.slot,
+            account: ui_account(&Account {
+                lamports: update.lamports,
+                data: update.data.clone(),
+                owner: update.owner,
+                executable: false,
+                rent_epoch: 0,
+            }),
+        }
+    }
+
+    /// Return the updated account pubkey and value
+    fn decode(&self) -> SdkResult<(Pubkey, Account)> {
+        let pubkey = self.pubkey.parse().map_err(|_| SdkError::InvalidBase58)?;
+        let account = self
+            .account
+            .decode()
+            .ok_or(SdkError::UnsupportedAccountData)?;
+        Ok((pubkey, account))
+    }
+}
+
+/// Recorded RPC session
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Fixture {
+    /// JSON-RPC requests in the order they were made
+    pub rpc: Vec<RpcExchange>,
+    /// account subscription updates in the order they were received
+    pub account_updates: Vec<RecordedUpdate>,
+}
+
+impl Fixture {
+    /// Load a fixture from a JSON file at `path`
+    pub fn load(path: impl AsRef<Path>) -> SdkResult<Self> {
+        let raw = fs::read(path).map_err(|err| SdkError::Generic(err.to_string()))?;
+        serde_json::from_slice(&raw).map_err(|err| SdkError::Generic(err.to_string()))
+    }
+
+    /// Save the fixture as a JSON file at `path`
+    pub fn save(&self, path: impl AsRef<Path>) -> SdkResult<()> {
+        let raw =
+            serde_json::to_vec_pretty(self).map_err(|err| SdkError::Generic(err.to_string()))?;
+        fs::write(path, raw).map_err(|err| SdkError::Generic(err.to_string()))
+    }
+}
+
+/// Records a live RPC session
+#[derive(Default)]
+pub struct Recorder {
+    fixture: Mutex<Fixture>,
+}
+
+impl Recorder {
+    /// Return an `RpcClient` to `endpoint` recording all requests
+    pub fn rpc_client(self: &Arc<Self>, endpoint: &str, commitment: CommitmentConfig) -> RpcClient {
+        RpcClient::new_sender(
+            RecordingSender {
+                inner: HttpSender::new(endpoint.to_string()),
+                recorder: Arc::clone(self),
+            },
+            RpcClientConfig::with_commitment(commitment),
+        )
+    }
+
+    /// Return an `AccountProvider` subscribing to `endpoint` via Ws and recording all updates
+    ///
+    /// Serve `AccountMap`, `MarketMap` and `OracleMap` subscriptions from it to record their updates
+    pub fn account_provider(
+        self: &Arc<Self>,
+        endpoint: &str,
+        commitment: CommitmentConfig,
+    ) -> SdkResult<Arc<dyn AccountProvider>> {
+        Ok(Arc::new(RecordingProvider {
+            ws_url: get_ws_url(endpoint)?,
+            commitment,
+            recorder: Arc::clone(self),
+        }))
+    }
+
+    /// Return the session recorded so far
+    pub fn fixture(&self) -> Fixture {
+        self.fixture.lock().expect("acquired").clone()
+    }
+
+    /// Save the session recorded so far as a JSON file at `path`
+    pub fn save(&self, path: impl AsRef<Path>) -> SdkResult<()> {
+        self.fixture().save(path)
+    }
+
+    /// Record an account subscription update
+    fn record_update(&self, update: &AccountUpdate) {
+        self.fixture
+            .lock()
+            .expect("acquired")
+            .account_updates
+            .push(RecordedUpdate::new(update));
+    }
+
+    fn record_exchange(&self, exchange: RpcExchange) {
+        self.fixture.lock().expect("acquired").rpc.push(exchange);
+    }
+}
+
+/// RPC transport recording requests made to some endpoint
+struct RecordingSender {
+    inner: HttpSender,
+    recorder: Arc<Recorder>,
+}
+
+#[async_trait]
+impl RpcSender for RecordingSender {
+    async fn send(&self, request: RpcRequest, params: Value) -> Result<Value, ClientError> {
+        let response = self.inner.send(request, params.clone()).await;
+        let (result, error) = match response {
+            Ok(ref result) => (Some(result.clone()), None),
+            Err(ref err) => (None, Some(RecordedError::new(err))),
+        };
+        self.recorder.record_exchange(RpcExchange {
+            method: request.to_string(),
+            params,
+            result,
+            error,
+        });
+
+        response
+    }
+
+    fn get_transport_stats(&self) -> RpcTransportStats {
+        self.inner.get_transport_stats()
+    }
+
+    fn url(&self) -> String {
+        self.inner.url()
+    }
+}
+
+/// Ws account subscriptions recording all updates
+struct RecordingProvider {
+    ws_url: String,
+    commitment: CommitmentConfig,
+    recorder: Arc<Recorder>,
+}
+
+impl RecordingProvider {
+    const SUBSCRIPTION_ID: &'static str = "recorder";
+}
+
+#[async_trait]
+impl AccountProvider for RecordingProvider {
+    async fn watch_account(
+        &self,
+        pubkey: Pubkey,
+        on_update: OnAccountUpdate,
+    ) -> SdkResult<UnsubHandle> {
+        let recorder = Arc::clone(&self.recorder);
+        // the initial value is recorded as the first update, replays start from it
+        WebsocketAccountSubscriber::new(self.ws_url.clone(), pubkey, self.commitment)
+            .subscribe(Self::SUBSCRIPTION_ID, true, move |update| {
+                recorder.record_update(update);
+                on_update(update);
+            })
+            .await
+    }
+}
+
+/// Replays a recorded RPC session
+///
+/// Account subscriptions start from the first recorded value of each account,
+/// later updates are pushed to subscribers in recorded order with `advance`
+pub struct Replay {
+    exchanges: Arc<ReplayExchanges>,
+    accounts: Arc<MockAccounts>,
+    /// updates yet to be replayed
+    updates: Vec<(Pubkey, Account, Slot)>,
+    next_update: AtomicUsize,
+}
+
+impl Replay {
+    /// Create a new `Replay` of `fixture`
+    ///
+    /// Returns error if the fixture contains invalid account updates
+    pub fn new(fixture: Fixture) -> SdkResult<Self> {
+        let accounts = Arc::new(MockAccounts::default());
+        let mut updates = Vec::with_capacity(fixture.account_updates.len());
+        for update in &fixture.account_updates {
+            let (pubkey, account) = update.decode()?;
+            if accounts.get_account(&pubkey).is_none() {
+                accounts.set_account_at(pubkey, account, update.slot);
+            } else {
+                updates.push((pubkey, account, update.slot));
+            }
+        }
+
+        Ok(Self {
+            exchanges: Arc::new(ReplayExchanges {
+                consumed: Mutex::new(vec![false; fixture.rpc.len()]),
+                exchanges: fixture.rpc,
+            }),
+            accounts,
+            updates,
+            next_update: AtomicUsize::new(0),
+        })
+    }
+
+    /// Return an `RpcClient` serving the recorded responses
+    pub fn rpc_client(&self) -> RpcClient {
+        RpcClient::new_sender(
+            ReplaySender(Arc::clone(&self.exchanges)),
+            RpcClientConfig::with_commitment(CommitmentConfig::confirmed()),
+        )
+    }
+
+    /// Return the accounts serving account subscriptions
+    pub fn accounts(&self) -> Arc<MockAccounts> {
+        Arc::clone(&self.accounts)
+    }
+
+    /// Push the next recorded account update to subscribers
+    ///
+    /// Returns the update's slot or `None` once all updates are replayed
+    pub fn advance(&self) -> Option<Slot> {
+        let idx = self.next_update.fetch_add(1, Ordering::Relaxed);
+        let (pubkey, account, slot) = self.updates.get(idx)?.clone();
+        self.accounts.set_account_at(pubkey, account, slot);
+        Some(slot)
+    }
+
+    /// Return the number of account updates yet to be replayed
+    pub fn remaining(&self) -> usize {
+        self.updates
+            .len()
+            .saturating_sub(self.next_update.load(Ordering::Relaxed))
+    }
+}
+
+/// Methods whose params legitimately differ between sessions e.g. the signed tx
+const UNMATCHED_PARAMS_METHODS: &[&str] = &[
+    "sendTransaction",
+    "simulateTransaction",
+    "getLatestBlockhash",
+];
+
+struct ReplayExchanges {
+    exchanges: Vec<RpcExchange>,
+    consumed: Mutex<Vec<bool>>,
+}
+
+impl ReplayExchanges {
+    /// Find the recorded response to `method` with `params`
+    ///
+    /// Prefers the next unreplayed exchange with matching params. Methods in `UNMATCHED_PARAMS_METHODS`
+    /// fall back to the next exchange with matching method only.
+    /// Once all matches are replayed the last is repeated.
+    fn find(&self, method: &str, params: &Value) -> Option<&RpcExchange> {
+        let match_params = |e: &RpcExchange| e.method == method && e.params == *params;
+        let match_method =
+            |e: &RpcExchange| e.method == method && UNMATCHED_PARAMS_METHODS.contains(&method);
+
+        let mut consumed = self.consumed.lock().expect("acquired");
+        let unconsumed = |f: &dyn Fn(&RpcExchange) -> bool| {
+            self.exchanges
+                .iter()
+                .enumerate()
+                .position(|(idx, e)| !consumed[idx] && f(e))
+        };
+        let idx = unconsumed(&match_params).or_else(|| unconsumed(&match_method));
+        match idx {
+            Some(idx) => {
+                consumed[idx] = true;
+                Some(&self.exchanges[idx])
+            }
+            None => self
+                .exchanges
+                .iter()
+                .rev()
+                .find(|e| match_params(*e))
+                .or_else(|| self.exchanges.iter().rev().find(|e| match_method(*e))),
+        }
+    }
+}
+
+/// RPC transport serving recorded responses
+struct ReplaySender(Arc<ReplayExchanges>);
+
+#[async_trait]
+impl RpcSender for ReplaySender {
+    async fn send(&self, request: RpcRequest, params: Value) -> Result<Value, ClientError> {
+        let method = request.to_string();
+        let exchange = self.0.find(&method, &params).ok_or_else(|| {
+            RpcError::RpcRequestError(format!("replay: no recorded response for {method}"))
+        })?;
+        match exchange.error {
+            Some(ref err) => Err(err.to_rpc_error().into()),
+            None => Ok(exchange.result.clone().unwrap_or_default()),
+        }
+    }
+
+    fn get_transport_stats(&self) -> RpcTransportStats {
+        RpcTransportStats::default()
+    }
+
+    fn url(&self) -> String {
+        MOCK_URL.into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn update(pubkey: Pubkey, data: Vec<u8>, slot: Slot) -> AccountUpdate {
+        AccountUpdate {
+            pubkey,
+            owner: Pubkey::new_unique(),
+            lamports: 1_000,
+            data,
+            slot,
+        }
+    }
+
+    #[tokio::test]
+    async fn replay_recorded_session() {
+        let pubkey = Pubkey::new_unique();
+        let recorder = Recorder::default();
+        recorder.record_exchange(RpcExchange {
+            method: "getSlot".into(),
+            params: json!([{ "commitment": "confirmed" }]),
+            result: Some(json!(100)),
+            error: None,
+        });
+        recorder.record_exchange(RpcExchange {
+            method: "getSlot".into(),
+            params: json!([{ "commitment": "confirmed" }]),
+            result: Some(json!(101)),
+            error: None,
+        });
+        recorder.record_exchange(RpcExchange {
+            method: "getBalance".into(),
+            params: json!([pubkey.to_string()]),
+            result: None,
+            error: Some(RecordedError::Response {
+                code: -32005,
+                message: "node unhealthy".into(),
+                data: RecordedErrorData::NodeUnhealthy {
+                    num_slots_behind: Some(10),
+                },
+            }),
+        });
+        recorder.record_exchange(RpcExchange {
+            method: "getAccountInfo".into(),
+            params: json!([pubkey.to_string()]),
+            result: Some(json!(null)),
+            error: None,
+        });
+        recorder.record_exchange(RpcExchange {
+            method: "getLatestBlockhash".into(),
+            params: json!([{ "commitment": "finalized" }]),
+            result: Some(json!(7)),
+            error: None,
+        });
+        recorder.record_update(&update(pubkey, vec![1, 2, 3], 100));
+        recorder.record_update(&update(pubkey, vec![4, 5, 6], 102));
+
+        // round trip via file
+        let path = std::env::temp_dir().join(format!("drift-fixture-{pubkey}.json"));
+        recorder.save(&path).unwrap();
+        let fixture = Fixture::load(&path).unwrap();
+        let _ = fs::remove_file(&path);
+        assert_eq!(fixture, recorder.fixture());
+
+        let replay = Replay::new(fixture).unwrap();
+        let rpc_client = replay.rpc_client();
+        assert_eq!(rpc_client.get_slot().await.unwrap(), 100);
+        assert_eq!(rpc_client.get_slot().await.unwrap(), 101);
+        // last response repeats
+        assert_eq!(rpc_client.get_slot().await.unwrap(), 101);
+        assert!(rpc_client.get_block_height().await.is_err());
+
+        // recorded errors are replayed as received
+        let err = rpc_client
+            .send::<u64>(RpcRequest::GetBalance, json!([pubkey.to_string()]))
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err.kind(),
+            ClientErrorKind::RpcError(RpcError::RpcResponseError {
+                code: -32005,
+                data: RpcResponseErrorData::NodeUnhealthy {
+                    num_slots_behind: Some(10)
+                },
+                ..
+            })
+        ));
+
+        // reads of unrecorded accounts are not served another account's response
+        let other = Pubkey::new_unique();
+        assert!(rpc_client
+            .send::<Value>(RpcRequest::GetAccountInfo, json!([other.to_string()]))
+            .await
+            .is_err());
+        // unless params legitimately differ between sessions
+        assert_eq!(
+            rpc_client
+                .send::<u64>(
+                    RpcRequest::GetLatestBlockhash,
+                    json!([{ "commitment": "confirmed" }])
+                )
+                .await
+                .unwrap(),
+            7
+        );
+
+        let accounts = replay.accounts();
+        assert_eq!(accounts.get_account(&pubkey).unwrap().data, vec![1, 2, 3]);
+        assert_eq!(replay.remaining(), 1);
+        assert_eq!(replay.advance(), Some(102));
+        assert_eq!(accounts.get_account(&pubkey).unwrap().data, vec![4, 5, 6]);
+        assert_eq!(accounts.slot(), 102);
+        assert_eq!(replay.advance(), None);
+        assert_eq!(replay.remaining(), 0);
+    }
+}
@@ -0,0 +1,211 @@
+//! RFQ (request for quote) orders
+//!
+//! Makers sign RFQ order params off-chain, takers match them onchain with `TransactionBuilder::place_and_match_rfq_orders`.
+//! Makers must initialize an RFQ user account beforehand, see `TransactionBuilder::initialize_rfq_user`.
+//!
+//! ```ignore
+//! // maker
+//! let order_params = rfq::maker_order_params(&wallet, 0, MarketId::perp(0), -1 * BASE_PRECISION_I64, price, max_ts, uuid);
+//! let message = rfq::sign_maker_order(&wallet, order_params).await?;
+//! // taker
+//! let tx = client
+//!     .init_tx(&taker_sub_account, false)
+//!     .await?
+//!     .place_and_match_rfq_orders(&[message.to_match(base_asset_amount)])
+//!     .build();
+//! ```
+use anchor_lang::AnchorSerialize;
+use solana_sdk::{instruction::Instruction, pubkey::Pubkey, signer::SignerError};
+
+use crate::{
+    constants::PROGRAM_ID,
+    swift::new_ed25519_verify_ix,
+    types::{
+        MarketId, PositionDirection, RFQMakerMessage, RFQMakerOrderParams, RFQMatch, SdkError,
+        SdkResult,
+    },
+    Wallet,
+};
+
+/// Build maker order params for an RFQ quote
+///
+/// * `wallet` - the maker's wallet, must be the maker account's authority
+/// * `sub_account_id` - the maker's sub-account
+/// * `market` - market of the quote
+/// * `amount` - size of the quote, a sub-zero amount indicates a short (BASE_PRECISION)
+/// * `price` - quote price (PRICE_PRECISION)
+/// * `max_ts` - unix timestamp after which the quote expires
+/// * `uuid` - unique id of the quote
+pub fn maker_order_params(
+    wallet: &Wallet,
+    sub_account_id: u16,
+    market: MarketId,
+    amount: i64,
+    price: u64,
+    max_ts: i64,
+    uuid: [u8; 8],
+) -> RFQMakerOrderParams {
+    RFQMakerOrderParams {
+        uuid,
+        authority: *wallet.authority(),
+        sub_account_id,
+        market_index: market.index(),
+        market_type: market.kind(),
+        base_asset_amount: amount.unsigned_abs(),
+        price,
+        direction: if amount >= 0 {
+            PositionDirection::Long
+        } else {
+            PositionDirection::Short
+        },
+        max_ts,
+    }
+}
+
+/// Serialize RFQ maker `order_params` for signing
+pub fn encode_maker_order_params(order_params: &RFQMakerOrderParams) -> Vec<u8> {
+    let mut data = Vec::new();
+    order_params.serialize(&mut data).expect("serializes");
+    data
+}
+
+/// Sign RFQ maker `order_params` with `wallet`
+///
+/// Returns the signed maker message for sending to takers.
+/// Returns error if `wallet` does not sign as `order_params.authority` e.g. a delegated wallet,
+/// the program only accepts quotes signed by the maker's authority
+pub async fn sign_maker_order(
+    wallet: &Wallet,
+    order_params: RFQMakerOrderParams,
+) -> SdkResult<RFQMakerMessage> {
+    if wallet.signer() != order_params.authority {
+        return Err(SdkError::Signing(SignerError::KeypairPubkeyMismatch));
+    }
+    let signature = wallet
+        .sign_message(&encode_maker_order_params(&order_params))
+        .await?;
+
+    Ok(RFQMakerMessage {
+        order_params,
+        signature: crate::types::Signature(signature.into()),
+    })
+}
+
+/// Return true if `message` is validly signed by the maker's authority
+pub fn verify_maker_message(message: &RFQMakerMessage) -> bool {
+    solana_sdk::signature::Signature::from(message.signature.0).verify(
+        message.order_params.authority.as_ref(),
+        &encode_maker_order_params(&message.order_params),
+    )
+}
+
+/// calculate the PDA of a drift user's RFQ user account
+pub fn derive_rfq_user(user: &Pubkey) -> Pubkey {
+    let (account, _seed) = Pubkey::find_program_address(&[&b"RFQ"[..], user.as_ref()], &PROGRAM_ID);
+    account
+}
+
+impl RFQMakerMessage {
+    /// Create a match against this maker quote for `base_asset_amount`
+    pub fn to_match(&self, base_asset_amount: u64) -> RFQMatch {
+        RFQMatch {
+            base_asset_amount,
+            maker_order_params: self.order_params,
+            maker_signature: self.signature,
+        }
+    }
+}
+
+impl RFQMatch {
+    /// Return the maker's drift user account address
+    pub fn maker(&self) -> Pubkey {
+        Wallet::derive_user_account(
+            &self.maker_order_params.authority,
+            self.maker_order_params.sub_account_id,
+        )
+    }
+
+    /// Build the ed25519 verify ix authenticating the maker's signature
+    pub fn to_ed25519_verify_ix(&self) -> Instruction {
+        new_ed25519_verify_ix(
+            &self.maker_order_params.authority,
+            &solana_sdk::signature::Signature::from(self.maker_signature.0),
+            &encode_maker_order_params(&self.maker_order_params),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anchor_lang::{AccountDeserialize, AccountSerialize};
+    use solana_sdk::{signature::Keypair, signer::Signer};
+
+    use super::*;
+    use crate::types::accounts::RFQUser;
+
+    #[tokio::test]
+    async fn sign_and_verify_maker_order() {
+        let wallet = Wallet::new(Keypair::new());
+        let order_params = maker_order_params(
+            &wallet,
+            1,
+            MarketId::perp(2),
+            -5_000,
+            100,
+            1_000,
+            *b"rfq-uuid",
+        );
+        assert_eq!(order_params.direction, PositionDirection::Short);
+        assert_eq!(order_params.base_asset_amount, 5_000);
+
+        let mut message = sign_maker_order(&wallet, order_params).await.unwrap();
+        assert!(verify_maker_message(&message));
+
+        let rfq_match = message.to_match(1_000);
+        assert_eq!(
+            rfq_match.maker(),
+            Wallet::derive_user_account(wallet.authority(), 1)
+        );
+
+        message.order_params.price += 1;
+        assert!(!verify_maker_message(&message));
+    }
+
+    #[tokio::test]
+    async fn sign_maker_order_rejects_delegated_wallet() {
+        let authority = Keypair::new();
+        let mut wallet = Wallet::new(Keypair::new());
+        wallet.to_delegated(authority.pubkey());
+        let order_params = maker_order_params(
+            &wallet,
+            0,
+            MarketId::perp(0),
+            5_000,
+            100,
+            1_000,
+            *b"rfq-uuid",
+        );
+        assert_eq!(order_params.authority, authority.pubkey());
+
+        assert!(matches!(
+            sign_maker_order(&wallet, order_params).await,
+            Err(SdkError::Signing(SignerError::KeypairPubkeyMismatch))
+        ));
+    }
+
+    #[test]
+    fn rfq_user_decode() {
+        let user_pubkey = Pubkey::new_unique();
+        let mut rfq_user = RFQUser {
+            user_pubkey,
+            ..Default::default()
+        };
+        rfq_user.rfq_order_data[0].uuid = *b"rfq-uuid";
+        rfq_user.rfq_order_data[0].max_ts = 1_000;
+
+        let mut data = Vec::new();
+        rfq_user.try_serialize(&mut data).unwrap();
+        let decoded = RFQUser::try_deserialize(&mut data.as_slice()).unwrap();
+        assert_eq!(decoded, rfq_user);
+    }
+}
@@ -0,0 +1,266 @@
+//! Pluggable tx signers
+//!
+//! `Wallet` signs via a `DriftSigner`, allowing keys to be held outside of the trading process
+//! e.g. by a remote signing service.
+//!
+//! ```ignore
+//! // keys are held by a signing service
+//! let signer = RemoteSigner::new("http://signer.internal:8080", authority);
+//! let wallet = Wallet::from_signer(signer);
+//! // co-signed txs
+//! let signer = MultiSigner::new(vec![Arc::new(keypair), Arc::new(remote_signer)]);
+//! let wallet = Wallet::from_signer(signer);
+//! ```
+use std::{fmt::Debug, sync::Arc};
+
+use async_trait::async_trait;
+use base64::Engine;
+use futures_util::future::join_all;
+use serde_json::json;
+use solana_client::{nonblocking::rpc_client::RpcClient, rpc_request::RpcRequest};
+use solana_sdk::{
+    message::VersionedMessage,
+    pubkey::Pubkey,
+    signature::{Keypair, Signature},
+    signer::{Signer, SignerError},
+};
+
+use crate::types::{SdkError, SdkResult};
+
+/// Signs messages and txs on behalf of a `Wallet`
+#[async_trait]
+pub trait DriftSigner: Debug + Send + Sync {
+    /// Return the signing pubkey
+    ///
+    /// For signers with multiple keys, this is the primary (fee payer) key
+    fn pubkey(&self) -> Pubkey;
+
+    /// Sign arbitrary `message` bytes with the primary key
+    async fn sign_message(&self, message: &[u8]) -> SdkResult<Signature>;
+
+    /// Sign a tx `message`, returning a signature for each key of the signer required by `message`
+    async fn sign_tx_message(
+        &self,
+        message: &VersionedMessage,
+    ) -> SdkResult<Vec<(Pubkey, Signature)>> {
+        let signature = self.sign_message(&message.serialize()).await?;
+        Ok(vec![(self.pubkey(), signature)])
+    }
+}
+
+#[async_trait]
+impl DriftSigner for Keypair {
+    fn pubkey(&self) -> Pubkey {
+        Signer::pubkey(self)
+    }
+
+    async fn sign_message(&self, message: &[u8]) -> SdkResult<Signature> {
+        Ok(Signer::sign_message(self, message))
+    }
+}
+
+#[async_trait]
+impl<T: DriftSigner + ?Sized> DriftSigner for Arc<T> {
+    fn pubkey(&self) -> Pubkey {
+        self.as_ref().pubkey()
+    }
+
+    async fn sign_message(&self, message: &[u8]) -> SdkResult<Signature> {
+        self.as_ref().sign_message(message).await
+    }
+
+    async fn sign_tx_message(
+        &self,
+        message: &VersionedMessage,
+    ) -> SdkResult<Vec<(Pubkey, Signature)>> {
+        self.as_ref().sign_tx_message(message).await
+    }
+}
+
+/// Signs via a remote signing service over JSON-RPC
+///
+/// The service must implement a `signMessage` method:
+/// - params: `[<pubkey (base58)>, <message (base64)>]`
+/// - result: `<signature (base58)>`
+pub struct RemoteSigner {
+    /// public key of the remote signing key
+    pubkey: Pubkey,
+    /// signing service client
+    rpc_client: RpcClient,
+}
+
+impl Debug for RemoteSigner {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RemoteSigner")
+            .field("pubkey", &self.pubkey)
+            .field("url", &self.rpc_client.url())
+            .finish()
+    }
+}
+
+impl RemoteSigner {
+    /// Create a new `RemoteSigner`
+    ///
+    /// * `url` - signing service endpoint
+    /// * `pubkey` - public key of the remote signing key
+    pub fn new(url: &str, pubkey: Pubkey) -> Self {
+        Self::with_rpc_client(RpcClient::new(url.to_string()), pubkey)
+    }
+
+    /// Create a new `RemoteSigner` using `rpc_client` to reach the signing service
+    pub fn with_rpc_client(rpc_client: RpcClient, pubkey: Pubkey) -> Self {
+        Self { pubkey, rpc_client }
+    }
+}
+
+#[async_trait]
+impl DriftSigner for RemoteSigner {
+    fn pubkey(&self) -> Pubkey {
+        self.pubkey
+    }
+
+    async fn sign_message(&self, message: &[u8]) -> SdkResult<Signature> {
+        let signature: String = self
+            .rpc_client
+            .send(
+                RpcRequest::Custom {
+                    method: "signMessage",
+                },
+                json!([
+                    self.pubkey.to_string(),
+                    base64::engine::general_purpose::STANDARD.encode(message)
+                ]),
+            )
+            .await?;
+        let signature: Signature = signature.parse().map_err(|_| SdkError::InvalidBase58)?;
+
+        // never trust the remote
+        if !signature.verify(self.pubkey.as_ref(), message) {
+            return Err(SignerError::InvalidInput("invalid remote signature".into()).into());
+        }
+
+        Ok(signature)
+    }
+}
+
+/// Co-signs txs with multiple signers
+///
+/// The first signer is the primary signer, used for message signing and as the wallet signer.
+/// Txs are signed by those signers required by the tx only
+#[derive(Debug)]
+pub struct MultiSigner {
+    signers: Vec<Arc<dyn DriftSigner>>,
+}
+
+impl MultiSigner {
+    /// Create a new `MultiSigner`
+    ///
+    /// # Panics
+    /// if `signers` is empty
+    pub fn new(signers: Vec<Arc<dyn DriftSigner>>) -> Self {
+        assert!(!signers.is_empty(), "at least 1 signer");
+        Self { signers }
+    }
+
+    /// Return the pubkeys of all signers
+    pub fn pubkeys(&self) -> Vec<Pubkey> {
+        self.signers.iter().map(|s| s.pubkey()).collect()
+    }
+}
+
+#[async_trait]
+impl DriftSigner for MultiSigner {
+    fn pubkey(&self) -> Pubkey {
+        self.signers[0].pubkey()
+    }
+
+    async fn sign_message(&self, message: &[u8]) -> SdkResult<Signature> {
+        self.signers[0].sign_message(message).await
+    }
+
+    async fn sign_tx_message(
+        &self,
+        message: &VersionedMessage,
+    ) -> SdkResult<Vec<(Pubkey, Signature)>> {
+        let required_signers =
+            &message.static_account_keys()[..message.header().num_required_signatures as usize];
+        let results = join_all(
+            self.signers
+                .iter()
+                .filter(|s| required_signers.contains(&s.pubkey()))
+                .map(|s| s.sign_tx_message(message)),
+        )
+        .await;
+        let mut signatures = Vec::with_capacity(self.signers.len());
+        for result in results {
+            signatures.extend(result?);
+        }
+        Ok(signatures)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use solana_client::rpc_client::Mocks;
+    use solana_sdk::{
+        instruction::{AccountMeta, Instruction},
+        message::Message,
+    };
+
+    use super::*;
+
+    fn remote_signer(keypair: &Keypair, message: &[u8]) -> RemoteSigner {
+        // the mock signing service signs `message` only
+        let mut mocks = Mocks::default();
+        mocks.insert(
+            RpcRequest::Custom {
+                method: "signMessage",
+            },
+            json!(Signer::sign_message(keypair, message).to_string()),
+        );
+        RemoteSigner::with_rpc_client(
+            RpcClient::new_mock_with_mocks("succeeds".into(), mocks),
+            Signer::pubkey(keypair),
+        )
+    }
+
+    #[tokio::test]
+    async fn remote_signer_signs() {
+        let keypair = Keypair::new();
+        let signer = remote_signer(&keypair, b"hello");
+        let signature = signer.sign_message(b"hello").await.unwrap();
+        assert!(signature.verify(Signer::pubkey(&keypair).as_ref(), b"hello"));
+
+        // remote returns a signature over some other message
+        let signer = remote_signer(&keypair, b"other");
+        assert!(signer.sign_message(b"hello").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn multi_signer_signs_all() {
+        let a = Keypair::new();
+        let payer = Signer::pubkey(&a);
+        let multi_signer = MultiSigner::new(vec![Arc::new(a), Arc::new(Keypair::new())]);
+        assert_eq!(DriftSigner::pubkey(&multi_signer), payer);
+
+        let co_signer = multi_signer.pubkeys()[1];
+        let ix = Instruction::new_with_bytes(
+            Pubkey::new_unique(),
+            &[1],
+            vec![AccountMeta::new_readonly(co_signer, true)],
+        );
+        let message = VersionedMessage::Legacy(Message::new(&[ix], Some(&payer)));
+        let signatures = multi_signer.sign_tx_message(&message).await.unwrap();
+        assert_eq!(signatures.len(), 2);
+        for ((pubkey, signature), expected) in signatures.iter().zip(multi_signer.pubkeys()) {
+            assert_eq!(*pubkey, expected);
+            assert!(signature.verify(pubkey.as_ref(), &message.serialize()));
+        }
+
+        // co-signer is not required
+        let message = VersionedMessage::Legacy(Message::new(&[], Some(&payer)));
+        let signatures = multi_signer.sign_tx_message(&message).await.unwrap();
+        assert_eq!(signatures.len(), 1);
+        assert_eq!(signatures[0].0, payer);
+    }
+}
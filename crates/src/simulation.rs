@@ -0,0 +1,187 @@
+//! Tx simulation reports
+//!
+//! Simulate txs without landing them, e.g. for pre-flight checks of orders.
+//!
+//! ```ignore
+//! let tx = client.init_tx(&sub_account, false).await?.place_orders(orders).build();
+//! let report = client.simulate(&tx).await?;
+//! if let Some(code) = report.error_code {
+//!     println!("order would fail: {code:?}");
+//! }
+//! for event in report.events {
+//!     println!("{event:?}");
+//! }
+//! ```
+use solana_client::rpc_response::RpcSimulateTransactionResult;
+use solana_sdk::{clock::Slot, message::VersionedMessage, transaction::TransactionError};
+
+use crate::{
+    constants::PROGRAM_ID,
+    event_subscriber::{try_parse_log, DriftEvent},
+    types::{decode_tx_error, errors::ErrorCode},
+};
+
+/// Result of a tx simulation
+#[derive(Debug, PartialEq)]
+pub struct SimulationReport {
+    /// slot the tx was simulated at
+    pub slot: Slot,
+    /// compute units consumed
+    pub units_consumed: Option<u64>,
+    /// tx error, if the tx would fail
+    pub error: Option<TransactionError>,
+    /// the drift program error, if `error` originates from the drift program
+    pub error_code: Option<ErrorCode>,
+    /// raw program logs
+    pub logs: Vec<String>,
+    /// drift events the tx would emit
+    ///
+    /// events have an empty signature as the tx did not land
+    pub events: Vec<DriftEvent>,
+}
+
+impl SimulationReport {
+    /// Build a report from the RPC simulation `result` of `tx` at `slot`
+    pub fn new(slot: Slot, result: RpcSimulateTransactionResult, tx: &VersionedMessage) -> Self {
+        let logs = result.logs.unwrap_or_default();
+        let events = parse_drift_events(&logs);
+
+        Self {
+            slot,
+            units_consumed: result.units_consumed,
+            error_code: result.err.as_ref().and_then(|err| decode_tx_error(err, tx)),
+            error: result.err,
+            logs,
+            events,
+        }
+    }
+
+    /// True if the tx would succeed
+    pub fn is_success(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+/// Parse drift events from tx `logs`
+///
+/// Only logs emitted while the drift program is executing are parsed, logs of other programs
+/// may look like events
+fn parse_drift_events(logs: &[String]) -> Vec<DriftEvent> {
+    let drift_program = PROGRAM_ID.to_string();
+    // programs of the current invocation stack
+    let mut invoked = Vec::<&str>::new();
+    let mut events = Vec::new();
+
+    for (tx_idx, log) in logs.iter().enumerate() {
+        let mut words = log.split_whitespace();
+        match (words.next(), words.next(), words.next()) {
+            (Some("Program"), Some(program), Some("invoke")) => invoked.push(program),
+            (Some("Program"), Some(program), Some("success" | "failed:"))
+                if invoked.last() == Some(&program) =>
+            {
+                invoked.pop();
+            }
+            _ => {
+                if invoked.last() == Some(&drift_program.as_str()) {
+                    events.extend(try_parse_log(log.as_str(), "", tx_idx));
+                }
+            }
+        }
+    }
+
+    events
+}
+
+#[cfg(test)]
+mod tests {
+    use solana_sdk::{
+        compute_budget::ComputeBudgetInstruction,
+        instruction::{Instruction, InstructionError},
+        message::Message,
+        pubkey::Pubkey,
+    };
+
+    use super::*;
+
+    #[test]
+    fn report_decodes_error_and_events() {
+        let code = anchor_lang::error::ERROR_CODE_OFFSET + ErrorCode::InsufficientCollateral as u32;
+        let result = RpcSimulateTransactionResult {
+            err: Some(TransactionError::InstructionError(
+                1,
+                InstructionError::Custom(code),
+            )),
+            logs: Some(vec![
+                "Program ComputeBudget111111111111111111111111111111 invoke [1]".into(),
+                "Program ComputeBudget111111111111111111111111111111 success".into(),
+                format!("Program {PROGRAM_ID} invoke [1]"),
+                "Program log: Instruction: PlaceAndTakePerpOrder".into(),
+                "Program log: 4DRDR8LtbQFOKvplAAAAAAAAGAABAAAAAAAAAAAAAAFGJn8TpIimFlKv8ZWRhmuU81x+ojkf3K4d+++MbslDfAGZcTYAAQEBAM5q/TIAAAABAAAAAAAAAAABAAAAAAAAAAAAAAAAAACTWxEAAAAAAA==".into(),
+                format!("Program {PROGRAM_ID} failed: custom program error: {code:#x}"),
+            ]),
+            accounts: None,
+            units_consumed: Some(50_000),
+            return_data: None,
+            inner_instructions: None,
+            replacement_blockhash: None,
+        };
+
+        let tx = VersionedMessage::Legacy(Message::new(
+            &[
+                ComputeBudgetInstruction::set_compute_unit_limit(200_000),
+                Instruction::new_with_bytes(PROGRAM_ID, &[], vec![]),
+            ],
+            Some(&Pubkey::new_unique()),
+        ));
+        let report = SimulationReport::new(100, result, &tx);
+        assert!(!report.is_success());
+        assert_eq!(report.slot, 100);
+        assert_eq!(report.units_consumed, Some(50_000));
+        assert_eq!(report.error_code, Some(ErrorCode::InsufficientCollateral));
+        assert_eq!(report.logs.len(), 6);
+        assert_eq!(report.events.len(), 1);
+    }
+
+    #[test]
+    fn report_ignores_short_and_non_drift_logs() {
+        let event_log = "Program log: 4DRDR8LtbQFOKvplAAAAAAAAGAABAAAAAAAAAAAAAAFGJn8TpIimFlKv8ZWRhmuU81x+ojkf3K4d+++MbslDfAGZcTYAAQEBAM5q/TIAAAABAAAAAAAAAAABAAAAAAAAAAAAAAAAAACTWxEAAAAAAA==";
+        let other_program = Pubkey::new_unique();
+        // base64 decodes to less than 8 bytes
+        let short_log = "Program log: Done";
+        assert!(try_parse_log(short_log, "", 0).is_none());
+
+        let result = RpcSimulateTransactionResult {
+            err: None,
+            logs: Some(vec![
+                format!("Program {other_program} invoke [1]"),
+                short_log.into(),
+                // event-like log from another program
+                event_log.into(),
+                format!("Program {PROGRAM_ID} invoke [2]"),
+                short_log.into(),
+                event_log.into(),
+                format!("Program {PROGRAM_ID} success"),
+                event_log.into(),
+                format!("Program {other_program} success"),
+            ]),
+            accounts: None,
+            units_consumed: Some(50_000),
+            return_data: None,
+            inner_instructions: None,
+            replacement_blockhash: None,
+        };
+        let tx = VersionedMessage::Legacy(Message::new(
+            &[Instruction::new_with_bytes(other_program, &[], vec![])],
+            Some(&Pubkey::new_unique()),
+        ));
+
+        let report = SimulationReport::new(100, result, &tx);
+        assert!(report.is_success());
+        assert_eq!(report.error_code, None);
+        // only the event logged by the drift program
+        assert_eq!(
+            report.events,
+            vec![try_parse_log(event_log, "", 5).unwrap()]
+        );
+    }
+}
@@ -0,0 +1,175 @@
+//! Spot swap quoting
+//!
+//! Swaps are executed by wrapping external swap ixs (e.g. from an aggregator) with drift's
+//! begin/end swap ixs, see `TransactionBuilder::swap`.
+//!
+//! ```ignore
+//! let quote = provider.quote(&request).await?;
+//! let tx = client
+//!     .init_tx(&sub_account, false)
+//!     .await?
+//!     .lookup_tables(&quote.lookup_tables)
+//!     .swap(request.in_market, request.out_market, quote.amount_in, quote.ixs)
+//!     .build();
+//! ```
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use solana_sdk::{
+    address_lookup_table::AddressLookupTableAccount, instruction::Instruction, pubkey::Pubkey,
+};
+
+use crate::{
+    math::constants::PRICE_PRECISION,
+    types::{SdkError, SdkResult},
+};
+
+/// Basis points denominator
+const BPS_PRECISION: u64 = 10_000;
+
+/// Parameters for requesting a swap quote
+#[derive(Clone, Debug, PartialEq)]
+pub struct SwapQuoteRequest {
+    /// spot market index of the token to sell
+    pub in_market: u16,
+    /// spot market index of the token to buy
+    pub out_market: u16,
+    /// mint of the token to sell
+    pub in_mint: Pubkey,
+    /// mint of the token to buy
+    pub out_mint: Pubkey,
+    /// amount of the in token to sell (in token precision)
+    pub amount_in: u64,
+    /// max. slippage tolerated (basis points)
+    pub slippage_bps: u16,
+    /// authority performing the swap, owner of the in/out token accounts
+    pub authority: Pubkey,
+}
+
+/// A swap quote with ixs ready for execution
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SwapQuote {
+    /// amount of the in token sold (in token precision)
+    pub amount_in: u64,
+    /// expected amount of the out token bought (in token precision)
+    pub amount_out: u64,
+    /// min. amount of the out token bought after slippage (in token precision)
+    pub min_amount_out: u64,
+    /// ixs performing the swap
+    pub ixs: Vec<Instruction>,
+    /// lookup tables required by `ixs`, if any
+    pub lookup_tables: Vec<AddressLookupTableAccount>,
+}
+
+/// Provides swap quotes and ixs e.g. from a DEX aggregator
+#[async_trait]
+pub trait SwapQuoteProvider: Send + Sync {
+    /// Get a quote for swapping `request.amount_in` of the in token to the out token
+    async fn quote(&self, request: &SwapQuoteRequest) -> SdkResult<SwapQuote>;
+}
+
+/// A fixed rate swap route
+#[derive(Clone, Debug, Default)]
+struct Route {
+    /// out token amount per in token amount (PRICE_PRECISION)
+    rate: u64,
+    /// ixs returned with every quote
+    ixs: Vec<Instruction>,
+}
+
+/// `SwapQuoteProvider` with fixed rate routes held in memory
+///
+/// useful for tests and simulations
+#[derive(Clone, Debug, Default)]
+pub struct InMemorySwapQuoteProvider {
+    routes: HashMap<(u16, u16), Route>,
+}
+
+impl InMemorySwapQuoteProvider {
+    /// Create a new provider without any routes
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a route from `in_market` to `out_market`
+    ///
+    /// * `rate` - out token amount received per in token amount (PRICE_PRECISION)
+    /// * `ixs` - ixs returned with every quote for this route
+    pub fn with_route(
+        mut self,
+        in_market: u16,
+        out_market: u16,
+        rate: u64,
+        ixs: Vec<Instruction>,
+    ) -> Self {
+        self.routes
+            .insert((in_market, out_market), Route { rate, ixs });
+        self
+    }
+}
+
+#[async_trait]
+impl SwapQuoteProvider for InMemorySwapQuoteProvider {
+    async fn quote(&self, request: &SwapQuoteRequest) -> SdkResult<SwapQuote> {
+        let route = self
+            .routes
+            .get(&(request.in_market, request.out_market))
+            .ok_or_else(|| {
+                SdkError::Generic(format!(
+                    "no swap route: spot/{} -> spot/{}",
+                    request.in_market, request.out_market
+                ))
+            })?;
+
+        let amount_out = (request.amount_in as u128 * route.rate as u128 / PRICE_PRECISION) as u64;
+        let slippage_bps = (request.slippage_bps as u64).min(BPS_PRECISION);
+        let min_amount_out = (amount_out as u128 * (BPS_PRECISION - slippage_bps) as u128
+            / BPS_PRECISION as u128) as u64;
+
+        Ok(SwapQuote {
+            amount_in: request.amount_in,
+            amount_out,
+            min_amount_out,
+            ixs: route.ixs.clone(),
+            lookup_tables: vec![],
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::constants::PRICE_PRECISION_U64;
+
+    fn request(in_market: u16, out_market: u16) -> SwapQuoteRequest {
+        SwapQuoteRequest {
+            in_market,
+            out_market,
+            in_mint: Pubkey::new_unique(),
+            out_mint: Pubkey::new_unique(),
+            amount_in: 1_000_000_000,
+            slippage_bps: 50,
+            authority: Pubkey::new_unique(),
+        }
+    }
+
+    #[tokio::test]
+    async fn in_memory_quote() {
+        let swap_ix = Instruction::new_with_bytes(Pubkey::new_unique(), &[1, 2, 3], vec![]);
+        // 1 SOL (1e9) -> 150 USDC (1e6)
+        let provider = InMemorySwapQuoteProvider::new().with_route(
+            1,
+            0,
+            150 * PRICE_PRECISION_U64 / 1_000,
+            vec![swap_ix.clone()],
+        );
+
+        let quote = provider.quote(&request(1, 0)).await.unwrap();
+        assert_eq!(quote.amount_in, 1_000_000_000);
+        assert_eq!(quote.amount_out, 150_000_000);
+        assert_eq!(quote.min_amount_out, 149_250_000);
+        assert_eq!(quote.ixs, vec![swap_ix]);
+
+        assert!(provider.quote(&request(0, 1)).await.is_err());
+    }
+}
@@ -0,0 +1,222 @@
+//! Swift (signed message) orders
+//!
+//! Takers sign order params off-chain and any keeper or market maker may place them onchain.
+//! The drift program authenticates the taker's signature via an ed25519 verify ix preceding the place ix.
+//!
+//! ```ignore
+//! // taker
+//! let signed_order = SignedSwiftOrder::sign(&taker_wallet, message).await?;
+//! // market maker
+//! let tx = client
+//!     .init_tx(&maker_sub_account, false)
+//!     .await?
+//!     .place_and_make_swift_perp_order(&(taker, taker_account), &signed_order, maker_order_params)
+//!     .build();
+//! ```
+use anchor_lang::{AnchorDeserialize, AnchorSerialize};
+use solana_sdk::{instruction::Instruction, pubkey::Pubkey, signature::Signature};
+
+use crate::{
+    constants::PROGRAM_ID,
+    types::{SdkError, SdkResult, SwiftOrderParamsMessage},
+    Wallet,
+};
+
+/// Length of an ed25519 public key
+const PUBKEY_LEN: usize = 32;
+/// Length of an ed25519 signature
+const SIGNATURE_LEN: usize = 64;
+/// Offset of the first signature's data in an ed25519 verify ix (num signatures, padding, offsets)
+const ED25519_DATA_START: usize = 2 + 14;
+
+/// A swift order params message signed by the taker
+#[derive(Clone, Debug, PartialEq)]
+pub struct SignedSwiftOrder {
+    /// the order params message
+    pub message: SwiftOrderParamsMessage,
+    /// serialized `message`, as signed
+    pub message_bytes: Vec<u8>,
+    /// taker's signature over `message_bytes`
+    pub signature: Signature,
+    /// public key of the signer (taker's authority)
+    pub signer: Pubkey,
+}
+
+impl SignedSwiftOrder {
+    /// Serialize and sign a swift order params `message` with `wallet`
+    ///
+    /// `wallet` must be the taker account's authority
+    pub async fn sign(wallet: &Wallet, message: SwiftOrderParamsMessage) -> SdkResult<Self> {
+        let message_bytes = encode_swift_order_params_message(&message);
+        let signature = wallet.sign_message(&message_bytes).await?;
+
+        Ok(Self {
+            message,
+            message_bytes,
+            signature,
+            signer: wallet.signer(),
+        })
+    }
+
+    /// Build a `SignedSwiftOrder` from parts received from a taker
+    ///
+    /// Returns error if the message cannot be decoded or the signature is invalid
+    pub fn from_parts(
+        message_bytes: Vec<u8>,
+        signature: Signature,
+        signer: Pubkey,
+    ) -> SdkResult<Self> {
+        let message = decode_swift_order_params_message(&message_bytes)?;
+        let signed_order = Self {
+            message,
+            message_bytes,
+            signature,
+            signer,
+        };
+        if !signed_order.verify() {
+            return Err(SdkError::Signing(
+                solana_sdk::signer::SignerError::InvalidInput(
+                    "invalid swift order signature".into(),
+                ),
+            ));
+        }
+
+        Ok(signed_order)
+    }
+
+    /// Return true if the signature is valid for the message and signer
+    pub fn verify(&self) -> bool {
+        self.signature
+            .verify(self.signer.as_ref(), self.message_bytes.as_slice())
+    }
+
+    /// Build the ed25519 verify ix authenticating this order
+    ///
+    /// must be placed immediately before the ix placing the order
+    pub fn to_ed25519_verify_ix(&self) -> Instruction {
+        new_ed25519_verify_ix(&self.signer, &self.signature, &self.message_bytes)
+    }
+}
+
+/// Serialize a swift order params `message` for signing
+pub fn encode_swift_order_params_message(message: &SwiftOrderParamsMessage) -> Vec<u8> {
+    let mut data = Vec::new();
+    message.serialize(&mut data).expect("serializes");
+    data
+}
+
+/// Deserialize a swift order params message from `data`
+pub fn decode_swift_order_params_message(data: &[u8]) -> SdkResult<SwiftOrderParamsMessage> {
+    SwiftOrderParamsMessage::deserialize(&mut &data[..]).map_err(|_| SdkError::Deserializing)
+}
+
+/// calculate the PDA of an authority's swift user orders account
+pub fn derive_swift_user_orders(authority: &Pubkey) -> Pubkey {
+    let (account, _seed) =
+        Pubkey::find_program_address(&[&b"SWIFT"[..], authority.as_ref()], &PROGRAM_ID);
+    account
+}
+
+/// Build an ed25519 program ix verifying `signature` of `message` by `pubkey`
+///
+/// all data is contained within the ix itself
+pub fn new_ed25519_verify_ix(
+    pubkey: &Pubkey,
+    signature: &Signature,
+    message: &[u8],
+) -> Instruction {
+    let public_key_offset = ED25519_DATA_START;
+    let signature_offset = public_key_offset + PUBKEY_LEN;
+    let message_data_offset = signature_offset + SIGNATURE_LEN;
+
+    // u16::MAX => data is in this ix
+    let offsets: [u16; 7] = [
+        signature_offset as u16,
+        u16::MAX,
+        public_key_offset as u16,
+        u16::MAX,
+        message_data_offset as u16,
+        message.len() as u16,
+        u16::MAX,
+    ];
+
+    let mut data = Vec::with_capacity(message_data_offset + message.len());
+    data.extend_from_slice(&[1, 0]); // num signatures, padding
+    for offset in offsets {
+        data.extend_from_slice(&offset.to_le_bytes());
+    }
+    data.extend_from_slice(pubkey.as_ref());
+    data.extend_from_slice(signature.as_ref());
+    data.extend_from_slice(message);
+
+    Instruction {
+        program_id: solana_sdk::ed25519_program::ID,
+        accounts: vec![],
+        data,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use solana_sdk::signature::Keypair;
+
+    use super::*;
+    use crate::types::{MarketId, NewOrder};
+
+    fn message() -> SwiftOrderParamsMessage {
+        SwiftOrderParamsMessage {
+            swift_order_params: NewOrder::market(MarketId::perp(1)).amount(1_000).build(),
+            sub_account_id: 2,
+            slot: 12_345,
+            uuid: *b"abcdefgh",
+            take_profit_order_params: None,
+            stop_loss_order_params: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn sign_and_verify() {
+        let wallet = Wallet::new(Keypair::new());
+        let signed_order = SignedSwiftOrder::sign(&wallet, message()).await.unwrap();
+        assert!(signed_order.verify());
+        assert_eq!(
+            decode_swift_order_params_message(&signed_order.message_bytes).unwrap(),
+            message()
+        );
+
+        let received = SignedSwiftOrder::from_parts(
+            signed_order.message_bytes.clone(),
+            signed_order.signature,
+            *wallet.authority(),
+        )
+        .unwrap();
+        assert_eq!(received, signed_order);
+
+        // wrong signer
+        assert!(SignedSwiftOrder::from_parts(
+            signed_order.message_bytes,
+            signed_order.signature,
+            Pubkey::new_unique(),
+        )
+        .is_err());
+    }
+
+    #[tokio::test]
+    async fn ed25519_verify_ix_layout() {
+        let wallet = Wallet::new(Keypair::new());
+        let signed_order = SignedSwiftOrder::sign(&wallet, message()).await.unwrap();
+        let ix = signed_order.to_ed25519_verify_ix();
+
+        let message_len = signed_order.message_bytes.len();
+        assert_eq!(ix.program_id, solana_sdk::ed25519_program::ID);
+        assert_eq!(ix.data.len(), 112 + message_len);
+        assert_eq!(&ix.data[16..48], wallet.authority().as_ref());
+        assert_eq!(&ix.data[48..112], signed_order.signature.as_ref());
+        assert_eq!(&ix.data[112..], signed_order.message_bytes.as_slice());
+        // message data size
+        assert_eq!(
+            u16::from_le_bytes([ix.data[12], ix.data[13]]) as usize,
+            message_len
+        );
+    }
+}
@@ -0,0 +1,402 @@
+//! Tx sending with rebroadcast and confirmation tracking
+//!
+//! Signed txs are rebroadcast at an interval until confirmed or until the tx blockhash expires.
+//!
+//! ```ignore
+//! let outcome = client
+//!     .sign_and_confirm(tx, TxSenderConfig::default())
+//!     .await?;
+//! match outcome {
+//!     TxOutcome::Landed { slot, .. } => println!("landed: {slot}"),
+//!     TxOutcome::Failed { code, .. } => println!("failed: {code:?}"),
+//!     TxOutcome::Expired { .. } => println!("expired"),
+//! }
+//! ```
+use std::{sync::Arc, time::Duration};
+
+use futures_util::StreamExt;
+use log::{debug, warn};
+use solana_client::{
+    nonblocking::{pubsub_client::PubsubClient, rpc_client::RpcClient},
+    rpc_config::{RpcSendTransactionConfig, RpcSignatureSubscribeConfig},
+    rpc_response::RpcSignatureResult,
+};
+use solana_sdk::{
+    clock::Slot,
+    commitment_config::CommitmentConfig,
+    message::VersionedMessage,
+    signature::Signature,
+    transaction::{TransactionError, VersionedTransaction},
+};
+
+use crate::{
+    fanout::FanoutSender,
+    types::{decode_tx_error, errors::ErrorCode, SdkError, SdkResult},
+    utils::get_ws_url,
+    Wallet,
+};
+
+const LOG_TARGET: &str = "txsender";
+
+/// How tx confirmations are tracked
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ConfirmationMethod {
+    /// Poll signature statuses at the given interval
+    Polling(Duration),
+    /// Subscribe to the signature via websocket (`signatureSubscribe`)
+    Websocket,
+}
+
+/// `TxSender` config
+#[derive(Copy, Clone, Debug)]
+pub struct TxSenderConfig {
+    /// interval to rebroadcast the tx at until confirmed or expired
+    pub rebroadcast_interval: Duration,
+    /// how tx confirmations are tracked
+    pub confirmation: ConfirmationMethod,
+    /// commitment level the tx must reach to be confirmed
+    pub commitment: CommitmentConfig,
+    /// RPC config for sending the tx
+    pub send_config: RpcSendTransactionConfig,
+}
+
+impl Default for TxSenderConfig {
+    fn default() -> Self {
+        Self {
+            rebroadcast_interval: Duration::from_secs(2),
+            confirmation: ConfirmationMethod::Polling(Duration::from_millis(500)),
+            commitment: CommitmentConfig::confirmed(),
+            send_config: RpcSendTransactionConfig {
+                skip_preflight: true,
+                max_retries: Some(0),
+                ..Default::default()
+            },
+        }
+    }
+}
+
+/// Final outcome of a sent tx
+#[derive(Clone, Debug, PartialEq)]
+pub enum TxOutcome {
+    /// tx landed successfully
+    Landed { signature: Signature, slot: Slot },
+    /// tx landed with an error
+    Failed {
+        signature: Signature,
+        slot: Slot,
+        /// the tx error
+        error: TransactionError,
+        /// the drift program error, if `error` originates from the drift program
+        code: Option<ErrorCode>,
+    },
+    /// tx did not land before its blockhash expired
+    Expired { signature: Signature },
+}
+
+impl TxOutcome {
+    fn from_status(tx: &VersionedTransaction, slot: Slot, err: Option<TransactionError>) -> Self {
+        let signature = tx.signatures[0];
+        match err {
+            None => Self::Landed { signature, slot },
+            Some(error) => Self::Failed {
+                signature,
+                slot,
+                code: decode_tx_error(&error, &tx.message),
+                error,
+            },
+        }
+    }
+    /// Return the tx signature
+    pub fn signature(&self) -> &Signature {
+        match self {
+            Self::Landed { signature, .. }
+            | Self::Failed { signature, .. }
+            | Self::Expired { signature } => signature,
+        }
+    }
+    /// True if the tx landed successfully
+    pub fn is_landed(&self) -> bool {
+        matches!(self, Self::Landed { .. })
+    }
+}
+
+/// Sends txs with rebroadcast until confirmed or expired
+#[derive(Clone)]
+pub struct TxSender {
+    rpc_client: Arc<RpcClient>,
+    config: TxSenderConfig,
+    /// send to multiple endpoints, if set
+    fanout: Option<Arc<FanoutSender>>,
+}
+
+impl TxSender {
+    /// Create a new `TxSender`
+    ///
+    /// * `rpc_client` - RPC client to send and confirm txs with
+    /// * `config` - rebroadcast and confirmation config
+    pub fn new(rpc_client: Arc<RpcClient>, config: TxSenderConfig) -> Self {
+        Self {
+            rpc_client,
+            config,
+            fanout: None,
+        }
+    }
+
+    /// Send (and rebroadcast) txs via `fanout`, `rpc_client` is still used for confirmations
+    pub fn with_fanout(mut self, fanout: Arc<FanoutSender>) -> Self {
+        self.fanout = Some(fanout);
+        self
+    }
+
+    /// Return the sender config
+    pub fn config(&self) -> &TxSenderConfig {
+        &self.config
+    }
+
+    /// Sign `tx` with `wallet` using a fresh blockhash and send it until confirmed or expired
+    pub async fn sign_and_send(
+        &self,
+        wallet: &Wallet,
+        tx: VersionedMessage,
+    ) -> SdkResult<TxOutcome> {
+        let (recent_block_hash, last_valid_block_height) = self
+            .rpc_client
+            .get_latest_blockhash_with_commitment(self.config.commitment)
+            .await?;
+        let tx = wallet.sign_tx(tx, recent_block_hash).await?;
+        self.send(&tx, last_valid_block_height).await
+    }
+
+    /// Send a signed `tx` until confirmed or expired
+    ///
+    /// * `tx` - signed tx
+    /// * `last_valid_block_height` - last block height the tx's blockhash is valid for
+    ///
+    /// Returns the tx outcome or error if the tx could not be sent initially
+    pub async fn send(
+        &self,
+        tx: &VersionedTransaction,
+        last_valid_block_height: u64,
+    ) -> SdkResult<TxOutcome> {
+        let signature = self.send_once(tx).await?;
+        debug!(target: LOG_TARGET, "sent tx: {signature:?}");
+
+        let confirmation = self.confirm(signature);
+        tokio::pin!(confirmation);
+        let mut rebroadcast = tokio::time::interval(self.config.rebroadcast_interval);
+        // first tick completes immediately
+        rebroadcast.tick().await;
+
+        loop {
+            tokio::select! {
+                status = &mut confirmation => {
+                    let (slot, err) = status?;
+                    return Ok(TxOutcome::from_status(tx, slot, err));
+                }
+                _ = rebroadcast.tick() => {
+                    let block_height = match self
+                        .rpc_client
+                        .get_block_height_with_commitment(self.config.commitment)
+                        .await
+                    {
+                        Ok(block_height) => block_height,
+                        Err(err) => {
+                            // the tx may still land, retry next tick
+                            warn!(target: LOG_TARGET, "block height query failed: {signature:?}, {err:?}");
+                            continue;
+                        }
+                    };
+                    if block_height > last_valid_block_height {
+                        // the tx may have landed since the last confirmation check
+                        return Ok(match self.signature_status(&signature).await? {
+                            Some((slot, err)) => TxOutcome::from_status(tx, slot, err),
+                            None => TxOutcome::Expired { signature },
+                        });
+                    }
+                    debug!(target: LOG_TARGET, "rebroadcast tx: {signature:?}");
+                    if let Err(err) = self.send_once(tx).await {
+                        warn!(target: LOG_TARGET, "rebroadcast failed: {signature:?}, {err:?}");
+                    }
+                }
+            }
+        }
+    }
+
+    /// Send `tx` once via the fanout endpoints or RPC client
+    async fn send_once(&self, tx: &VersionedTransaction) -> SdkResult<Signature> {
+        match self.fanout {
+            Some(ref fanout) => fanout.send(tx, self.config.send_config).await,
+            None => self
+                .rpc_client
+                .send_transaction_with_config(tx, self.config.send_config)
+                .await
+                .map_err(Into::into),
+        }
+    }
+
+    /// Wait for `signature` to reach the configured commitment
+    ///
+    /// Returns the landed slot and tx error, if any
+    async fn confirm(&self, signature: Signature) -> SdkResult<(Slot, Option<TransactionError>)> {
+        match self.config.confirmation {
+            ConfirmationMethod::Polling(interval) => loop {
+                tokio::time::sleep(interval).await;
+                match self.signature_status(&signature).await {
+                    Ok(Some(status)) => return Ok(status),
+                    Ok(None) => continue,
+                    Err(err) => warn!(target: LOG_TARGET, "status query failed: {err:?}"),
+                }
+            },
+            ConfirmationMethod::Websocket => {
+                let ws_url = get_ws_url(&self.rpc_client.url())?;
+                let pubsub = PubsubClient::new(&ws_url).await?;
+                let (mut stream, unsub) = pubsub
+                    .signature_subscribe(
+                        &signature,
+                        Some(RpcSignatureSubscribeConfig {
+                            commitment: Some(self.config.commitment),
+                            enable_received_notification: Some(false),
+                        }),
+                    )
+                    .await?;
+
+                while let Some(response) = stream.next().await {
+                    if let RpcSignatureResult::ProcessedSignature(result) = response.value {
+                        unsub().await;
+                        return Ok((response.context.slot, result.err));
+                    }
+                }
+
+                Err(SdkError::WebsocketError)
+            }
+        }
+    }
+
+    /// Query the status of `signature` at the configured commitment
+    ///
+    /// Returns `None` if the tx has not reached the commitment
+    async fn signature_status(
+        &self,
+        signature: &Signature,
+    ) -> SdkResult<Option<(Slot, Option<TransactionError>)>> {
+        let response = self
+            .rpc_client
+            .get_signature_statuses(&[*signature])
+            .await?;
+        Ok(response
+            .value
+            .into_iter()
+            .next()
+            .flatten()
+            .filter(|status| status.satisfies_commitment(self.config.commitment))
+            .map(|status| (status.slot, status.err)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use solana_client::{rpc_client::Mocks, rpc_request::RpcRequest};
+    use solana_sdk::{
+        instruction::{Instruction, InstructionError},
+        message::Message,
+        signature::Keypair,
+    };
+
+    use super::*;
+    use crate::constants::PROGRAM_ID;
+
+    async fn signed_tx() -> VersionedTransaction {
+        let wallet = Wallet::new(Keypair::new());
+        let ix = Instruction::new_with_bytes(PROGRAM_ID, &[], vec![]);
+        let message = VersionedMessage::Legacy(Message::new(&[ix], Some(wallet.authority())));
+        wallet.sign_tx(message, Default::default()).await.unwrap()
+    }
+
+    /// `url` sets the mock RPC default behaviour
+    fn sender(url: &str, mocks: Mocks) -> TxSender {
+        let rpc_client = RpcClient::new_mock_with_mocks(url.into(), mocks);
+        TxSender::new(
+            Arc::new(rpc_client),
+            TxSenderConfig {
+                rebroadcast_interval: Duration::from_millis(50),
+                confirmation: ConfirmationMethod::Polling(Duration::from_millis(10)),
+                ..Default::default()
+            },
+        )
+    }
+
+    fn status_response(err: serde_json::Value) -> serde_json::Value {
+        json!({
+            "context": { "slot": 100 },
+            "value": [{
+                "slot": 99,
+                "confirmations": null,
+                "err": err,
+                "status": { "Ok": null },
+                "confirmationStatus": "finalized",
+            }],
+        })
+    }
+
+    #[tokio::test]
+    async fn send_landed() {
+        let tx = signed_tx().await;
+        let mut mocks = Mocks::default();
+        mocks.insert(
+            RpcRequest::GetSignatureStatuses,
+            status_response(json!(null)),
+        );
+
+        let outcome = sender("succeeds", mocks).send(&tx, 1_000).await.unwrap();
+        assert_eq!(
+            outcome,
+            TxOutcome::Landed {
+                signature: tx.signatures[0],
+                slot: 99
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn send_failed_decodes_drift_error() {
+        let tx = signed_tx().await;
+        let code = anchor_lang::error::ERROR_CODE_OFFSET + ErrorCode::InsufficientCollateral as u32;
+        let mut mocks = Mocks::default();
+        mocks.insert(
+            RpcRequest::GetSignatureStatuses,
+            status_response(json!({ "InstructionError": [0, { "Custom": code }] })),
+        );
+
+        let outcome = sender("succeeds", mocks).send(&tx, 1_000).await.unwrap();
+        assert_eq!(
+            outcome,
+            TxOutcome::Failed {
+                signature: tx.signatures[0],
+                slot: 99,
+                error: TransactionError::InstructionError(0, InstructionError::Custom(code)),
+                code: Some(ErrorCode::InsufficientCollateral),
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn send_expired() {
+        let tx = signed_tx().await;
+        let mut mocks = Mocks::default();
+        mocks.insert(RpcRequest::GetBlockHeight, json!(1_001));
+
+        // signature statuses are never found
+        let outcome = sender("sig_not_found", mocks)
+            .send(&tx, 1_000)
+            .await
+            .unwrap();
+        assert_eq!(
+            outcome,
+            TxOutcome::Expired {
+                signature: tx.signatures[0]
+            }
+        );
+        assert!(!outcome.is_landed());
+    }
+}
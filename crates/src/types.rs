@@ -11,6 +11,7 @@ pub use solana_sdk::{
 };
 use solana_sdk::{
     instruction::{AccountMeta, InstructionError},
+    native_token::LAMPORTS_PER_SOL,
     pubkey::Pubkey,
     transaction::TransactionError,
 };
@@ -26,8 +27,9 @@ pub use crate::drift_idl::{
     types::*,
 };
 use crate::{
-    constants::{ids, LUT_DEVNET, LUT_MAINNET},
+    constants::{ids, LUT_DEVNET, LUT_MAINNET, PROGRAM_ID},
     drift_idl::errors::ErrorCode,
+    math::constants::PERCENTAGE_PRECISION_U64,
     Wallet,
 };
 
@@ -322,22 +324,21 @@ pub enum SdkError {
     AlreadySubscribed,
     #[error("invalid URL")]
     InvalidUrl,
+    #[error("invalid bundle size: {0}")]
+    InvalidBundleSize(usize),
+    #[error("bundle error: {0}")]
+    Bundle(String),
+    #[error("grpc error: {0}")]
+    Grpc(String),
 }
 
 impl SdkError {
     /// extract anchor error code from the SdkError if it exists
     pub fn to_anchor_error_code(&self) -> Option<ErrorCode> {
         if let SdkError::Rpc(inner) = self {
-            if let Some(TransactionError::InstructionError(_, InstructionError::Custom(code))) =
-                inner.get_transaction_error()
-            {
-                // inverse of anchor's 'From<ErrorCode> for u32'
-                return Some(unsafe {
-                    std::mem::transmute::<u32, ErrorCode>(
-                        code - anchor_lang::error::ERROR_CODE_OFFSET,
-                    )
-                });
-            }
+            return inner
+                .get_transaction_error()
+                .and_then(|err| decode_program_error(&err));
         }
         None
     }
@@ -356,6 +357,43 @@ impl SdkError {
     }
 }
 
+/// Decode the drift program `ErrorCode` from a tx error, if it exists
+///
+/// The failing program is not checked, see `decode_tx_error`
+pub fn decode_program_error(err: &TransactionError) -> Option<ErrorCode> {
+    if let TransactionError::InstructionError(_, InstructionError::Custom(code)) = err {
+        return error_code_from_custom(*code);
+    }
+    None
+}
+
+/// Decode the drift program `ErrorCode` from the error of a tx, if it exists
+///
+/// * `err` - the tx error
+/// * `message` - the failed tx message
+///
+/// Returns `None` if the failing instruction is not a drift program instruction
+pub fn decode_tx_error(err: &TransactionError, message: &VersionedMessage) -> Option<ErrorCode> {
+    if let TransactionError::InstructionError(ix_idx, _) = err {
+        let ix = message.instructions().get(*ix_idx as usize)?;
+        let program_id = message
+            .static_account_keys()
+            .get(ix.program_id_index as usize)?;
+        if *program_id == PROGRAM_ID {
+            return decode_program_error(err);
+        }
+    }
+    None
+}
+
+/// Convert a custom program error `code` to drift `ErrorCode`
+///
+/// Returns `None` if `code` is not a drift `ErrorCode`
+fn error_code_from_custom(code: u32) -> Option<ErrorCode> {
+    // inverse of anchor's 'From<ErrorCode> for u32', generated from the IDL
+    ErrorCode::try_from(code).ok()
+}
+
 /// Helper type for Accounts included in drift instructions
 ///
 /// Provides sorting implementation matching drift program
@@ -457,6 +495,53 @@ impl MarketPrecision for accounts::PerpMarket {
     }
 }
 
+impl accounts::State {
+    /// Max. number of sub-accounts allowed by the exchange
+    pub fn max_number_of_sub_accounts(&self) -> u64 {
+        if self.max_number_of_sub_accounts <= 5 {
+            u64::MAX
+        } else {
+            (self.max_number_of_sub_accounts as u64).saturating_mul(100)
+        }
+    }
+
+    /// Fee charged (lamports) to initialize a new sub-account
+    ///
+    /// The fee scales up to `max_initialize_user_fee` as the exchange approaches its sub-account limit
+    pub fn init_user_fee(&self) -> u64 {
+        let max_init_fee = (self.max_initialize_user_fee as u64) * LAMPORTS_PER_SOL / 100;
+        let target_utilization = 8 * PERCENTAGE_PRECISION_U64 / 10;
+        let account_space_utilization = self
+            .number_of_sub_accounts
+            .saturating_mul(PERCENTAGE_PRECISION_U64)
+            / self.max_number_of_sub_accounts().max(1);
+
+        if account_space_utilization > target_utilization {
+            max_init_fee.saturating_mul(account_space_utilization - target_utilization)
+                / (PERCENTAGE_PRECISION_U64 - target_utilization)
+        } else {
+            0
+        }
+    }
+}
+
+impl accounts::HighLeverageModeConfig {
+    /// Number of users that may still enable high leverage mode
+    pub fn remaining_slots(&self) -> u32 {
+        self.max_users.saturating_sub(self.current_users)
+    }
+
+    /// True if high leverage mode is reduce only i.e. it cannot be enabled by new users
+    pub fn is_reduce_only(&self) -> bool {
+        self.reduce_only > 0
+    }
+
+    /// True if high leverage mode can currently be enabled by a user
+    pub fn can_enable(&self) -> bool {
+        !self.is_reduce_only() && self.remaining_slots() > 0
+    }
+}
+
 #[derive(Copy, Clone)]
 pub struct ReferrerInfo {
     referrer: Pubkey,
@@ -538,11 +623,39 @@ mod tests {
         rpc_response::RpcSimulateTransactionResult,
     };
     use solana_sdk::{
-        instruction::InstructionError, pubkey::Pubkey, transaction::TransactionError,
+        instruction::{Instruction, InstructionError},
+        message::{Message, VersionedMessage},
+        native_token::LAMPORTS_PER_SOL,
+        pubkey::Pubkey,
+        transaction::TransactionError,
     };
 
-    use super::{RemainingAccount, SdkError};
-    use crate::{drift_idl::errors::ErrorCode, MarketType};
+    use super::{accounts::State, decode_tx_error, RemainingAccount, SdkError};
+    use crate::{constants::PROGRAM_ID, drift_idl::errors::ErrorCode, MarketType};
+
+    #[test]
+    fn state_sub_account_limit_and_init_fee() {
+        let mut state = State {
+            max_number_of_sub_accounts: 5,
+            max_initialize_user_fee: 100,
+            number_of_sub_accounts: 1_000_000,
+            ..Default::default()
+        };
+        // no limit
+        assert_eq!(state.max_number_of_sub_accounts(), u64::MAX);
+        assert_eq!(state.init_user_fee(), 0);
+
+        state.max_number_of_sub_accounts = 10;
+        assert_eq!(state.max_number_of_sub_accounts(), 1_000);
+        // below target utilization
+        state.number_of_sub_accounts = 800;
+        assert_eq!(state.init_user_fee(), 0);
+        // fee scales to max. at the limit
+        state.number_of_sub_accounts = 900;
+        assert_eq!(state.init_user_fee(), LAMPORTS_PER_SOL / 2);
+        state.number_of_sub_accounts = 1_000;
+        assert_eq!(state.init_user_fee(), LAMPORTS_PER_SOL);
+    }
 
     #[test]
     fn market_type_str() {
@@ -583,6 +696,39 @@ mod tests {
         );
     }
 
+    #[test]
+    fn decode_drift_program_error() {
+        let message = VersionedMessage::Legacy(Message::new(
+            &[
+                Instruction::new_with_bytes(Pubkey::new_unique(), &[], vec![]),
+                Instruction::new_with_bytes(PROGRAM_ID, &[], vec![]),
+            ],
+            None,
+        ));
+        let code = anchor_lang::error::ERROR_CODE_OFFSET + ErrorCode::InsufficientCollateral as u32;
+
+        let drift_err = TransactionError::InstructionError(1, InstructionError::Custom(code));
+        assert_eq!(
+            decode_tx_error(&drift_err, &message),
+            Some(ErrorCode::InsufficientCollateral)
+        );
+        // error from another program
+        let other_err = TransactionError::InstructionError(0, InstructionError::Custom(code));
+        assert_eq!(decode_tx_error(&other_err, &message), None);
+        // out of range codes
+        let unknown_err = TransactionError::InstructionError(1, InstructionError::Custom(u32::MAX));
+        assert_eq!(decode_tx_error(&unknown_err, &message), None);
+        let unknown_err = TransactionError::InstructionError(1, InstructionError::Custom(1));
+        assert_eq!(decode_tx_error(&unknown_err, &message), None);
+        // inverse of anchor's 'From<ErrorCode> for u32'
+        let offset = anchor_lang::error::ERROR_CODE_OFFSET;
+        for code in offset..offset + 1_000 {
+            if let Ok(error_code) = ErrorCode::try_from(code) {
+                assert_eq!(u32::from(error_code), code);
+            }
+        }
+    }
+
     #[test]
     fn account_type_sorting() {
         let mut accounts = vec![
@@ -623,4 +769,23 @@ mod tests {
             ]
         )
     }
+
+    #[test]
+    fn high_leverage_mode_slots() {
+        let mut config = crate::drift_idl::accounts::HighLeverageModeConfig {
+            max_users: 10,
+            current_users: 9,
+            ..Default::default()
+        };
+        assert_eq!(config.remaining_slots(), 1);
+        assert!(config.can_enable());
+
+        config.current_users = 11;
+        assert_eq!(config.remaining_slots(), 0);
+        assert!(!config.can_enable());
+
+        config.current_users = 0;
+        config.reduce_only = 1;
+        assert!(!config.can_enable());
+    }
 }
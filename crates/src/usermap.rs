@@ -19,9 +19,12 @@ use solana_client::{
 };
 use solana_sdk::{commitment_config::CommitmentConfig, pubkey::Pubkey};
 
+#[cfg(feature = "grpc")]
+use crate::grpc::GrpcSubscriber;
 use crate::{
     constants,
     drift_idl::accounts::User,
+    failover::RpcEndpoints,
     memcmp::{get_non_idle_user_filter, get_user_filter},
     utils::get_ws_url,
     websocket_program_account_subscriber::{
@@ -39,6 +42,9 @@ pub struct GlobalUserMap {
     latest_slot: Arc<AtomicU64>,
     commitment: CommitmentConfig,
     rpc: RpcClient,
+    /// serves the subscription from a gRPC stream, if set
+    #[cfg(feature = "grpc")]
+    grpc: Option<Arc<GrpcSubscriber>>,
 }
 
 impl GlobalUserMap {
@@ -73,14 +79,56 @@ impl GlobalUserMap {
             latest_slot: Arc::new(AtomicU64::new(0)),
             commitment,
             rpc,
+            #[cfg(feature = "grpc")]
+            grpc: None,
         }
     }
 
+    /// Sync and subscribe via the active endpoint of `endpoints`
+    ///
+    /// The Ws subscription reconnects when the active endpoint changes
+    pub fn with_failover(mut self, endpoints: Arc<RpcEndpoints>) -> Self {
+        self.rpc = endpoints.rpc_client(self.commitment);
+        self.subscription = self.subscription.with_failover(endpoints);
+        self
+    }
+
+    /// Serve the subscription from the `grpc` stream in place of Ws
+    ///
+    /// The caller is responsible for starting the stream
+    #[cfg(feature = "grpc")]
+    pub fn with_grpc(mut self, grpc: Arc<GrpcSubscriber>) -> Self {
+        self.grpc = Some(grpc);
+        self
+    }
+
     pub async fn subscribe(&self) -> SdkResult<UnsubHandle> {
         if self.sync_lock.is_some() {
             self.sync().await?;
         }
 
+        #[cfg(feature = "grpc")]
+        if let Some(ref grpc) = self.grpc {
+            let latest_slot = self.latest_slot.clone();
+            let user_map = self.usermap.clone();
+            let unsub = grpc.watch_program(
+                constants::PROGRAM_ID,
+                self.subscription.options.filters.clone(),
+                move |update| {
+                    if update.slot > latest_slot.load(Ordering::Relaxed) {
+                        latest_slot.store(update.slot, Ordering::Relaxed);
+                    }
+                    match User::deserialize(&mut &update.data[8..]) {
+                        Ok(user) => {
+                            user_map.insert(update.pubkey.to_string(), user);
+                        }
+                        Err(err) => log::warn!("invalid user account: {err:?}"),
+                    }
+                },
+            )?;
+            return Ok(unsub);
+        }
+
         let unsub = self
             .subscription
             .subscribe::<User, _>(Self::SUBSCRIPTION_ID, {
@@ -126,6 +126,16 @@ pub fn dlob_subscribe_ws_json(market: &str) -> String {
     .to_string()
 }
 
+/// Encode `name` as a fixed size, space padded drift account name
+///
+/// names longer than 32 bytes are truncated
+pub fn encode_name(name: &str) -> [u8; 32] {
+    let mut encoded = [b' '; 32];
+    let len = name.len().min(32);
+    encoded[..len].copy_from_slice(&name.as_bytes()[..len]);
+    encoded
+}
+
 pub(crate) fn zero_account_to_bytes<T: bytemuck::Pod + anchor_lang::Discriminator>(
     account: T,
 ) -> Vec<u8> {
@@ -342,6 +352,16 @@ mod tests {
         assert!(keypair.pubkey().to_string() == "EtiM5qwcrrawQP9FfRErBatNvDgEU656tk5aA8iTgqri");
     }
 
+    #[test]
+    fn test_encode_name() {
+        let name = encode_name("Main Account");
+        assert_eq!(&name[..12], b"Main Account");
+        assert!(name[12..].iter().all(|b| *b == b' '));
+
+        let long_name = "a".repeat(40);
+        assert_eq!(encode_name(&long_name), [b'a'; 32]);
+    }
+
     #[test]
     fn test_https_to_ws() {
         let https_url = "https://dlob.drift.trade";
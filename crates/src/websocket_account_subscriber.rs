@@ -1,4 +1,4 @@
-use std::str::FromStr;
+use std::{str::FromStr, sync::Arc};
 
 use futures_util::StreamExt;
 use log::warn;
@@ -10,7 +10,11 @@ use solana_client::{
 use solana_sdk::{commitment_config::CommitmentConfig, pubkey::Pubkey};
 use tokio::sync::oneshot;
 
-use crate::{utils::get_http_url, SdkError, SdkResult, UnsubHandle};
+use crate::{
+    failover::{active_changed, RpcEndpoints},
+    utils::get_http_url,
+    SdkError, SdkResult, UnsubHandle,
+};
 
 const LOG_TARGET: &str = "wsaccsub";
 
@@ -32,6 +36,8 @@ pub struct WebsocketAccountSubscriber {
     url: String,
     pub(crate) pubkey: Pubkey,
     pub(crate) commitment: CommitmentConfig,
+    /// follows the active endpoint, if set
+    failover: Option<Arc<RpcEndpoints>>,
 }
 
 impl WebsocketAccountSubscriber {
@@ -40,9 +46,16 @@ impl WebsocketAccountSubscriber {
             url,
             pubkey,
             commitment,
+            failover: None,
         }
     }
 
+    /// Connect to the active endpoint of `endpoints`, reconnecting when it changes
+    pub fn with_failover(mut self, endpoints: Arc<RpcEndpoints>) -> Self {
+        self.failover = Some(endpoints);
+        self
+    }
+
     /// Start a Ws account subscription task
     ///
     /// * `subscription_name` - some user defined identifier for the subscription
@@ -63,7 +76,10 @@ impl WebsocketAccountSubscriber {
             // seed initial account state
             log::debug!(target: LOG_TARGET, "seeding account: {subscription_name}-{:?}", self.pubkey);
             let owner: Pubkey;
-            let rpc = RpcClient::new(get_http_url(&self.url)?);
+            let rpc = match self.failover {
+                Some(ref endpoints) => endpoints.rpc_client(self.commitment),
+                None => RpcClient::new(get_http_url(&self.url)?),
+            };
             match rpc
                 .get_account_with_commitment(&self.pubkey, self.commitment)
                 .await
@@ -101,15 +117,24 @@ impl WebsocketAccountSubscriber {
         let base_delay = tokio::time::Duration::from_secs(2);
 
         let url = self.url.clone();
+        let commitment = self.commitment;
+        let failover = self.failover.clone();
+        let mut active_endpoint = failover.as_ref().map(|f| f.watch_active());
         let (unsub_tx, mut unsub_rx) = oneshot::channel::<()>();
 
         tokio::spawn({
             let mut latest_slot = 0;
+            let mut reseed = false;
             let pubkey = self.pubkey;
 
             async move {
                 log::debug!(target: LOG_TARGET, "spawn account subscriber: {subscription_name}-{pubkey:?}");
                 let exit_status = 'outer: loop {
+                    let active = failover.as_ref().map(|f| f.active());
+                    let url = failover
+                        .as_ref()
+                        .zip(active)
+                        .map_or_else(|| url.clone(), |(f, idx)| f.ws_url(idx));
                     let pubsub = match PubsubClient::new(&url).await {
                         Ok(client) => {
                             attempt = 0;
@@ -117,6 +142,14 @@ impl WebsocketAccountSubscriber {
                         }
                         Err(err) => {
                             warn!(target: LOG_TARGET, "couldn't subscribe {pubkey:?}: {err:?}, retrying...");
+                            // fail over, the next attempt connects to the new active endpoint
+                            if let Some((endpoints, idx)) = failover.as_ref().zip(active) {
+                                endpoints.mark_ws_unhealthy(idx);
+                                if let Some(active_endpoint) = active_endpoint.as_mut() {
+                                    active_endpoint.borrow_and_update();
+                                }
+                                reseed = true;
+                            }
                             attempt += 1;
                             if attempt >= max_reconnection_attempts {
                                 log::error!(
@@ -136,42 +169,78 @@ impl WebsocketAccountSubscriber {
                         .account_subscribe(&pubkey, Some(account_config.clone()))
                         .await
                     {
-                        Ok((mut account_updates, account_unsubscribe)) => loop {
-                            attempt = 0;
-                            tokio::select! {
-                                biased;
-                                message = account_updates.next() => {
-                                    match message {
-                                        Some(message) => {
-                                            let slot = message.context.slot;
+                        Ok((mut account_updates, account_unsubscribe)) => {
+                            // updates may have been missed while switching endpoints
+                            if let (true, Some(endpoints)) = (reseed, failover.as_ref()) {
+                                reseed = false;
+                                match endpoints
+                                    .rpc_client(commitment)
+                                    .get_account_with_commitment(&pubkey, commitment)
+                                    .await
+                                {
+                                    Ok(response) => {
+                                        if let Some(account) = response.value {
+                                            let slot = response.context.slot;
                                             if slot >= latest_slot {
                                                 latest_slot = slot;
-                                                if let Some(data) = message.value.data.decode() {
-                                                    let account_update = AccountUpdate {
-                                                        owner: Pubkey::from_str(&message.value.owner).unwrap(),
-                                                        lamports: message.value.lamports,
-                                                        pubkey,
-                                                        data,
-                                                        slot,
-                                                    };
-                                                    handler_fn(&account_update);
-                                                }
+                                                handler_fn(&AccountUpdate {
+                                                    owner: account.owner,
+                                                    lamports: account.lamports,
+                                                    pubkey,
+                                                    data: account.data,
+                                                    slot,
+                                                });
                                             }
                                         }
-                                        None => {
-                                            log::warn!("{}: Account stream interrupted", subscription_name);
-                                            account_unsubscribe().await;
-                                            break;
-                                        }
+                                    }
+                                    Err(err) => {
+                                        warn!(target: LOG_TARGET, "reseeding account failed: {pubkey:?}, {err:?}");
                                     }
                                 }
-                                _ = &mut unsub_rx => {
-                                    log::debug!(target: LOG_TARGET, "{}: Unsubscribing from account stream: {pubkey:?}", subscription_name);
-                                    account_unsubscribe().await;
-                                    break 'outer Ok(());
+                            }
+                            loop {
+                                attempt = 0;
+                                tokio::select! {
+                                    biased;
+                                    message = account_updates.next() => {
+                                        match message {
+                                            Some(message) => {
+                                                let slot = message.context.slot;
+                                                if slot >= latest_slot {
+                                                    latest_slot = slot;
+                                                    if let Some(data) = message.value.data.decode() {
+                                                        let account_update = AccountUpdate {
+                                                            owner: Pubkey::from_str(&message.value.owner).unwrap(),
+                                                            lamports: message.value.lamports,
+                                                            pubkey,
+                                                            data,
+                                                            slot,
+                                                        };
+                                                        handler_fn(&account_update);
+                                                    }
+                                                }
+                                            }
+                                            None => {
+                                                log::warn!("{}: Account stream interrupted", subscription_name);
+                                                account_unsubscribe().await;
+                                                break;
+                                            }
+                                        }
+                                    }
+                                    _ = &mut unsub_rx => {
+                                        log::debug!(target: LOG_TARGET, "{}: Unsubscribing from account stream: {pubkey:?}", subscription_name);
+                                        account_unsubscribe().await;
+                                        break 'outer Ok(());
+                                    }
+                                    _ = active_changed(&mut active_endpoint) => {
+                                        log::warn!("{}: endpoint changed, reconnecting", subscription_name);
+                                        account_unsubscribe().await;
+                                        reseed = true;
+                                        break;
+                                    }
                                 }
                             }
-                        },
+                        }
                         Err(_) => {
                             log::error!(
                                 "{}: Failed to subscribe to account stream, retrying",
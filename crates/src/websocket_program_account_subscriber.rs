@@ -1,4 +1,4 @@
-use std::time::Instant;
+use std::{sync::Arc, time::Instant};
 
 use anchor_lang::AnchorDeserialize;
 use futures_util::StreamExt;
@@ -14,6 +14,7 @@ use tokio::sync::oneshot;
 
 use crate::{
     constants,
+    failover::{active_changed, RpcEndpoints},
     types::{DataAndSlot, SdkError},
     UnsubHandle,
 };
@@ -45,11 +46,23 @@ pub struct WebsocketProgramAccountOptions {
 pub struct WebsocketProgramAccountSubscriber {
     url: String,
     pub(crate) options: WebsocketProgramAccountOptions,
+    /// follows the active endpoint, if set
+    failover: Option<Arc<RpcEndpoints>>,
 }
 
 impl WebsocketProgramAccountSubscriber {
     pub fn new(url: String, options: WebsocketProgramAccountOptions) -> Self {
-        WebsocketProgramAccountSubscriber { url, options }
+        WebsocketProgramAccountSubscriber {
+            url,
+            options,
+            failover: None,
+        }
+    }
+
+    /// Connect to the active endpoint of `endpoints`, reconnecting when it changes
+    pub fn with_failover(mut self, endpoints: Arc<RpcEndpoints>) -> Self {
+        self.failover = Some(endpoints);
+        self
     }
 
     /// Start a GPA subscription task
@@ -77,11 +90,37 @@ impl WebsocketProgramAccountSubscriber {
         let max_reconnection_attempts = 20;
         let base_delay = tokio::time::Duration::from_secs(5);
         let url = self.url.clone();
+        let failover = self.failover.clone();
+        let mut active_endpoint = failover.as_ref().map(|f| f.watch_active());
 
         tokio::spawn(async move {
             let mut latest_slot = 0;
             let result = 'outer: loop {
-                let pubsub = PubsubClient::new(&url).await.expect("connects");
+                let active = failover.as_ref().map(|f| f.active());
+                let url = failover
+                    .as_ref()
+                    .zip(active)
+                    .map_or_else(|| url.clone(), |(f, idx)| f.ws_url(idx));
+                let pubsub = match PubsubClient::new(&url).await {
+                    Ok(client) => client,
+                    Err(err) => {
+                        error!("{subscription_name}: couldn't connect: {err:?}, retrying");
+                        // fail over, the next attempt connects to the new active endpoint
+                        if let Some((endpoints, idx)) = failover.as_ref().zip(active) {
+                            endpoints.mark_ws_unhealthy(idx);
+                            if let Some(active_endpoint) = active_endpoint.as_mut() {
+                                active_endpoint.borrow_and_update();
+                            }
+                        }
+                        attempt += 1;
+                        if attempt >= max_reconnection_attempts {
+                            error!("Max reconnection attempts reached.");
+                            break 'outer Err(SdkError::MaxReconnectionAttemptsReached);
+                        }
+                        tokio::time::sleep(base_delay).await;
+                        continue;
+                    }
+                };
                 match pubsub
                     .program_subscribe(&constants::PROGRAM_ID, Some(config.clone()))
                     .await
@@ -122,6 +161,11 @@ impl WebsocketProgramAccountSubscriber {
                                 unsubscriber().await;
                                 break 'outer Ok(())
                             }
+                            _ = active_changed(&mut active_endpoint) => {
+                                warn!("endpoint changed, reconnecting: {subscription_name}");
+                                unsubscriber().await;
+                                break;
+                            }
                         }
                     },
                     Err(_) => {